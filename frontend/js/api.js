@@ -35,6 +35,7 @@ export const api = {
         body: JSON.stringify(data),
     }),
     getPlayerStatus: () => apiFetch('/player/status'),
+    getPlayerMap: (radius) => apiFetch(`/player/map?radius=${radius}`),
     getLocation: (id, accessLevel) => apiFetch(`/locations/${id}?access_level=${accessLevel}`),
     movePlayer: (targetLocationId) => apiFetch('/player/move', {
         method: 'POST',
@@ -58,7 +58,8 @@ export function updateLocationUI(response) {
     if (links && links.length > 0) {
         links.forEach(link => {
             const li = document.createElement('li');
-            li.innerText = link.link_text;
+            // Закрытые переходы показываем, но с пометкой: сервер все равно откажет с причиной
+            li.innerText = link.locked ? `${link.link_text} [ЗАБЛОКИРОВАНО]` : link.link_text;
             li.dataset.targetId = link.target_location_id; // Сохраняем ID цели
             li.addEventListener('click', () => handleAction(link.target_location_id));
             dom.actionList.appendChild(li);
@@ -0,0 +1,16 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_player_visits.up.sql
+
+-- История посещений: одна строка на пару (игрок, локация)
+CREATE TABLE player_visits (
+    user_id UUID NOT NULL REFERENCES players(user_id) ON DELETE CASCADE,
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    first_visited_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_visited_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    visit_count INT NOT NULL DEFAULT 1,
+    PRIMARY KEY (user_id, location_id)
+);
+
+-- Текущая локация каждого игрока считается посещенной
+INSERT INTO player_visits (user_id, location_id)
+SELECT user_id, current_location_id FROM players WHERE current_location_id IS NOT NULL;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS world_flags;
+
+ALTER TABLE location_links
+    DROP COLUMN IF EXISTS denial_message,
+    DROP COLUMN IF EXISTS access_condition;
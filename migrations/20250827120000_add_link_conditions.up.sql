@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_add_link_conditions.up.sql
+
+-- Условие перехода на языке правил (см. server/src/world/rules.rs)
+-- и сообщение, которое игрок видит при невыполненном условии
+ALTER TABLE location_links
+    ADD COLUMN access_condition TEXT,
+    ADD COLUMN denial_message TEXT;
+
+-- Глобальные флаги мира, на которые могут ссылаться условия
+CREATE TABLE world_flags (
+    key VARCHAR(255) PRIMARY KEY,
+    value JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON world_flags
+FOR EACH ROW
+EXECUTE FUNCTION trigger_set_timestamp();
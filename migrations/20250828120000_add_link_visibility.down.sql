@@ -0,0 +1,9 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS player_discovered_links;
+
+ALTER TABLE location_links
+    DROP COLUMN IF EXISTS reveal_condition,
+    DROP COLUMN IF EXISTS search_chance,
+    DROP COLUMN IF EXISTS visibility;
+
+DROP TYPE IF EXISTS link_visibility;
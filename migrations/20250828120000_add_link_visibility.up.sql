@@ -0,0 +1,21 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_add_link_visibility.up.sql
+
+-- Visible - виден всем; Hidden - виден после обнаружения; Secret - никогда не показывается,
+-- но им можно воспользоваться, если знать, куда он ведет
+CREATE TYPE link_visibility AS ENUM ('Visible', 'Hidden', 'Secret');
+
+ALTER TABLE location_links
+    ADD COLUMN visibility link_visibility NOT NULL DEFAULT 'Visible',
+    -- Шанс (в процентах) найти скрытый переход одной командой поиска
+    ADD COLUMN search_chance INT NOT NULL DEFAULT 100 CHECK (search_chance BETWEEN 0 AND 100),
+    -- Условие на языке правил, при котором скрытый переход обнаруживается сам (например, "has item scanner")
+    ADD COLUMN reveal_condition TEXT;
+
+-- Какие скрытые переходы обнаружил каждый игрок
+CREATE TABLE player_discovered_links (
+    user_id UUID NOT NULL REFERENCES players(user_id) ON DELETE CASCADE,
+    link_id UUID NOT NULL REFERENCES location_links(id) ON DELETE CASCADE,
+    discovered_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, link_id)
+);
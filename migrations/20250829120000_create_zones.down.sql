@@ -0,0 +1,14 @@
+-- Add down migration script here
+UPDATE locations SET security_level = 0 WHERE security_level IS NULL;
+
+ALTER TABLE locations
+    ALTER COLUMN security_level SET DEFAULT 0,
+    ALTER COLUMN security_level SET NOT NULL;
+
+ALTER TABLE locations
+    DROP COLUMN IF EXISTS access_condition,
+    DROP COLUMN IF EXISTS ambient_description,
+    DROP COLUMN IF EXISTS zone_id;
+
+DROP TABLE IF EXISTS zones;
+DROP TYPE IF EXISTS zone_kind;
@@ -0,0 +1,45 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_zones.up.sql
+
+-- Иерархия фиксированной глубины: сектор > зона > локация
+CREATE TYPE zone_kind AS ENUM ('Sector', 'Zone');
+
+CREATE TABLE zones (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    parent_id UUID REFERENCES zones(id) ON DELETE RESTRICT,
+    kind zone_kind NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    -- Значения по умолчанию для вложенных локаций; NULL - взять у родителя
+    security_level INT,
+    ambient_description TEXT,
+    access_condition TEXT,
+    locked_down BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- У сектора нет родителя, у зоны он обязателен
+    CHECK ((kind = 'Sector') = (parent_id IS NULL))
+);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON zones
+FOR EACH ROW
+EXECUTE FUNCTION trigger_set_timestamp();
+
+-- Стартовый сектор и зона, в которые попадают все существующие локации
+INSERT INTO zones (id, parent_id, kind, name, security_level) VALUES
+('5ec70000-0000-4000-8000-000000000001', NULL, 'Sector', 'СЕКТОР 0', 0),
+('5ec70000-0000-4000-8000-000000000002', '5ec70000-0000-4000-8000-000000000001', 'Zone', 'ВХОДНОЙ КОНТУР', NULL);
+
+ALTER TABLE locations
+    ADD COLUMN zone_id UUID REFERENCES zones(id) ON DELETE RESTRICT,
+    ADD COLUMN ambient_description TEXT,
+    ADD COLUMN access_condition TEXT;
+
+UPDATE locations SET zone_id = '5ec70000-0000-4000-8000-000000000002';
+
+ALTER TABLE locations ALTER COLUMN zone_id SET NOT NULL;
+
+-- NULL в security_level теперь означает "наследовать от зоны"
+ALTER TABLE locations
+    ALTER COLUMN security_level DROP NOT NULL,
+    ALTER COLUMN security_level DROP DEFAULT;
@@ -0,0 +1,9 @@
+-- Add down migration script here
+ALTER TABLE players DROP COLUMN IF EXISTS current_instance_id;
+
+DROP TABLE IF EXISTS location_instances;
+
+ALTER TABLE locations DROP COLUMN IF EXISTS instanced;
+
+DROP TABLE IF EXISTS party_members;
+DROP TABLE IF EXISTS parties;
@@ -0,0 +1,38 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_instances.up.sql
+
+-- Группы игроков; один игрок состоит максимум в одной группе
+CREATE TABLE parties (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    leader_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE party_members (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    party_id UUID NOT NULL REFERENCES parties(id) ON DELETE CASCADE,
+    joined_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Инстанцированная локация получает отдельную копию на каждую группу или одиночного игрока
+ALTER TABLE locations ADD COLUMN instanced BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE location_instances (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    -- Владелец копии: либо группа, либо одиночный игрок
+    party_id UUID REFERENCES parties(id) ON DELETE CASCADE,
+    owner_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    -- Локальное состояние копии (двери, рычаги); перекрывает флаги мира
+    state JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- Когда копия опустела; NULL - внутри кто-то есть
+    emptied_at TIMESTAMPTZ,
+    CHECK ((party_id IS NULL) <> (owner_id IS NULL))
+);
+
+CREATE UNIQUE INDEX location_instances_party_idx ON location_instances (location_id, party_id) WHERE party_id IS NOT NULL;
+CREATE UNIQUE INDEX location_instances_owner_idx ON location_instances (location_id, owner_id) WHERE owner_id IS NOT NULL;
+
+ALTER TABLE players
+    ADD COLUMN current_instance_id UUID REFERENCES location_instances(id) ON DELETE SET NULL;
@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP TRIGGER IF EXISTS asset_ref_count ON locations;
+DROP FUNCTION IF EXISTS trigger_asset_ref_count();
+
+ALTER TABLE locations DROP COLUMN IF EXISTS image_asset_id;
+
+DROP TABLE IF EXISTS assets;
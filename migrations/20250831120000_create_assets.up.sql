@@ -0,0 +1,38 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_assets.up.sql
+
+-- Загруженные изображения. Файлы лежат на диске под именем <content_hash>.<extension>
+CREATE TABLE assets (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    content_hash CHAR(64) UNIQUE NOT NULL,
+    mime_type VARCHAR(64) NOT NULL,
+    extension VARCHAR(8) NOT NULL,
+    byte_size INT NOT NULL,
+    width INT NOT NULL,
+    height INT NOT NULL,
+    -- Сколько локаций ссылается на изображение; поддерживается триггером ниже
+    ref_count INT NOT NULL DEFAULT 0 CHECK (ref_count >= 0),
+    uploaded_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE locations ADD COLUMN image_asset_id UUID REFERENCES assets(id) ON DELETE SET NULL;
+
+-- Счетчик ссылок обновляется при любом изменении локаций, в том числе при удалении
+CREATE OR REPLACE FUNCTION trigger_asset_ref_count()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') AND OLD.image_asset_id IS NOT NULL THEN
+        UPDATE assets SET ref_count = ref_count - 1 WHERE id = OLD.image_asset_id;
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') AND NEW.image_asset_id IS NOT NULL THEN
+        UPDATE assets SET ref_count = ref_count + 1 WHERE id = NEW.image_asset_id;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER asset_ref_count
+AFTER INSERT OR DELETE OR UPDATE OF image_asset_id ON locations
+FOR EACH ROW
+EXECUTE FUNCTION trigger_asset_ref_count();
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE users DROP COLUMN IF EXISTS preferred_language;
+
+DROP TABLE IF EXISTS link_translations;
+DROP TABLE IF EXISTS location_translations;
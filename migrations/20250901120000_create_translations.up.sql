@@ -0,0 +1,25 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_translations.up.sql
+
+-- Основной текст локаций и переходов хранится на языке по умолчанию (DEFAULT_LANGUAGE),
+-- здесь лежат переводы на остальные языки
+CREATE TABLE location_translations (
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    language VARCHAR(8) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT NOT NULL,
+    ambient_description TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (location_id, language)
+);
+
+CREATE TABLE link_translations (
+    link_id UUID NOT NULL REFERENCES location_links(id) ON DELETE CASCADE,
+    language VARCHAR(8) NOT NULL,
+    link_text VARCHAR(255) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (link_id, language)
+);
+
+-- Язык, выбранный игроком; NULL - определять по заголовку Accept-Language
+ALTER TABLE users ADD COLUMN preferred_language VARCHAR(8);
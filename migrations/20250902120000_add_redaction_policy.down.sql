@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE locations DROP COLUMN IF EXISTS redaction_leaks;
@@ -0,0 +1,6 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_add_redaction_policy.up.sql
+
+-- Какие поля локации могут частично "просочиться" игроку с недостаточным уровнем доступа.
+-- Допустимые значения: name, description, ambient_description, image
+ALTER TABLE locations ADD COLUMN redaction_leaks TEXT[] NOT NULL DEFAULT '{name,description}';
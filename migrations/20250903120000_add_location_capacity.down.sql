@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS location_queue;
+
+ALTER TABLE locations DROP COLUMN IF EXISTS capacity;
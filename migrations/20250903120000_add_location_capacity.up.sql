@@ -0,0 +1,16 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_add_location_capacity.up.sql
+
+-- Максимальное число игроков в локации; NULL - без ограничений
+ALTER TABLE locations ADD COLUMN capacity INT CHECK (capacity > 0);
+
+-- Очередь на вход в заполненную локацию. Игрок стоит максимум в одной очереди.
+-- Когда место освобождается, первому в очереди выдается бронь до reserved_until.
+CREATE TABLE location_queue (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    enqueued_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    reserved_until TIMESTAMPTZ
+);
+
+CREATE INDEX idx_location_queue_order ON location_queue (location_id, enqueued_at);
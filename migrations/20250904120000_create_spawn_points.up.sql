@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_spawn_points.up.sql
+
+-- Точки появления новых игроков (и тех, чья локация была удалена).
+-- Точка выбирается случайно с учетом веса; точки с заданной ролью
+-- используются только для этой роли и имеют приоритет над общими (role IS NULL).
+CREATE TABLE spawn_points (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    weight INT NOT NULL DEFAULT 1 CHECK (weight > 0),
+    role user_role,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Бывшая захардкоженная стартовая локация становится общей точкой появления
+INSERT INTO spawn_points (location_id)
+SELECT id FROM locations WHERE id = 'a1b2c3d4-e5f6-7890-1234-567890abcdef';
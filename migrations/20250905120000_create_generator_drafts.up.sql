@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_generator_drafts.up.sql
+
+-- Черновики процедурного генератора: параметры и получившийся кластер,
+-- который Архитектор просматривает перед тем, как создать локации
+CREATE TABLE generator_drafts (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    created_by UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    params JSONB NOT NULL,
+    cluster JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,11 @@
+-- Add down migration script here
+ALTER TABLE players ADD COLUMN inventory JSONB;
+
+UPDATE players p
+SET inventory = (
+    SELECT jsonb_object_agg(item_id, quantity)
+    FROM (SELECT item_id, SUM(quantity) AS quantity FROM player_items WHERE user_id = p.user_id GROUP BY item_id) i
+);
+
+DROP TABLE IF EXISTS player_items;
+DROP TABLE IF EXISTS item_definitions;
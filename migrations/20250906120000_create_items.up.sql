@@ -0,0 +1,69 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_items.up.sql
+
+-- Описания предметов. Идентификатор - читаемый код ("keycard_red"),
+-- на него ссылаются условия доступа (`has keycard_red`).
+CREATE TABLE item_definitions (
+    id VARCHAR(255) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    stackable BOOLEAN NOT NULL DEFAULT FALSE,
+    weight REAL NOT NULL DEFAULT 0 CHECK (weight >= 0),
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    -- Расходуемый предмет исчезает (одна штука) при использовании
+    consumable BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Что видит игрок при использовании; NULL - предмет нельзя использовать
+    use_message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON item_definitions
+FOR EACH ROW
+EXECUTE FUNCTION trigger_set_timestamp();
+
+-- Экземпляры предметов у игроков. Складываемые предметы лежат одной строкой
+-- с количеством (stacked), остальные - по строке на каждую штуку.
+-- Флаг копируется из описания, чтобы уникальность стопки держал индекс.
+CREATE TABLE player_items (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    item_id VARCHAR(255) NOT NULL REFERENCES item_definitions(id) ON DELETE CASCADE,
+    quantity INT NOT NULL DEFAULT 1 CHECK (quantity > 0),
+    stacked BOOLEAN NOT NULL DEFAULT FALSE,
+    acquired_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (stacked OR quantity = 1)
+);
+
+CREATE INDEX idx_player_items_user ON player_items (user_id);
+CREATE UNIQUE INDEX idx_player_items_stack ON player_items (user_id, item_id) WHERE stacked;
+
+-- Перенос сырого JSONB: массив кодов (["keycard_red"]) или объект {"keycard_red": 2}.
+-- Неизвестные коды получают описания-заглушки, которые Архитекторы потом дополнят.
+CREATE TEMPORARY TABLE legacy_inventory AS
+SELECT user_id, item_id, SUM(quantity)::INT AS quantity
+FROM (
+    SELECT p.user_id, e.value #>> '{}' AS item_id, 1 AS quantity
+    FROM players p,
+         jsonb_array_elements(CASE WHEN jsonb_typeof(p.inventory) = 'array' THEN p.inventory ELSE '[]' END) e
+    WHERE jsonb_typeof(e.value) = 'string'
+    UNION ALL
+    SELECT p.user_id, e.key,
+           CASE WHEN jsonb_typeof(e.value) = 'number' THEN (e.value #>> '{}')::NUMERIC::INT ELSE 1 END
+    FROM players p,
+         jsonb_each(CASE WHEN jsonb_typeof(p.inventory) = 'object' THEN p.inventory ELSE '{}' END) e
+) raw
+WHERE item_id <> ''
+GROUP BY user_id, item_id
+HAVING SUM(quantity) > 0;
+
+INSERT INTO item_definitions (id, name, stackable)
+SELECT DISTINCT item_id, item_id, TRUE FROM legacy_inventory;
+
+INSERT INTO player_items (user_id, item_id, quantity, stacked)
+SELECT user_id, item_id, quantity, TRUE FROM legacy_inventory;
+
+DROP TABLE legacy_inventory;
+
+ALTER TABLE players DROP COLUMN inventory;
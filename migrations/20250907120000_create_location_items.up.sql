@@ -0,0 +1,25 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_location_items.up.sql
+
+-- Предметы, лежащие в локациях: разложенные Архитекторами или выброшенные игроками.
+-- В копии инстанцированной локации свои предметы (instance_id); при создании
+-- копия получает то, что Архитекторы разложили в самой локации.
+CREATE TABLE location_items (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    instance_id UUID REFERENCES location_instances(id) ON DELETE CASCADE,
+    item_id VARCHAR(255) NOT NULL REFERENCES item_definitions(id) ON DELETE CASCADE,
+    quantity INT NOT NULL DEFAULT 1 CHECK (quantity > 0),
+    stacked BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Кто положил предмет; NULL - пользователь удален
+    placed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    placed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (stacked OR quantity = 1)
+);
+
+CREATE INDEX idx_location_items_location ON location_items (location_id, instance_id);
+-- Одна стопка предмета на локацию (или на копию)
+CREATE UNIQUE INDEX idx_location_items_stack ON location_items (location_id, item_id)
+    WHERE stacked AND instance_id IS NULL;
+CREATE UNIQUE INDEX idx_location_items_instance_stack ON location_items (instance_id, item_id)
+    WHERE stacked AND instance_id IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS trade_items;
+DROP TABLE IF EXISTS trade_offers;
+DROP TABLE IF EXISTS trades;
+ALTER TABLE players DROP COLUMN IF EXISTS credits;
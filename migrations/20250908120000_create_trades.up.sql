@@ -0,0 +1,38 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_trades.up.sql
+
+-- Валюта игроков
+ALTER TABLE players ADD COLUMN credits BIGINT NOT NULL DEFAULT 0 CHECK (credits >= 0);
+
+-- Незавершенные сделки между двумя игроками в одной комнате.
+-- Завершенные и отмененные сделки удаляются, поэтому ссылок на локацию нет:
+-- удаление локации не должно уносить с собой вещи из залога.
+CREATE TABLE trades (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    location_id UUID NOT NULL,
+    instance_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Сторона сделки. Игрок участвует не более чем в одной сделке.
+CREATE TABLE trade_offers (
+    trade_id UUID NOT NULL REFERENCES trades(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL UNIQUE REFERENCES users(id) ON DELETE CASCADE,
+    initiator BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Кредиты в залоге: уже списаны с игрока
+    credits BIGINT NOT NULL DEFAULT 0 CHECK (credits >= 0),
+    confirmed BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (trade_id, user_id)
+);
+
+-- Предметы в залоге: изъяты из инвентаря на время сделки
+CREATE TABLE trade_items (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    trade_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    item_id VARCHAR(255) NOT NULL REFERENCES item_definitions(id) ON DELETE CASCADE,
+    quantity INT NOT NULL CHECK (quantity > 0),
+    FOREIGN KEY (trade_id, user_id) REFERENCES trade_offers(trade_id, user_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_trade_items_trade ON trade_items (trade_id);
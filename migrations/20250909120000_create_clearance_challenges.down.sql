@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS clearance_audit;
+DROP TABLE IF EXISTS clearance_challenges;
+DROP TYPE IF EXISTS challenge_kind;
@@ -0,0 +1,42 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_clearance_challenges.up.sql
+
+CREATE TYPE challenge_kind AS ENUM ('Riddle', 'Item');
+
+-- Испытания, за прохождение которых игрок получает уровень доступа.
+-- Riddle - ответ на загадку терминала, Item - предъявление предмета.
+CREATE TABLE clearance_challenges (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    title VARCHAR(255) NOT NULL,
+    prompt TEXT NOT NULL,
+    kind challenge_kind NOT NULL,
+    -- Для Riddle: правильный ответ (сравнивается без учета регистра и лишних пробелов)
+    answer TEXT,
+    -- Для Item: какой предмет предъявить и забирается ли он
+    required_item_id VARCHAR(255) REFERENCES item_definitions(id) ON DELETE SET NULL,
+    consume_item BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Проходить испытание можно с этого уровня; повышает до grants_level
+    min_access_level INT NOT NULL DEFAULT 0,
+    grants_level INT NOT NULL,
+    creator_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (grants_level > min_access_level),
+    CHECK (kind <> 'Riddle' OR answer IS NOT NULL)
+);
+
+CREATE INDEX idx_clearance_challenges_location ON clearance_challenges (location_id);
+
+-- Журнал изменений уровня доступа: испытания и ручные назначения Архитекторов
+CREATE TABLE clearance_audit (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    old_level INT NOT NULL,
+    new_level INT NOT NULL,
+    challenge_id UUID REFERENCES clearance_challenges(id) ON DELETE SET NULL,
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_clearance_audit_user ON clearance_audit (user_id, created_at);
@@ -0,0 +1,7 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS player_objectives;
+DROP TABLE IF EXISTS player_quests;
+DROP TABLE IF EXISTS quest_objectives;
+DROP TABLE IF EXISTS quest_reward_items;
+DROP TABLE IF EXISTS quests;
+DROP TYPE IF EXISTS objective_kind;
@@ -0,0 +1,66 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_quests.up.sql
+
+CREATE TYPE objective_kind AS ENUM ('VisitLocation', 'ObtainItem', 'TalkToNpc', 'ReachAccessLevel');
+
+CREATE TABLE quests (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    title VARCHAR(255) NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    -- Награды: уровень доступа (только повышение) и флаги мира {"key": value}
+    reward_access_level INT,
+    reward_flags JSONB NOT NULL DEFAULT '{}',
+    creator_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON quests
+FOR EACH ROW
+EXECUTE FUNCTION trigger_set_timestamp();
+
+CREATE TABLE quest_reward_items (
+    quest_id UUID NOT NULL REFERENCES quests(id) ON DELETE CASCADE,
+    item_id VARCHAR(255) NOT NULL REFERENCES item_definitions(id) ON DELETE CASCADE,
+    quantity INT NOT NULL DEFAULT 1 CHECK (quantity > 0),
+    PRIMARY KEY (quest_id, item_id)
+);
+
+-- Цели задания. Выполняются в любом порядке; задание завершено, когда выполнены все.
+-- Заполнено только поле, соответствующее виду цели.
+CREATE TABLE quest_objectives (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    quest_id UUID NOT NULL REFERENCES quests(id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    kind objective_kind NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    location_id UUID REFERENCES locations(id) ON DELETE CASCADE,
+    item_id VARCHAR(255) REFERENCES item_definitions(id) ON DELETE CASCADE,
+    quantity INT NOT NULL DEFAULT 1 CHECK (quantity > 0),
+    npc_id UUID,
+    access_level INT,
+    CHECK (kind <> 'VisitLocation' OR location_id IS NOT NULL),
+    CHECK (kind <> 'ObtainItem' OR item_id IS NOT NULL),
+    CHECK (kind <> 'TalkToNpc' OR npc_id IS NOT NULL),
+    CHECK (kind <> 'ReachAccessLevel' OR access_level IS NOT NULL)
+);
+
+CREATE INDEX idx_quest_objectives_quest ON quest_objectives (quest_id, position);
+
+-- Взятые игроками задания; completed_at IS NULL - задание активно
+CREATE TABLE player_quests (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    quest_id UUID NOT NULL REFERENCES quests(id) ON DELETE CASCADE,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ,
+    PRIMARY KEY (user_id, quest_id)
+);
+
+-- Выполненные игроком цели
+CREATE TABLE player_objectives (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    objective_id UUID NOT NULL REFERENCES quest_objectives(id) ON DELETE CASCADE,
+    completed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, objective_id)
+);
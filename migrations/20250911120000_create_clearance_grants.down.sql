@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE clearance_challenges DROP COLUMN IF EXISTS grant_duration_secs;
+DROP TABLE IF EXISTS clearance_grants;
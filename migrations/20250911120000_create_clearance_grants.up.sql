@@ -0,0 +1,25 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_clearance_grants.up.sql
+
+-- Временные допуски: уровень доступа на время или доступ к одной локации до срока.
+-- Истекшие записи остаются в таблице как журнал; processed отмечает, что истечение
+-- уже обработано (игрок выведен из закрытой для него локации).
+CREATE TABLE clearance_grants (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    access_level INT,
+    location_id UUID REFERENCES locations(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    processed BOOLEAN NOT NULL DEFAULT FALSE,
+    -- NULL - выдан игровым событием
+    granted_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK ((access_level IS NULL) <> (location_id IS NULL))
+);
+
+CREATE INDEX idx_clearance_grants_active ON clearance_grants (user_id, expires_at);
+CREATE INDEX idx_clearance_grants_pending ON clearance_grants (expires_at) WHERE NOT processed;
+
+-- Испытание может выдавать временный допуск вместо постоянного повышения
+ALTER TABLE clearance_challenges ADD COLUMN grant_duration_secs INT CHECK (grant_duration_secs > 0);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE clearance_grants DROP CONSTRAINT IF EXISTS clearance_grants_single_use_location;
+ALTER TABLE clearance_grants DROP COLUMN IF EXISTS single_use;
+DROP TABLE IF EXISTS access_requests;
+DROP TYPE IF EXISTS access_request_status;
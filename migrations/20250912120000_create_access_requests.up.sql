@@ -0,0 +1,31 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_access_requests.up.sql
+
+CREATE TYPE access_request_status AS ENUM ('Pending', 'Approved', 'Denied', 'Cancelled');
+
+-- Запросы игроков на доступ в закрытую локацию. Решает создатель локации,
+-- а если его нет - любой модератор. Записи не удаляются: это история запросов.
+CREATE TABLE access_requests (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    message TEXT NOT NULL DEFAULT '',
+    status access_request_status NOT NULL DEFAULT 'Pending',
+    -- NULL - запрос адресован модераторам
+    approver_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    decided_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    decision_note TEXT,
+    -- Пропуск, выданный при одобрении
+    grant_id UUID REFERENCES clearance_grants(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    decided_at TIMESTAMPTZ
+);
+
+-- Не больше одного нерассмотренного запроса игрока на локацию
+CREATE UNIQUE INDEX idx_access_requests_pending ON access_requests (user_id, location_id) WHERE status = 'Pending';
+CREATE INDEX idx_access_requests_approver ON access_requests (approver_id, created_at);
+
+-- Разовый пропуск: действует на один вход и сгорает, когда игрок покидает локацию
+ALTER TABLE clearance_grants ADD COLUMN single_use BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE clearance_grants ADD CONSTRAINT clearance_grants_single_use_location
+    CHECK (NOT single_use OR location_id IS NOT NULL);
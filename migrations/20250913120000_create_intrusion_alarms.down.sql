@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE players DROP COLUMN IF EXISTS movement_locked_until;
+DROP TABLE IF EXISTS zone_alarms;
+DROP TABLE IF EXISTS intrusion_attempts;
+DROP TABLE IF EXISTS intrusion_settings;
@@ -0,0 +1,45 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_intrusion_alarms.up.sql
+
+-- Пороги системы обнаружения вторжений для локации. NULL - значение по умолчанию из конфигурации.
+CREATE TABLE intrusion_settings (
+    location_id UUID PRIMARY KEY REFERENCES locations(id) ON DELETE CASCADE,
+    -- За какой период считаются попытки
+    window_secs INT CHECK (window_secs > 0),
+    -- После скольких попыток игрока его перемещения блокируются и на сколько (дальше время удваивается)
+    cooldown_after INT CHECK (cooldown_after > 0),
+    cooldown_secs INT CHECK (cooldown_secs > 0),
+    -- После скольких попыток всех игроков в зоне объявляется тревога и сколько она длится
+    alarm_after INT CHECK (alarm_after > 0),
+    alarm_secs INT CHECK (alarm_secs > 0)
+);
+
+-- Попытки войти в локацию без достаточного уровня доступа
+CREATE TABLE intrusion_attempts (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    required_level INT NOT NULL,
+    access_level INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_intrusion_attempts_location ON intrusion_attempts (location_id, created_at);
+CREATE INDEX idx_intrusion_attempts_user ON intrusion_attempts (user_id, created_at);
+
+CREATE TABLE zone_alarms (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    zone_id UUID NOT NULL REFERENCES zones(id) ON DELETE CASCADE,
+    -- Где была попытка, поднявшая тревогу
+    location_id UUID REFERENCES locations(id) ON DELETE SET NULL,
+    triggered_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    raised_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    -- Кто снял тревогу досрочно
+    silenced_by UUID REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_zone_alarms_zone ON zone_alarms (zone_id, expires_at);
+
+-- До этого момента перемещения игрока заблокированы системой безопасности
+ALTER TABLE players ADD COLUMN movement_locked_until TIMESTAMPTZ;
@@ -0,0 +1,8 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS location_acl;
+ALTER TABLE locations DROP COLUMN IF EXISTS public_hub;
+ALTER TABLE locations DROP COLUMN IF EXISTS personal;
+ALTER TABLE locations DROP COLUMN IF EXISTS entry_policy;
+ALTER TABLE locations DROP COLUMN IF EXISTS owner_id;
+DROP TYPE IF EXISTS acl_entry_kind;
+DROP TYPE IF EXISTS entry_policy;
@@ -0,0 +1,24 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_location_acl.up.sql
+
+-- DenyList - входить могут все, кроме запрещенных; AllowList - только разрешенные
+CREATE TYPE entry_policy AS ENUM ('DenyList', 'AllowList');
+CREATE TYPE acl_entry_kind AS ENUM ('Editor', 'Allow', 'Deny');
+
+ALTER TABLE locations ADD COLUMN owner_id UUID REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE locations ADD COLUMN entry_policy entry_policy NOT NULL DEFAULT 'DenyList';
+-- Личная комната, созданная игроком (а не Архитектором)
+ALTER TABLE locations ADD COLUMN personal BOOLEAN NOT NULL DEFAULT FALSE;
+-- К публичным узлам игроки могут привязывать свои комнаты
+ALTER TABLE locations ADD COLUMN public_hub BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX idx_locations_owner ON locations (owner_id) WHERE owner_id IS NOT NULL;
+
+CREATE TABLE location_acl (
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind acl_entry_kind NOT NULL,
+    added_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (location_id, user_id, kind)
+);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS player_transits;
+ALTER TABLE location_links DROP COLUMN IF EXISTS travel_secs;
@@ -0,0 +1,19 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_player_transits.up.sql
+
+-- Сколько секунд занимает переход по связи; 0 - мгновенно
+ALTER TABLE location_links ADD COLUMN travel_secs INT NOT NULL DEFAULT 0 CHECK (travel_secs >= 0);
+
+-- Игроки в пути. Пока переход не завершен, players.current_location_id указывает на исходную локацию
+CREATE TABLE player_transits (
+    user_id UUID PRIMARY KEY REFERENCES players(user_id) ON DELETE CASCADE,
+    link_id UUID REFERENCES location_links(id) ON DELETE SET NULL,
+    source_location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    target_location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    -- Встать в очередь, если к прибытию цель окажется заполнена
+    queue BOOLEAN NOT NULL DEFAULT FALSE,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    arrives_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_player_transits_arrives_at ON player_transits (arrives_at);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS npc_conversations;
+DROP TABLE IF EXISTS npcs;
+ALTER TABLE players DROP COLUMN IF EXISTS flags;
@@ -0,0 +1,36 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_npcs.up.sql
+
+-- Флаги игрока (например, выставленные в разговоре с NPC). В условиях перекрывают флаги мира
+ALTER TABLE players ADD COLUMN flags JSONB NOT NULL DEFAULT '{}';
+
+-- Персонажи, которых Архитекторы расставляют по локациям. Диалог - дерево узлов
+-- в формате models::npc::Dialogue, проверяется при сохранении.
+CREATE TABLE npcs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    dialogue JSONB NOT NULL,
+    creator_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_npcs_location ON npcs (location_id);
+
+CREATE TRIGGER set_timestamp
+BEFORE UPDATE ON npcs
+FOR EACH ROW
+EXECUTE FUNCTION trigger_set_timestamp();
+
+-- Где игрок остановился в разговоре с NPC. node_id NULL - разговор окончен,
+-- следующий начнется с начала дерева.
+CREATE TABLE npc_conversations (
+    user_id UUID NOT NULL REFERENCES players(user_id) ON DELETE CASCADE,
+    npc_id UUID NOT NULL REFERENCES npcs(id) ON DELETE CASCADE,
+    node_id TEXT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, npc_id)
+);
@@ -0,0 +1,15 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_challenge_attempts.up.sql
+
+-- Неверные ответы игрока на загадку испытания. Пороги и рост блокировки те же,
+-- что у системы обнаружения вторжений для локации испытания.
+CREATE TABLE challenge_attempts (
+    user_id UUID NOT NULL REFERENCES players(user_id) ON DELETE CASCADE,
+    challenge_id UUID NOT NULL REFERENCES clearance_challenges(id) ON DELETE CASCADE,
+    -- Неверные ответы подряд за окно intrusion window_secs
+    failures INT NOT NULL DEFAULT 0,
+    last_failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- До этого момента новые ответы не принимаются
+    locked_until TIMESTAMPTZ,
+    PRIMARY KEY (user_id, challenge_id)
+);
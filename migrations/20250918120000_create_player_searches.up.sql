@@ -0,0 +1,10 @@
+-- Add up migration script here
+-- /migrations/TIMESTAMP_create_player_searches.up.sql
+
+-- Когда игрок последний раз обыскивал локацию: повторный поиск возможен только после паузы
+CREATE TABLE player_searches (
+    user_id UUID NOT NULL REFERENCES players(user_id) ON DELETE CASCADE,
+    location_id UUID NOT NULL REFERENCES locations(id) ON DELETE CASCADE,
+    searched_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, location_id)
+);
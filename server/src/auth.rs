@@ -34,4 +34,13 @@ pub async fn auth_middleware(
     req.extensions_mut().insert(claims);
 
     Ok(next.run(req).await)
-}
\ No newline at end of file
+}
+
+/// Проверяет, что роль пользователя не ниже требуемой (например, Architect и выше).
+pub fn require_role(claims: &Claims, role: UserRole) -> Result<(), AppError> {
+    if claims.role >= role {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden)
+    }
+}
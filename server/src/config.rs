@@ -1,17 +1,75 @@
 // /var/w ww/structure/server/src/config.rs
 use std::env;
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
+    // Сколько секунд пустая копия инстанцированной локации живет до удаления
+    pub instance_empty_timeout_secs: u64,
+    // Сколько секунд первый в очереди может занять освободившееся место в локации
+    pub queue_reservation_secs: u64,
+    // Каталог для загруженных изображений и предельный размер одного файла
+    pub asset_dir: String,
+    pub asset_max_bytes: usize,
+    // Картинка-заглушка для локаций, к которым у игрока нет доступа
+    pub scrambled_image_url: String,
+    // Куда помещать игроков, если ни одной точки появления не настроено
+    pub fallback_spawn_location_id: Option<Uuid>,
+    // Куда выводить игрока, у которого истек временный допуск к его локации (иначе - в точку появления)
+    pub safe_location_id: Option<Uuid>,
+    // Пороги обнаружения вторжений по умолчанию (локация может задать свои, см. world::intrusion)
+    pub intrusion_window_secs: i32,
+    pub intrusion_cooldown_after: i32,
+    pub intrusion_cooldown_secs: i32,
+    pub intrusion_alarm_after: i32,
+    pub intrusion_alarm_secs: i32,
+    // Сколько секунд игрок ждет перед повторным поиском в той же локации
+    pub search_cooldown_secs: u64,
+    // Сколько личных комнат может создать один игрок
+    pub max_personal_rooms: i64,
+    // Язык основного текста локаций и список языков, на которые его можно перевести
+    pub default_language: String,
+    pub supported_languages: Vec<String>,
 }
 
 impl Config {
     pub fn from_env() -> Self {
+        let default_language: String = env_or("DEFAULT_LANGUAGE", "ru".to_string()).to_ascii_lowercase();
+        let mut supported_languages: Vec<String> = env_or("SUPPORTED_LANGUAGES", "ru,en".to_string())
+            .split(',')
+            .map(|language| language.trim().to_ascii_lowercase())
+            .filter(|language| !language.is_empty())
+            .collect();
+        if !supported_languages.contains(&default_language) {
+            supported_languages.insert(0, default_language.clone());
+        }
+
         Self {
             database_url: env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
             jwt_secret: env::var("JWT_SECRET").expect("JWT_SECRET must be set"),
+            instance_empty_timeout_secs: env_or("INSTANCE_EMPTY_TIMEOUT_SECS", 300),
+            queue_reservation_secs: env_or("QUEUE_RESERVATION_SECS", 30),
+            asset_dir: env_or("ASSET_DIR", "assets".to_string()),
+            asset_max_bytes: env_or("ASSET_MAX_BYTES", 5 * 1024 * 1024),
+            scrambled_image_url: env_or("SCRAMBLED_IMAGE_URL", "/static/images/scrambled.gif".to_string()),
+            fallback_spawn_location_id: env::var("FALLBACK_SPAWN_LOCATION").ok().and_then(|id| id.parse().ok()),
+            safe_location_id: env::var("SAFE_LOCATION").ok().and_then(|id| id.parse().ok()),
+            intrusion_window_secs: env_or("INTRUSION_WINDOW_SECS", 300),
+            intrusion_cooldown_after: env_or("INTRUSION_COOLDOWN_AFTER", 2),
+            intrusion_cooldown_secs: env_or("INTRUSION_COOLDOWN_SECS", 10),
+            intrusion_alarm_after: env_or("INTRUSION_ALARM_AFTER", 3),
+            intrusion_alarm_secs: env_or("INTRUSION_ALARM_SECS", 300),
+            search_cooldown_secs: env_or("SEARCH_COOLDOWN_SECS", 30),
+            max_personal_rooms: env_or("MAX_PERSONAL_ROOMS", 3),
+            default_language,
+            supported_languages,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Необязательная переменная окружения с значением по умолчанию.
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
@@ -11,8 +11,13 @@ pub enum AppError {
     SqlxError(sqlx::Error),
     PasswordHashError(argon2::password_hash::Error),
     JwtError(jsonwebtoken::errors::Error),
+    IoError(std::io::Error),
     NotFound,
     Unauthorized,
+    Forbidden,
+    // Отказ в доступе с игровым сообщением, которое показывается игроку как есть
+    AccessDenied(String),
+    BadRequest(String),
     InvalidCredentials,
     InternalServerError,
 }
@@ -32,10 +37,17 @@ impl IntoResponse for AppError {
                 tracing::error!("JWT error: {:?}", e);
                 (StatusCode::UNAUTHORIZED, "Invalid token".to_string())
             }
+            AppError::IoError(e) => {
+                tracing::error!("IO error: {:?}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Storage Error".to_string())
+            }
             AppError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "Invalid email or password".to_string()),
             AppError::InternalServerError => (StatusCode::INTERNAL_SERVER_ERROR, "An internal error occurred".to_string()),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Authentication required".to_string()),
             AppError::NotFound => (StatusCode::NOT_FOUND, "Resource not found".to_string()),
+            AppError::Forbidden => (StatusCode::FORBIDDEN, "Insufficient permissions".to_string()),
+            AppError::AccessDenied(message) => (StatusCode::FORBIDDEN, message),
+            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
         };
 
         let body = Json(json!({ "error": error_message }));
@@ -62,4 +74,9 @@ impl From<jsonwebtoken::errors::Error> for AppError {
     fn from(err: jsonwebtoken::errors::Error) -> Self {
         AppError::JwtError(err)
     }
-}
\ No newline at end of file
+}
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::IoError(err)
+    }
+}
@@ -0,0 +1,295 @@
+// /server/src/handlers/access_request_handler.rs
+
+// Запросы доступа: игрок, которого не пустили в локацию, просит пропуск.
+// Запрос адресуется создателю локации, а если его нет - модераторам; модераторы
+// могут решить любой запрос. Одобрение выдает разовый или временный пропуск
+// (см. world::grants). Обе стороны получают уведомления по WebSocket.
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    handlers::grant_handler::GrantTerms,
+    models::{
+        access_request::{AccessRequest, AccessRequestStatus},
+        user::UserRole,
+    },
+    state::AppState,
+    world::{
+        grants::{announce_grant, issue_grant, GrantScope},
+        rules::RuleContext,
+        zones::fetch_location,
+    },
+    ws::utils::{send_to_staff, send_to_user},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::Deserialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+const MAX_MESSAGE_CHARS: usize = 1000;
+
+async fn fetch_request(state: &AppState, id: Uuid) -> Result<AccessRequest, AppError> {
+    let request = sqlx::query_as!(
+        AccessRequest,
+        r#"
+        SELECT r.id, r.user_id, u.username, r.location_id, r.message, r.status AS "status: _", r.approver_id,
+               r.decided_by, r.decision_note, r.grant_id, r.created_at, r.decided_at
+        FROM access_requests r JOIN users u ON u.id = r.user_id
+        WHERE r.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(request)
+}
+
+/// Рассылает состояние запроса игроку и тем, кто его рассматривает.
+async fn notify_access_request(state: &AppState, request: &AccessRequest, event: &str) {
+    let message = serde_json::json!({ "type": event, "request": request }).to_string();
+    send_to_user(state, request.user_id, message.clone()).await;
+    match request.approver_id {
+        Some(approver_id) => {
+            send_to_user(state, approver_id, message).await;
+        }
+        None => send_to_staff(state, message).await,
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AccessRequestPayload {
+    #[serde(default)]
+    pub message: String,
+}
+
+/// Игрок просит доступ в локацию, в которую его не пускает уровень.
+pub async fn create_access_request(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+    Json(payload): Json<AccessRequestPayload>,
+) -> Result<Json<AccessRequest>, AppError> {
+    let message = payload.message.trim();
+    if message.chars().count() > MAX_MESSAGE_CHARS {
+        return Err(AppError::BadRequest(format!("Message cannot exceed {} characters", MAX_MESSAGE_CHARS)));
+    }
+
+    let location = fetch_location(&state.pool, location_id).await?;
+    let ctx = RuleContext::load(&state.pool, claims.sub, claims.role).await?;
+    if ctx.clears(location.id, location.security_level) {
+        return Err(AppError::AccessDenied("ДОСТУП В ЛОКАЦИЮ УЖЕ ЕСТЬ.".to_string()));
+    }
+
+    // Создатель локации рассматривает запросы сам, если он еще существует
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO access_requests (user_id, location_id, message, approver_id)
+        SELECT $1, l.id, $3, u.id FROM locations l LEFT JOIN users u ON u.id = l.creator_id
+        WHERE l.id = $2
+        ON CONFLICT (user_id, location_id) WHERE status = 'Pending' DO NOTHING
+        RETURNING id
+        "#,
+        claims.sub,
+        location_id,
+        message
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::AccessDenied("ЗАПРОС УЖЕ ОТПРАВЛЕН И ОЖИДАЕТ РЕШЕНИЯ.".to_string()))?;
+
+    let request = fetch_request(&state, id).await?;
+    tracing::info!("{} запросил доступ в локацию {}", claims.username, location_id);
+    notify_access_request(&state, &request, "access_request_created").await;
+    Ok(Json(request))
+}
+
+/// История запросов самого игрока.
+pub async fn list_player_access_requests(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<AccessRequest>>, AppError> {
+    let requests = sqlx::query_as!(
+        AccessRequest,
+        r#"
+        SELECT r.id, r.user_id, u.username, r.location_id, r.message, r.status AS "status: _", r.approver_id,
+               r.decided_by, r.decision_note, r.grant_id, r.created_at, r.decided_at
+        FROM access_requests r JOIN users u ON u.id = r.user_id
+        WHERE r.user_id = $1
+        ORDER BY r.created_at DESC
+        "#,
+        claims.sub
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(requests))
+}
+
+#[derive(Deserialize)]
+pub struct AccessRequestQuery {
+    pub status: Option<AccessRequestStatus>,
+}
+
+/// Запросы, которые может рассмотреть пользователь: адресованные ему,
+/// а модераторам и выше - все.
+pub async fn list_access_requests(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<AccessRequestQuery>,
+) -> Result<Json<Vec<AccessRequest>>, AppError> {
+    let requests = sqlx::query_as!(
+        AccessRequest,
+        r#"
+        SELECT r.id, r.user_id, u.username, r.location_id, r.message, r.status AS "status: _", r.approver_id,
+               r.decided_by, r.decision_note, r.grant_id, r.created_at, r.decided_at
+        FROM access_requests r JOIN users u ON u.id = r.user_id
+        WHERE ($1 OR r.approver_id = $2) AND ($3::access_request_status IS NULL OR r.status = $3)
+        ORDER BY r.created_at DESC
+        "#,
+        claims.role >= UserRole::Moderator,
+        claims.sub,
+        query.status as Option<AccessRequestStatus>
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(requests))
+}
+
+/// Блокирует нерассмотренный запрос, который пользователь вправе решить. Возвращает игрока и локацию.
+async fn lock_pending_request(conn: &mut PgConnection, claims: &Claims, id: Uuid) -> Result<(Uuid, Uuid), AppError> {
+    let request = sqlx::query!(
+        r#"
+        SELECT user_id, location_id, approver_id, status AS "status: AccessRequestStatus"
+        FROM access_requests WHERE id = $1 FOR UPDATE
+        "#,
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    if request.approver_id != Some(claims.sub) && claims.role < UserRole::Moderator {
+        return Err(AppError::Forbidden);
+    }
+    if request.status != AccessRequestStatus::Pending {
+        return Err(AppError::BadRequest("Request has already been decided".to_string()));
+    }
+    Ok((request.user_id, request.location_id))
+}
+
+#[derive(Deserialize)]
+pub struct ApprovePayload {
+    // Разовый пропуск вместо действующего до срока
+    #[serde(default)]
+    pub single_use: bool,
+    #[serde(flatten)]
+    pub terms: GrantTerms,
+    pub note: Option<String>,
+}
+
+/// Одобряет запрос и выдает игроку пропуск в локацию.
+pub async fn approve_access_request(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ApprovePayload>,
+) -> Result<Json<AccessRequest>, AppError> {
+    let expires_at = payload.terms.expires_at()?;
+    let note = payload.note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+
+    let mut tx = state.pool.begin().await?;
+    let (user_id, location_id) = lock_pending_request(&mut tx, &claims, id).await?;
+    let scope = if payload.single_use { GrantScope::Pass(location_id) } else { GrantScope::Location(location_id) };
+    let reason = note.unwrap_or("Запрос доступа одобрен");
+    let grant = issue_grant(&mut tx, user_id, scope, expires_at, Some(claims.sub), reason).await?;
+    sqlx::query!(
+        r#"
+        UPDATE access_requests
+        SET status = 'Approved', decided_by = $2, decision_note = $3, grant_id = $4, decided_at = NOW()
+        WHERE id = $1
+        "#,
+        id,
+        claims.sub,
+        note,
+        grant.id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!("{} одобрил запрос доступа {} до {}", claims.username, id, expires_at);
+    let request = fetch_request(&state, id).await?;
+    announce_grant(&state, &grant).await;
+    notify_access_request(&state, &request, "access_request_decided").await;
+    Ok(Json(request))
+}
+
+#[derive(Deserialize)]
+pub struct DenyPayload {
+    pub note: Option<String>,
+}
+
+pub async fn deny_access_request(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    payload: Option<Json<DenyPayload>>,
+) -> Result<Json<AccessRequest>, AppError> {
+    let note = payload
+        .and_then(|Json(payload)| payload.note)
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+
+    let mut tx = state.pool.begin().await?;
+    lock_pending_request(&mut tx, &claims, id).await?;
+    sqlx::query!(
+        r#"
+        UPDATE access_requests
+        SET status = 'Denied', decided_by = $2, decision_note = $3, decided_at = NOW()
+        WHERE id = $1
+        "#,
+        id,
+        claims.sub,
+        note
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!("{} отклонил запрос доступа {}", claims.username, id);
+    let request = fetch_request(&state, id).await?;
+    notify_access_request(&state, &request, "access_request_decided").await;
+    Ok(Json(request))
+}
+
+/// Игрок отзывает свой нерассмотренный запрос.
+pub async fn cancel_access_request(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE access_requests SET status = 'Cancelled', decided_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND status = 'Pending'
+        "#,
+        id,
+        claims.sub
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    let request = fetch_request(&state, id).await?;
+    notify_access_request(&state, &request, "access_request_cancelled").await;
+    Ok(StatusCode::NO_CONTENT)
+}
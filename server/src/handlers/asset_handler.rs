@@ -0,0 +1,244 @@
+// /server/src/handlers/asset_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{asset::Asset, user::UserRole},
+    state::AppState,
+};
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+    Extension, Json,
+};
+use image::{ImageFormat, ImageReader, Limits};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{io::Cursor, path::Path as FsPath};
+use uuid::Uuid;
+
+/// Больше этого по любой стороне картинку даже не декодируем.
+const MAX_IMAGE_DIMENSION: u32 = 8192;
+const THUMBNAIL_SIZE: u32 = 256;
+/// Неиспользуемые изображения удаляются не сразу: Архитектор мог загрузить картинку
+/// и еще не успеть привязать ее к локации.
+const COLLECT_GRACE_PERIOD_SECS: f64 = 3600.0;
+
+#[derive(Serialize)]
+pub struct AssetResponse {
+    #[serde(flatten)]
+    pub asset: Asset,
+    pub url: String,
+    pub thumbnail_url: String,
+}
+
+impl From<Asset> for AssetResponse {
+    fn from(asset: Asset) -> Self {
+        Self {
+            url: asset.url(),
+            thumbnail_url: asset.thumbnail_url(),
+            asset,
+        }
+    }
+}
+
+struct ProcessedImage {
+    width: u32,
+    height: u32,
+    thumbnail: Vec<u8>,
+}
+
+/// Декодирует изображение целиком (это и есть проверка, что файл не битый) и строит миниатюру.
+fn process_image(data: &[u8], format: ImageFormat) -> Result<ProcessedImage, AppError> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+    let image = reader
+        .decode()
+        .map_err(|e| AppError::BadRequest(format!("Invalid image: {}", e)))?;
+
+    let mut thumbnail = Vec::new();
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .write_to(&mut Cursor::new(&mut thumbnail), ImageFormat::Png)
+        .map_err(|e| {
+            tracing::error!("Failed to encode thumbnail: {:?}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(ProcessedImage { width: image.width(), height: image.height(), thumbnail })
+}
+
+/// Пишет файл через временный, чтобы читатели никогда не видели его наполовину записанным.
+/// У каждой записи свой временный файл: одновременные загрузки одной картинки не мешают друг другу.
+async fn write_atomically(path: &FsPath, data: &[u8]) -> Result<(), AppError> {
+    let tmp_path = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+    tokio::fs::write(&tmp_path, data).await?;
+    tokio::fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
+
+/// Загружает изображение (тело запроса - сам файл). Только для Архитекторов.
+/// Одинаковые файлы хранятся один раз: повторная загрузка возвращает существующую запись.
+/// Неиспользуемая запись при этом снова получает отсрочку от сборки мусора.
+pub async fn upload_asset(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    body: Bytes,
+) -> Result<Json<AssetResponse>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    if body.is_empty() {
+        return Err(AppError::BadRequest("Empty upload".to_string()));
+    }
+    if body.len() > state.config.asset_max_bytes {
+        return Err(AppError::BadRequest(format!(
+            "Image is larger than {} bytes",
+            state.config.asset_max_bytes
+        )));
+    }
+
+    // Тип определяем по содержимому, а не по заголовкам клиента
+    let format = image::guess_format(&body)
+        .map_err(|_| AppError::BadRequest("Unrecognized image format".to_string()))?;
+    let (mime_type, extension) = match format {
+        ImageFormat::Png => ("image/png", "png"),
+        ImageFormat::Jpeg => ("image/jpeg", "jpg"),
+        ImageFormat::Gif => ("image/gif", "gif"),
+        ImageFormat::WebP => ("image/webp", "webp"),
+        _ => return Err(AppError::BadRequest("Only PNG, JPEG, GIF and WebP images are allowed".to_string())),
+    };
+
+    let content_hash = format!("{:x}", Sha256::digest(&body));
+
+    let data = body.clone();
+    let processed = tokio::task::spawn_blocking(move || process_image(&data, format))
+        .await
+        .map_err(|e| {
+            tracing::error!("Image processing task failed: {:?}", e);
+            AppError::InternalServerError
+        })??;
+
+    let asset_dir = FsPath::new(&state.config.asset_dir);
+    tokio::fs::create_dir_all(asset_dir).await?;
+    // Файлы пишем до записи в базу: запись без файла хуже, чем файл без записи
+    write_atomically(&asset_dir.join(format!("{}.{}", content_hash, extension)), &body).await?;
+    write_atomically(&asset_dir.join(format!("{}_thumb.png", content_hash)), &processed.thumbnail).await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO assets (content_hash, mime_type, extension, byte_size, width, height, uploaded_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (content_hash) DO UPDATE SET created_at = NOW() WHERE assets.ref_count = 0
+        "#,
+        content_hash,
+        mime_type,
+        extension,
+        body.len() as i32,
+        processed.width as i32,
+        processed.height as i32,
+        claims.sub
+    )
+    .execute(&state.pool)
+    .await?;
+
+    let asset = sqlx::query_as!(Asset, "SELECT * FROM assets WHERE content_hash = $1", content_hash)
+        .fetch_one(&state.pool)
+        .await?;
+
+    tracing::info!("{} загрузил изображение {}", claims.username, asset.file_name());
+    Ok(Json(asset.into()))
+}
+
+/// Список загруженных изображений. Только для Архитекторов.
+pub async fn list_assets(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<AssetResponse>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let assets = sqlx::query_as!(Asset, "SELECT * FROM assets ORDER BY created_at DESC")
+        .fetch_all(&state.pool)
+        .await?;
+
+    Ok(Json(assets.into_iter().map(AssetResponse::from).collect()))
+}
+
+/// Разбирает имя файла вида `<sha256>[_thumb].<ext>`. Все остальное (в том числе `..`) отвергается.
+fn content_type_for(name: &str) -> Option<&'static str> {
+    let (stem, extension) = name.split_once('.')?;
+    let hash = stem.strip_suffix("_thumb").unwrap_or(stem);
+    if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) {
+        return None;
+    }
+    match extension {
+        "png" => Some("image/png"),
+        "jpg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Отдает файл изображения. Публичный: картинки подставляются в <img> без токена.
+pub async fn serve_asset(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let content_type = content_type_for(&name).ok_or(AppError::NotFound)?;
+
+    let data = match tokio::fs::read(FsPath::new(&state.config.asset_dir).join(&name)).await {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(AppError::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+
+    // Имя файла - хеш содержимого, поэтому кешировать можно навсегда
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+        ],
+        data,
+    ))
+}
+
+#[derive(Serialize)]
+pub struct CollectResponse {
+    pub removed: usize,
+}
+
+/// Удаляет изображения, на которые больше не ссылается ни одна локация. Только для Архитекторов.
+pub async fn collect_assets(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<CollectResponse>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let removed = sqlx::query!(
+        r#"
+        DELETE FROM assets
+        WHERE ref_count = 0 AND created_at < NOW() - make_interval(secs => $1)
+        RETURNING content_hash, extension
+        "#,
+        COLLECT_GRACE_PERIOD_SECS
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let asset_dir = FsPath::new(&state.config.asset_dir);
+    for row in &removed {
+        for name in [format!("{}.{}", row.content_hash, row.extension), format!("{}_thumb.png", row.content_hash)] {
+            if let Err(e) = tokio::fs::remove_file(asset_dir.join(&name)).await {
+                tracing::warn!("Не удалось удалить файл {}: {}", name, e);
+            }
+        }
+    }
+
+    tracing::info!("{} удалил {} неиспользуемых изображений", claims.username, removed.len());
+    Ok(Json(CollectResponse { removed: removed.len() }))
+}
@@ -0,0 +1,432 @@
+// /server/src/handlers/challenge_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{
+        challenge::{ChallengeKind, ChallengeView, ClearanceAuditEntry, ClearanceChallenge},
+        user::UserRole,
+    },
+    state::AppState,
+    world::{
+        clearance::{
+            announce_clearance, clear_wrong_answers, lock_access_level, normalize_answer, record_wrong_answer,
+            riddle_lockout, set_access_level, ClearanceChange,
+        },
+        grants::{active_clearance, announce_grant, issue_grant, GrantScope},
+        intrusion::load_settings,
+        inventory::take_one,
+        quests::{emit_quest_events, QuestEvent},
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+async fn fetch_challenge(state: &AppState, id: Uuid) -> Result<ClearanceChallenge, AppError> {
+    let challenge = sqlx::query_as!(
+        ClearanceChallenge,
+        r#"
+        SELECT id, location_id, title, prompt, kind AS "kind: _", answer, required_item_id, consume_item,
+               min_access_level, grants_level, grant_duration_secs, creator_id, created_at
+        FROM clearance_challenges WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(challenge)
+}
+
+/// Испытания в текущей локации игрока.
+pub async fn list_player_challenges(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<ChallengeView>>, AppError> {
+    let player = sqlx::query!("SELECT current_location_id, access_level FROM players WHERE user_id = $1", claims.sub)
+        .fetch_one(&state.pool)
+        .await?;
+    let location_id = player.current_location_id.ok_or(AppError::NotFound)?;
+    let effective_level = active_clearance(&state.pool, claims.sub).await?.access_level;
+
+    let challenges = sqlx::query!(
+        r#"
+        SELECT id, title, prompt, kind AS "kind: ChallengeKind", required_item_id, min_access_level, grants_level,
+               grant_duration_secs
+        FROM clearance_challenges WHERE location_id = $1
+        ORDER BY grants_level, title
+        "#,
+        location_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let views = challenges
+        .into_iter()
+        .map(|c| ChallengeView {
+            available: effective_level >= c.min_access_level
+                && current_level(c.grant_duration_secs, player.access_level, effective_level) < c.grants_level,
+            id: c.id,
+            title: c.title,
+            prompt: c.prompt,
+            kind: c.kind,
+            required_item_id: c.required_item_id,
+            min_access_level: c.min_access_level,
+            grants_level: c.grants_level,
+            grant_duration_secs: c.grant_duration_secs,
+        })
+        .collect();
+
+    Ok(Json(views))
+}
+
+/// С каким уровнем сравнивается награда испытания: временный допуск имеет смысл,
+/// только если он выше действующего уровня, постоянное повышение - если выше постоянного.
+fn current_level(grant_duration_secs: Option<i32>, access_level: i32, effective_level: i32) -> i32 {
+    if grant_duration_secs.is_some() { effective_level } else { access_level }
+}
+
+#[derive(Deserialize)]
+pub struct AttemptPayload {
+    /// Ответ на загадку; для испытаний с предметом не нужен.
+    pub answer: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AttemptResponse {
+    pub old_level: i32,
+    pub new_level: i32,
+}
+
+/// Попытка пройти испытание. Успех сразу повышает уровень доступа
+/// или, если у испытания задан срок, выдает временный допуск.
+pub async fn attempt_challenge(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    payload: Option<Json<AttemptPayload>>,
+) -> Result<Json<AttemptResponse>, AppError> {
+    let answer = payload.and_then(|Json(payload)| payload.answer);
+    let challenge = fetch_challenge(&state, id).await?;
+
+    // Строка игрока блокируется до конца: два одновременных прохождения не запишут повышение дважды
+    let mut tx = state.pool.begin().await?;
+    let level = lock_access_level(&mut tx, claims.sub).await?;
+    let effective_level = active_clearance(&mut *tx, claims.sub).await?.access_level;
+    let location_id = sqlx::query_scalar!("SELECT current_location_id FROM players WHERE user_id = $1", claims.sub)
+        .fetch_one(&mut *tx)
+        .await?;
+    if location_id != Some(challenge.location_id) {
+        return Err(AppError::AccessDenied("ИСПЫТАНИЕ НАХОДИТСЯ В ДРУГОЙ ЛОКАЦИИ.".to_string()));
+    }
+    if effective_level < challenge.min_access_level {
+        return Err(AppError::AccessDenied("НЕДОСТАТОЧНЫЙ УРОВЕНЬ ДОПУСКА ДЛЯ ЭТОГО ИСПЫТАНИЯ.".to_string()));
+    }
+    if current_level(challenge.grant_duration_secs, level, effective_level) >= challenge.grants_level {
+        return Err(AppError::AccessDenied("ВАШ УРОВЕНЬ ДОПУСКА УЖЕ НЕ НИЖЕ.".to_string()));
+    }
+
+    match challenge.kind {
+        ChallengeKind::Riddle => {
+            if let Some(secs) = riddle_lockout(&mut tx, claims.sub, challenge.id).await? {
+                return Err(AppError::AccessDenied(format!(
+                    "СЛИШКОМ МНОГО НЕВЕРНЫХ ОТВЕТОВ. ПОВТОРИТЕ ЧЕРЕЗ {} С.",
+                    secs
+                )));
+            }
+            let expected = challenge.answer.as_deref().map(normalize_answer);
+            let given = answer.as_deref().map(normalize_answer);
+            if given.is_none() || given != expected {
+                // Неверный ответ записывается, поэтому транзакция фиксируется и при отказе
+                let settings = load_settings(&mut *tx, challenge.location_id, &state.config).await?;
+                let lockout = record_wrong_answer(&mut tx, claims.sub, challenge.id, &settings).await?;
+                tx.commit().await?;
+
+                tracing::info!(
+                    "{} неверно ответил на испытание {} (блокировка {:?})",
+                    claims.username,
+                    challenge.id,
+                    lockout
+                );
+                return Err(AppError::AccessDenied(match lockout {
+                    Some(secs) => format!("НЕВЕРНЫЙ ОТВЕТ. ОТВЕТЫ ЗАБЛОКИРОВАНЫ НА {} С.", secs),
+                    None => "НЕВЕРНЫЙ ОТВЕТ.".to_string(),
+                }));
+            }
+            clear_wrong_answers(&mut tx, claims.sub, challenge.id).await?;
+        }
+        ChallengeKind::Item => {
+            let Some(item_id) = challenge.required_item_id.as_deref() else {
+                return Err(AppError::AccessDenied("ИСПЫТАНИЕ НЕДОСТУПНО.".to_string()));
+            };
+            let presented = if challenge.consume_item {
+                take_one(&mut tx, claims.sub, item_id).await?
+            } else {
+                sqlx::query_scalar!(
+                    r#"SELECT EXISTS (SELECT 1 FROM player_items WHERE user_id = $1 AND item_id = $2) AS "exists!""#,
+                    claims.sub,
+                    item_id
+                )
+                .fetch_one(&mut *tx)
+                .await?
+            };
+            if !presented {
+                return Err(AppError::AccessDenied("У ВАС НЕТ НУЖНОГО ПРЕДМЕТА.".to_string()));
+            }
+        }
+    }
+
+    let reason = format!("Испытание «{}»", challenge.title);
+    if let Some(duration_secs) = challenge.grant_duration_secs {
+        let expires_at = Utc::now() + Duration::seconds(duration_secs.into());
+        let scope = GrantScope::AccessLevel(challenge.grants_level);
+        let grant = issue_grant(&mut tx, claims.sub, scope, expires_at, None, &reason).await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            "{} прошел испытание {}: временный уровень {} до {}",
+            claims.username,
+            challenge.id,
+            challenge.grants_level,
+            expires_at
+        );
+        announce_grant(&state, &grant).await;
+        return Ok(Json(AttemptResponse { old_level: effective_level, new_level: challenge.grants_level }));
+    }
+
+    let change = ClearanceChange { challenge_id: Some(challenge.id), granted_by: None, reason: &reason };
+    let old_level = set_access_level(&mut tx, claims.sub, challenge.grants_level, change).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "{} прошел испытание {}: уровень доступа {} -> {}",
+        claims.username,
+        challenge.id,
+        old_level,
+        challenge.grants_level
+    );
+    announce_clearance(&state, claims.sub, old_level, challenge.grants_level, &reason).await;
+    emit_quest_events(&state, claims.sub, vec![QuestEvent::AccessLevelChanged(challenge.grants_level)]).await;
+    Ok(Json(AttemptResponse { old_level, new_level: challenge.grants_level }))
+}
+
+#[derive(Deserialize)]
+pub struct ChallengePayload {
+    pub title: String,
+    pub prompt: String,
+    pub kind: ChallengeKind,
+    pub answer: Option<String>,
+    pub required_item_id: Option<String>,
+    #[serde(default)]
+    pub consume_item: bool,
+    #[serde(default)]
+    pub min_access_level: i32,
+    pub grants_level: i32,
+    pub grant_duration_secs: Option<i32>,
+}
+
+impl ChallengePayload {
+    async fn validate(&self, state: &AppState) -> Result<(), AppError> {
+        if self.title.trim().is_empty() {
+            return Err(AppError::BadRequest("Challenge title cannot be empty".to_string()));
+        }
+        if self.grants_level <= self.min_access_level {
+            return Err(AppError::BadRequest("grants_level must be greater than min_access_level".to_string()));
+        }
+        if self.grant_duration_secs.is_some_and(|secs| secs <= 0) {
+            return Err(AppError::BadRequest("grant_duration_secs must be positive".to_string()));
+        }
+        match self.kind {
+            ChallengeKind::Riddle => {
+                if self.answer.as_deref().is_none_or(|answer| normalize_answer(answer).is_empty()) {
+                    return Err(AppError::BadRequest("Riddle challenges need an answer".to_string()));
+                }
+            }
+            ChallengeKind::Item => {
+                let item_id = self
+                    .required_item_id
+                    .as_deref()
+                    .ok_or_else(|| AppError::BadRequest("Item challenges need required_item_id".to_string()))?;
+                sqlx::query_scalar!("SELECT id FROM item_definitions WHERE id = $1", item_id)
+                    .fetch_optional(&state.pool)
+                    .await?
+                    .ok_or_else(|| AppError::BadRequest(format!("Unknown item '{}'", item_id)))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Все испытания локации вместе с ответами. Только для Архитекторов.
+pub async fn list_location_challenges(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+) -> Result<Json<Vec<ClearanceChallenge>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let challenges = sqlx::query_as!(
+        ClearanceChallenge,
+        r#"
+        SELECT id, location_id, title, prompt, kind AS "kind: _", answer, required_item_id, consume_item,
+               min_access_level, grants_level, grant_duration_secs, creator_id, created_at
+        FROM clearance_challenges WHERE location_id = $1
+        ORDER BY grants_level, title
+        "#,
+        location_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(challenges))
+}
+
+pub async fn create_challenge(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+    Json(payload): Json<ChallengePayload>,
+) -> Result<Json<ClearanceChallenge>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    payload.validate(&state).await?;
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO clearance_challenges
+            (location_id, title, prompt, kind, answer, required_item_id, consume_item,
+             min_access_level, grants_level, grant_duration_secs, creator_id)
+        SELECT id, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11 FROM locations WHERE id = $1
+        RETURNING id
+        "#,
+        location_id,
+        payload.title.trim(),
+        payload.prompt,
+        payload.kind as ChallengeKind,
+        payload.answer,
+        payload.required_item_id,
+        payload.consume_item,
+        payload.min_access_level,
+        payload.grants_level,
+        payload.grant_duration_secs,
+        claims.sub
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(fetch_challenge(&state, id).await?))
+}
+
+pub async fn update_challenge(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ChallengePayload>,
+) -> Result<Json<ClearanceChallenge>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    payload.validate(&state).await?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE clearance_challenges
+        SET title = $2, prompt = $3, kind = $4, answer = $5, required_item_id = $6, consume_item = $7,
+            min_access_level = $8, grants_level = $9, grant_duration_secs = $10
+        WHERE id = $1
+        "#,
+        id,
+        payload.title.trim(),
+        payload.prompt,
+        payload.kind as ChallengeKind,
+        payload.answer,
+        payload.required_item_id,
+        payload.consume_item,
+        payload.min_access_level,
+        payload.grants_level,
+        payload.grant_duration_secs
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(fetch_challenge(&state, id).await?))
+}
+
+pub async fn delete_challenge(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let result = sqlx::query!("DELETE FROM clearance_challenges WHERE id = $1", id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct AccessLevelPayload {
+    pub access_level: i32,
+    pub reason: String,
+}
+
+/// Ручное назначение уровня доступа. Только для Архитекторов; причина обязательна и попадает в журнал.
+pub async fn set_player_access_level(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<AccessLevelPayload>,
+) -> Result<Json<AttemptResponse>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::BadRequest("Reason cannot be empty".to_string()));
+    }
+
+    let mut tx = state.pool.begin().await?;
+    let change = ClearanceChange { challenge_id: None, granted_by: Some(claims.sub), reason };
+    let old_level = set_access_level(&mut tx, user_id, payload.access_level, change).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "{} изменил уровень доступа игрока {}: {} -> {}",
+        claims.username,
+        user_id,
+        old_level,
+        payload.access_level
+    );
+    announce_clearance(&state, user_id, old_level, payload.access_level, reason).await;
+    emit_quest_events(&state, user_id, vec![QuestEvent::AccessLevelChanged(payload.access_level)]).await;
+    Ok(Json(AttemptResponse { old_level, new_level: payload.access_level }))
+}
+
+/// Журнал изменений уровня доступа игрока. Только для Архитекторов.
+pub async fn get_clearance_log(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<Vec<ClearanceAuditEntry>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let entries = sqlx::query_as!(
+        ClearanceAuditEntry,
+        "SELECT * FROM clearance_audit WHERE user_id = $1 ORDER BY created_at DESC",
+        user_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(entries))
+}
@@ -0,0 +1,210 @@
+// /server/src/handlers/generator_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::user::UserRole,
+    state::AppState,
+    world::{
+        generator::{generate, GeneratedCluster, GeneratorParams},
+        zones::validate_location_zone,
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json as SqlJson;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftParams {
+    pub zone_id: Uuid,
+    /// Существующая локация, которая при коммите связывается со входом в кластер.
+    pub attach_to: Option<Uuid>,
+    #[serde(flatten)]
+    pub generator: GeneratorParams,
+}
+
+#[derive(Serialize)]
+pub struct Draft {
+    pub id: Uuid,
+    pub created_by: Uuid,
+    pub params: SqlJson<DraftParams>,
+    pub cluster: SqlJson<GeneratedCluster>,
+    pub created_at: DateTime<Utc>,
+}
+
+async fn fetch_draft(state: &AppState, id: Uuid) -> Result<Draft, AppError> {
+    let draft = sqlx::query_as!(
+        Draft,
+        r#"
+        SELECT id, created_by, params AS "params: SqlJson<DraftParams>",
+               cluster AS "cluster: SqlJson<GeneratedCluster>", created_at
+        FROM generator_drafts WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    Ok(draft)
+}
+
+async fn validate_attach_to(state: &AppState, attach_to: Option<Uuid>) -> Result<(), AppError> {
+    if let Some(location_id) = attach_to {
+        sqlx::query_scalar!("SELECT id FROM locations WHERE id = $1", location_id)
+            .fetch_optional(&state.pool)
+            .await?
+            .ok_or_else(|| AppError::BadRequest("Unknown attach_to location".to_string()))?;
+    }
+    Ok(())
+}
+
+/// Генерирует кластер и сохраняет его как черновик для просмотра. Только для Архитекторов.
+pub async fn create_draft(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(params): Json<DraftParams>,
+) -> Result<Json<Draft>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    params.generator.validate()?;
+    validate_location_zone(&state.pool, params.zone_id).await?;
+    validate_attach_to(&state, params.attach_to).await?;
+
+    let cluster = generate(&params.generator)?;
+
+    let id = sqlx::query_scalar!(
+        "INSERT INTO generator_drafts (created_by, params, cluster) VALUES ($1, $2, $3) RETURNING id",
+        claims.sub,
+        SqlJson(&params) as _,
+        SqlJson(&cluster) as _
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    Ok(Json(fetch_draft(&state, id).await?))
+}
+
+pub async fn get_draft(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Draft>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    Ok(Json(fetch_draft(&state, id).await?))
+}
+
+pub async fn delete_draft(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let result = sqlx::query!("DELETE FROM generator_drafts WHERE id = $1", id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Serialize)]
+pub struct CommitResponse {
+    pub entry_location_id: Uuid,
+    /// Идентификаторы созданных локаций в порядке их номеров в черновике.
+    pub location_ids: Vec<Uuid>,
+}
+
+/// Создает локации и переходы из черновика ровно в том виде, в каком он был показан,
+/// и удаляет черновик. Все или ничего: кластер создается в одной транзакции.
+pub async fn commit_draft(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<CommitResponse>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let mut tx = state.pool.begin().await?;
+    // Черновик забирается удалением в той же транзакции: из двух одновременных коммитов
+    // кластер создаст только один, второй получит NotFound
+    let draft = sqlx::query!(
+        r#"
+        DELETE FROM generator_drafts WHERE id = $1
+        RETURNING params AS "params: SqlJson<DraftParams>", cluster AS "cluster: SqlJson<GeneratedCluster>"
+        "#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    let params = draft.params.0;
+    let cluster = draft.cluster.0;
+    // Зона или точка привязки могли исчезнуть, пока черновик лежал
+    validate_location_zone(&state.pool, params.zone_id).await?;
+    validate_attach_to(&state, params.attach_to).await?;
+
+    let mut location_ids = Vec::with_capacity(cluster.locations.len());
+    for location in &cluster.locations {
+        let location_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO locations (name, description, security_level, creator_id, zone_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+            "#,
+            location.name,
+            location.description,
+            location.security_level,
+            claims.sub,
+            params.zone_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        location_ids.push(location_id);
+    }
+
+    let entry_location_id = location_ids[0];
+    let mut links: Vec<(Uuid, Uuid, String)> = cluster
+        .links
+        .iter()
+        .map(|link| (location_ids[link.source], location_ids[link.target], link.link_text.clone()))
+        .collect();
+    if let Some(attach_to) = params.attach_to {
+        let attach_name = sqlx::query_scalar!("SELECT name FROM locations WHERE id = $1", attach_to)
+            .fetch_one(&mut *tx)
+            .await?;
+        links.push((attach_to, entry_location_id, cluster.locations[0].name.clone()));
+        links.push((entry_location_id, attach_to, attach_name));
+    }
+
+    let sources: Vec<Uuid> = links.iter().map(|(source, _, _)| *source).collect();
+    let targets: Vec<Uuid> = links.iter().map(|(_, target, _)| *target).collect();
+    let texts: Vec<String> = links.into_iter().map(|(_, _, text)| text).collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO location_links (source_location_id, target_location_id, link_text)
+        SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::varchar[])
+        "#,
+        &sources,
+        &targets,
+        &texts as &[String]
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    tracing::info!(
+        "{} создал из черновика {} кластер из {} локаций (seed {})",
+        claims.username,
+        id,
+        location_ids.len(),
+        params.generator.seed
+    );
+    Ok(Json(CommitResponse { entry_location_id, location_ids }))
+}
@@ -0,0 +1,149 @@
+// /server/src/handlers/grant_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{challenge::ClearanceGrant, user::UserRole},
+    state::AppState,
+    world::grants::{announce_grant, expire_grants, issue_grant, GrantScope},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Активные временные допуски игрока.
+pub async fn list_player_grants(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<ClearanceGrant>>, AppError> {
+    let grants = sqlx::query_as!(
+        ClearanceGrant,
+        "SELECT * FROM clearance_grants WHERE user_id = $1 AND expires_at > NOW() ORDER BY expires_at",
+        claims.sub
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(grants))
+}
+
+/// Все допуски игрока, включая истекшие. Для модераторов и выше.
+pub async fn list_grants(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<Vec<ClearanceGrant>>, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+
+    let grants = sqlx::query_as!(
+        ClearanceGrant,
+        "SELECT * FROM clearance_grants WHERE user_id = $1 ORDER BY created_at DESC",
+        user_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(grants))
+}
+
+/// Срок допуска: ровно одно из двух - длительность или момент истечения.
+#[derive(Deserialize)]
+pub struct GrantTerms {
+    pub duration_secs: Option<i64>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl GrantTerms {
+    pub fn expires_at(&self) -> Result<DateTime<Utc>, AppError> {
+        match (self.duration_secs, self.expires_at) {
+            (Some(secs), None) if secs > 0 => Duration::try_seconds(secs)
+                .and_then(|duration| Utc::now().checked_add_signed(duration))
+                .ok_or_else(|| AppError::BadRequest("duration_secs is too large".to_string())),
+            (Some(_), None) => Err(AppError::BadRequest("duration_secs must be positive".to_string())),
+            (None, Some(expires_at)) if expires_at > Utc::now() => Ok(expires_at),
+            (None, Some(_)) => Err(AppError::BadRequest("expires_at must be in the future".to_string())),
+            _ => Err(AppError::BadRequest("Specify either duration_secs or expires_at".to_string())),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct GrantPayload {
+    // Ровно одно из двух: уровень или локация
+    pub access_level: Option<i32>,
+    pub location_id: Option<Uuid>,
+    // Разовый пропуск (только для локации)
+    #[serde(default)]
+    pub single_use: bool,
+    #[serde(flatten)]
+    pub terms: GrantTerms,
+    pub reason: String,
+}
+
+/// Выдает игроку временный допуск. Для модераторов и выше; причина обязательна.
+pub async fn create_grant(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<GrantPayload>,
+) -> Result<Json<ClearanceGrant>, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(AppError::BadRequest("Reason cannot be empty".to_string()));
+    }
+
+    let scope = match (payload.access_level, payload.location_id) {
+        (Some(_), None) if payload.single_use => {
+            return Err(AppError::BadRequest("Single-use passes need location_id".to_string()));
+        }
+        (Some(level), None) => GrantScope::AccessLevel(level),
+        (None, Some(location_id)) => {
+            sqlx::query_scalar!("SELECT id FROM locations WHERE id = $1", location_id)
+                .fetch_optional(&state.pool)
+                .await?
+                .ok_or_else(|| AppError::BadRequest("Unknown location".to_string()))?;
+            if payload.single_use { GrantScope::Pass(location_id) } else { GrantScope::Location(location_id) }
+        }
+        _ => return Err(AppError::BadRequest("Specify either access_level or location_id".to_string())),
+    };
+    let expires_at = payload.terms.expires_at()?;
+
+    let mut tx = state.pool.begin().await?;
+    sqlx::query_scalar!("SELECT user_id FROM players WHERE user_id = $1", user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let grant = issue_grant(&mut tx, user_id, scope, expires_at, Some(claims.sub), reason).await?;
+    tx.commit().await?;
+
+    tracing::info!("{} выдал игроку {} временный допуск {} до {}", claims.username, user_id, grant.id, expires_at);
+    announce_grant(&state, &grant).await;
+    Ok(Json(grant))
+}
+
+/// Досрочно отзывает допуск: он истекает сейчас же, и игрок при необходимости
+/// выводится из закрытой для него локации.
+pub async fn revoke_grant(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+
+    let user_id = sqlx::query_scalar!(
+        "UPDATE clearance_grants SET expires_at = LEAST(expires_at, NOW()) WHERE id = $1 RETURNING user_id",
+        id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    tracing::info!("{} отозвал временный допуск {} игрока {}", claims.username, id, user_id);
+    expire_grants(&state, user_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
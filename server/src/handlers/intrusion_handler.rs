@@ -0,0 +1,184 @@
+// /server/src/handlers/intrusion_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{
+        intrusion::{IntrusionAttempt, ZoneAlarm},
+        user::UserRole,
+    },
+    state::AppState,
+    world::{
+        intrusion::{load_settings, IntrusionSettings},
+        zones::zone_location_ids,
+    },
+    ws::utils::broadcast_to_locations,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Сколько последних попыток проникновения отдается за раз.
+const ATTEMPTS_LIMIT: i64 = 100;
+
+/// Действующие пороги обнаружения вторжений локации. Только для Архитекторов.
+pub async fn get_intrusion_settings(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+) -> Result<Json<IntrusionSettings>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    sqlx::query_scalar!("SELECT id FROM locations WHERE id = $1", location_id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(Json(load_settings(&state.pool, location_id, &state.config).await?))
+}
+
+/// Незаданные поля берутся из конфигурации сервера.
+#[derive(Deserialize)]
+pub struct IntrusionSettingsPayload {
+    pub window_secs: Option<i32>,
+    pub cooldown_after: Option<i32>,
+    pub cooldown_secs: Option<i32>,
+    pub alarm_after: Option<i32>,
+    pub alarm_secs: Option<i32>,
+}
+
+pub async fn set_intrusion_settings(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+    Json(payload): Json<IntrusionSettingsPayload>,
+) -> Result<Json<IntrusionSettings>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let values = [
+        payload.window_secs,
+        payload.cooldown_after,
+        payload.cooldown_secs,
+        payload.alarm_after,
+        payload.alarm_secs,
+    ];
+    if values.into_iter().flatten().any(|value| value <= 0) {
+        return Err(AppError::BadRequest("Intrusion thresholds must be positive".to_string()));
+    }
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO intrusion_settings (location_id, window_secs, cooldown_after, cooldown_secs, alarm_after, alarm_secs)
+        SELECT id, $2, $3, $4, $5, $6 FROM locations WHERE id = $1
+        ON CONFLICT (location_id) DO UPDATE
+        SET window_secs = $2, cooldown_after = $3, cooldown_secs = $4, alarm_after = $5, alarm_secs = $6
+        "#,
+        location_id,
+        payload.window_secs,
+        payload.cooldown_after,
+        payload.cooldown_secs,
+        payload.alarm_after,
+        payload.alarm_secs
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(load_settings(&state.pool, location_id, &state.config).await?))
+}
+
+/// Последние попытки проникновения в локацию. Для модераторов и выше.
+pub async fn list_intrusions(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+) -> Result<Json<Vec<IntrusionAttempt>>, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+
+    let attempts = sqlx::query_as!(
+        IntrusionAttempt,
+        r#"
+        SELECT a.id, a.user_id, u.username, a.location_id, a.required_level, a.access_level, a.created_at
+        FROM intrusion_attempts a JOIN users u ON u.id = a.user_id
+        WHERE a.location_id = $1
+        ORDER BY a.created_at DESC
+        LIMIT $2
+        "#,
+        location_id,
+        ATTEMPTS_LIMIT
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(attempts))
+}
+
+/// Действующие тревоги во всех зонах. Для модераторов и выше.
+pub async fn list_alarms(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<ZoneAlarm>>, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+
+    let alarms = sqlx::query_as!(
+        ZoneAlarm,
+        "SELECT * FROM zone_alarms WHERE expires_at > NOW() ORDER BY raised_at DESC"
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(alarms))
+}
+
+/// Досрочно снимает тревогу и сообщает об этом всем в зоне.
+pub async fn silence_alarm(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+
+    let zone_id = sqlx::query_scalar!(
+        r#"
+        UPDATE zone_alarms SET expires_at = NOW(), silenced_by = $2
+        WHERE id = $1 AND expires_at > NOW()
+        RETURNING zone_id
+        "#,
+        id,
+        claims.sub
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    tracing::info!("{} снял тревогу {} в зоне {}", claims.username, id, zone_id);
+    let location_ids = zone_location_ids(&state.pool, zone_id).await?;
+    let message = serde_json::json!({ "type": "zone_alarm_cleared", "alarm_id": id, "zone_id": zone_id });
+    broadcast_to_locations(&state, &location_ids, message.to_string()).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Снимает блокировку перемещений, наложенную системой безопасности.
+pub async fn clear_movement_lock(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+
+    let result = sqlx::query!(
+        "UPDATE players SET movement_locked_until = NULL WHERE user_id = $1 AND movement_locked_until > NOW()",
+        user_id
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tracing::info!("{} снял блокировку перемещений игрока {}", claims.username, user_id);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,360 @@
+// /server/src/handlers/item_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{
+        item::{InventoryItem, ItemDefinition, LocationItem},
+        user::UserRole,
+    },
+    state::AppState,
+    world::{
+        inventory::{find_inventory_item, grant_item, load_inventory, take_item, validate_item_id},
+        quests::{emit_quest_events, QuestEvent},
+        room_items::{
+            broadcast_room_items, load_room_items, lock_player_room, place_item, take_room_item, RoomItemsChange,
+        },
+    },
+    ws::RoomKey,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct InventoryView {
+    pub items: Vec<InventoryItem>,
+    pub total_weight: f32,
+}
+
+pub async fn get_inventory(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<InventoryView>, AppError> {
+    let items = load_inventory(&state.pool, claims.sub).await?;
+    let total_weight = items.iter().map(|item| item.weight * item.quantity as f32).sum();
+
+    Ok(Json(InventoryView { items, total_weight }))
+}
+
+/// Осмотр предмета из своего инвентаря.
+pub async fn inspect_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<InventoryItem>, AppError> {
+    Ok(Json(find_inventory_item(&state.pool, claims.sub, id).await?))
+}
+
+#[derive(Serialize)]
+pub struct UseItemResponse {
+    pub message: String,
+    pub consumed: bool,
+    /// Сколько штук этого экземпляра осталось.
+    pub remaining: i32,
+}
+
+/// Использование предмета: игрок видит его сообщение, расходуемый предмет теряет одну штуку.
+pub async fn use_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<UseItemResponse>, AppError> {
+    let mut tx = state.pool.begin().await?;
+
+    let item = sqlx::query!(
+        r#"
+        SELECT pi.item_id, pi.quantity, d.consumable, d.use_message
+        FROM player_items pi
+        JOIN item_definitions d ON d.id = pi.item_id
+        WHERE pi.id = $1 AND pi.user_id = $2
+        FOR UPDATE OF pi
+        "#,
+        id,
+        claims.sub
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let Some(message) = item.use_message else {
+        return Err(AppError::AccessDenied("ЭТОТ ПРЕДМЕТ НЕЛЬЗЯ ИСПОЛЬЗОВАТЬ.".to_string()));
+    };
+    let mut remaining = item.quantity;
+    if item.consumable {
+        take_item(&mut tx, claims.sub, id, Some(1)).await?;
+        remaining -= 1;
+    }
+    tx.commit().await?;
+
+    tracing::info!("{} использовал предмет {}", claims.username, item.item_id);
+    Ok(Json(UseItemResponse { message, consumed: item.consumable, remaining }))
+}
+
+/// Предметы, лежащие в комнате игрока.
+pub async fn get_room_items(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<LocationItem>>, AppError> {
+    let player = sqlx::query!(
+        "SELECT current_location_id, current_instance_id FROM players WHERE user_id = $1",
+        claims.sub
+    )
+    .fetch_one(&state.pool)
+    .await?;
+    let location_id = player.current_location_id.ok_or(AppError::NotFound)?;
+
+    Ok(Json(load_room_items(&state.pool, location_id, player.current_instance_id).await?))
+}
+
+/// Сколько штук взять или бросить; без тела запроса - всю стопку.
+#[derive(Deserialize)]
+pub struct QuantityPayload {
+    pub quantity: Option<i32>,
+}
+
+pub async fn pick_up(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    payload: Option<Json<QuantityPayload>>,
+) -> Result<StatusCode, AppError> {
+    let quantity = payload.and_then(|Json(payload)| payload.quantity);
+    let mut tx = state.pool.begin().await?;
+    let room = lock_player_room(&mut tx, claims.sub).await?;
+    let (item_id, taken) = take_room_item(&mut tx, room, id, quantity).await?;
+    grant_item(&mut tx, claims.sub, &item_id, taken).await?;
+    tx.commit().await?;
+
+    tracing::info!("{} подобрал {} x{} в {}", claims.username, item_id, taken, room.location_id);
+    let change = RoomItemsChange {
+        action: "picked_up",
+        user_id: claims.sub,
+        username: &claims.username,
+        item_id: &item_id,
+        quantity: taken,
+    };
+    broadcast_room_items(&state, room, change).await?;
+    emit_quest_events(&state, claims.sub, vec![QuestEvent::ObtainedItem(item_id)]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Выбрасывает предмет из инвентаря (`id` - экземпляр) в текущую комнату.
+pub async fn drop_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    payload: Option<Json<QuantityPayload>>,
+) -> Result<StatusCode, AppError> {
+    let quantity = payload.and_then(|Json(payload)| payload.quantity);
+    let mut tx = state.pool.begin().await?;
+    let room = lock_player_room(&mut tx, claims.sub).await?;
+    let (item_id, dropped) = take_item(&mut tx, claims.sub, id, quantity).await?;
+    place_item(&mut tx, room, &item_id, dropped, claims.sub).await?;
+    tx.commit().await?;
+
+    tracing::info!("{} выбросил {} x{} в {}", claims.username, item_id, dropped, room.location_id);
+    let change = RoomItemsChange {
+        action: "dropped",
+        user_id: claims.sub,
+        username: &claims.username,
+        item_id: &item_id,
+        quantity: dropped,
+    };
+    broadcast_room_items(&state, room, change).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct PlaceItemPayload {
+    pub item_id: String,
+    #[serde(default = "default_quantity")]
+    pub quantity: i32,
+}
+
+/// Раскладывает предметы в локации. Только для Архитекторов.
+/// В инстанцированной локации их получат копии, созданные после этого.
+pub async fn place_location_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+    Json(payload): Json<PlaceItemPayload>,
+) -> Result<Json<Vec<LocationItem>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let room = RoomKey::new(location_id, None);
+
+    let mut tx = state.pool.begin().await?;
+    sqlx::query_scalar!("SELECT id FROM locations WHERE id = $1", location_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    place_item(&mut tx, room, &payload.item_id, payload.quantity, claims.sub).await?;
+    tx.commit().await?;
+
+    let change = RoomItemsChange {
+        action: "placed",
+        user_id: claims.sub,
+        username: &claims.username,
+        item_id: &payload.item_id,
+        quantity: payload.quantity,
+    };
+    broadcast_room_items(&state, room, change).await?;
+    Ok(Json(load_room_items(&state.pool, location_id, None).await?))
+}
+
+pub async fn remove_location_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((location_id, id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let room = RoomKey::new(location_id, None);
+
+    let mut tx = state.pool.begin().await?;
+    let (item_id, removed) = take_room_item(&mut tx, room, id, None).await?;
+    tx.commit().await?;
+
+    let change = RoomItemsChange {
+        action: "removed",
+        user_id: claims.sub,
+        username: &claims.username,
+        item_id: &item_id,
+        quantity: removed,
+    };
+    broadcast_room_items(&state, room, change).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Описания предметов. Только для Архитекторов.
+pub async fn list_items(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<ItemDefinition>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let items = sqlx::query_as!(ItemDefinition, "SELECT * FROM item_definitions ORDER BY id")
+        .fetch_all(&state.pool)
+        .await?;
+
+    Ok(Json(items))
+}
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub stackable: bool,
+    #[serde(default)]
+    pub weight: f32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub consumable: bool,
+    pub use_message: Option<String>,
+}
+
+/// Создает или обновляет описание предмета. Смена `stackable` не трогает уже выданные
+/// экземпляры: новое правило действует для следующих выдач.
+pub async fn set_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+    Json(payload): Json<ItemPayload>,
+) -> Result<Json<ItemDefinition>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    validate_item_id(&id)?;
+    if payload.name.trim().is_empty() {
+        return Err(AppError::BadRequest("Item name cannot be empty".to_string()));
+    }
+    if !payload.weight.is_finite() || payload.weight < 0.0 {
+        return Err(AppError::BadRequest("Weight must be a non-negative number".to_string()));
+    }
+    let mut tags: Vec<String> = Vec::with_capacity(payload.tags.len());
+    for tag in payload.tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let item = sqlx::query_as!(
+        ItemDefinition,
+        r#"
+        INSERT INTO item_definitions (id, name, description, stackable, weight, tags, consumable, use_message)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (id) DO UPDATE
+        SET name = EXCLUDED.name, description = EXCLUDED.description, stackable = EXCLUDED.stackable,
+            weight = EXCLUDED.weight, tags = EXCLUDED.tags, consumable = EXCLUDED.consumable,
+            use_message = EXCLUDED.use_message
+        RETURNING *
+        "#,
+        id,
+        payload.name.trim(),
+        payload.description,
+        payload.stackable,
+        payload.weight,
+        &tags,
+        payload.consumable,
+        payload.use_message
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    Ok(Json(item))
+}
+
+/// Удаляет описание вместе со всеми выданными экземплярами.
+pub async fn delete_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let result = sqlx::query!("DELETE FROM item_definitions WHERE id = $1", id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct GrantPayload {
+    pub user_id: Uuid,
+    #[serde(default = "default_quantity")]
+    pub quantity: i32,
+}
+
+fn default_quantity() -> i32 {
+    1
+}
+
+/// Выдает предмет игроку. Только для Архитекторов.
+pub async fn grant(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<String>,
+    Json(payload): Json<GrantPayload>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let mut tx = state.pool.begin().await?;
+    sqlx::query_scalar!("SELECT user_id FROM players WHERE user_id = $1", payload.user_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    grant_item(&mut tx, payload.user_id, &id, payload.quantity).await?;
+    tx.commit().await?;
+
+    tracing::info!("{} выдал {} x{} игроку {}", claims.username, id, payload.quantity, payload.user_id);
+    emit_quest_events(&state, payload.user_id, vec![QuestEvent::ObtainedItem(id)]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
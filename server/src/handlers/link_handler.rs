@@ -0,0 +1,151 @@
+// /server/src/handlers/link_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{link::{LinkVisibility, LocationLink}, user::UserRole},
+    state::AppState,
+    world::rules::validate_expression,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+const MAX_TRAVEL_SECS: i32 = 3600;
+
+#[derive(Deserialize)]
+pub struct LinkPayload {
+    pub target_location_id: Uuid,
+    pub link_text: String,
+    #[serde(default)]
+    pub required_access_level: i32,
+    pub access_condition: Option<String>,
+    pub denial_message: Option<String>,
+    #[serde(default)]
+    pub visibility: LinkVisibility,
+    #[serde(default = "default_search_chance")]
+    pub search_chance: i32,
+    pub reveal_condition: Option<String>,
+    #[serde(default)]
+    pub travel_secs: i32,
+}
+
+fn default_search_chance() -> i32 {
+    100
+}
+
+impl LinkPayload {
+    /// Проверяет оба выражения на языке правил, диапазон шанса поиска и время в пути.
+    fn validate(&self) -> Result<(Option<String>, Option<String>), AppError> {
+        if !(0..=100).contains(&self.search_chance) {
+            return Err(AppError::BadRequest("search_chance must be between 0 and 100".to_string()));
+        }
+        if !(0..=MAX_TRAVEL_SECS).contains(&self.travel_secs) {
+            return Err(AppError::BadRequest(format!(
+                "travel_secs must be between 0 and {}",
+                MAX_TRAVEL_SECS
+            )));
+        }
+        let condition = validate_expression("access condition", self.access_condition.as_deref())?;
+        let reveal_condition = validate_expression("reveal condition", self.reveal_condition.as_deref())?;
+        Ok((condition, reveal_condition))
+    }
+}
+
+/// Создает переход из локации `id`. Только для Архитекторов.
+pub async fn create_link(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(source_id): Path<Uuid>,
+    Json(payload): Json<LinkPayload>,
+) -> Result<Json<LocationLink>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let (condition, reveal_condition) = payload.validate()?;
+
+    let link = sqlx::query_as!(
+        LocationLink,
+        r#"
+        INSERT INTO location_links
+            (source_location_id, target_location_id, link_text, required_access_level, access_condition, denial_message,
+             visibility, search_chance, reveal_condition, travel_secs)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        RETURNING id, source_location_id, target_location_id, link_text, required_access_level, access_condition,
+                  denial_message, created_at, visibility AS "visibility: _", search_chance, reveal_condition,
+                  travel_secs
+        "#,
+        source_id,
+        payload.target_location_id,
+        payload.link_text,
+        payload.required_access_level,
+        condition,
+        payload.denial_message,
+        payload.visibility as LinkVisibility,
+        payload.search_chance,
+        reveal_condition,
+        payload.travel_secs
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    tracing::info!("{} создал переход {} -> {}", claims.username, source_id, link.target_location_id);
+    Ok(Json(link))
+}
+
+/// Полностью перезаписывает переход. Только для Архитекторов.
+pub async fn update_link(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(link_id): Path<Uuid>,
+    Json(payload): Json<LinkPayload>,
+) -> Result<Json<LocationLink>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let (condition, reveal_condition) = payload.validate()?;
+
+    let link = sqlx::query_as!(
+        LocationLink,
+        r#"
+        UPDATE location_links
+        SET target_location_id = $2, link_text = $3, required_access_level = $4,
+            access_condition = $5, denial_message = $6,
+            visibility = $7, search_chance = $8, reveal_condition = $9, travel_secs = $10
+        WHERE id = $1
+        RETURNING id, source_location_id, target_location_id, link_text, required_access_level, access_condition,
+                  denial_message, created_at, visibility AS "visibility: _", search_chance, reveal_condition,
+                  travel_secs
+        "#,
+        link_id,
+        payload.target_location_id,
+        payload.link_text,
+        payload.required_access_level,
+        condition,
+        payload.denial_message,
+        payload.visibility as LinkVisibility,
+        payload.search_chance,
+        reveal_condition,
+        payload.travel_secs
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    Ok(Json(link))
+}
+
+pub async fn delete_link(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(link_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let result = sqlx::query!("DELETE FROM location_links WHERE id = $1", link_id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
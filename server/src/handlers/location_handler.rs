@@ -1,68 +1,282 @@
 // /server/src/handlers/location_handler.rs
 use crate::{
-    auth::Claims,
+    auth::{require_role, Claims},
     error::AppError,
     // Добавляем LocationLink
-    models::{location::Location, link::LocationLink},
+    models::{
+        acl::EntryPolicy,
+        asset::Asset,
+        link::{LinkVisibility, LocationLink},
+        location::Location,
+        user::UserRole,
+    },
     state::AppState,
+    world::{
+        acl::acl_denial,
+        capacity::process_queue,
+        discovery::known_links,
+        i18n::{localize_links, localize_locations, Language},
+        links::link_denial,
+        redaction::{redact_location, validate_leaks, DEFAULT_LEAKS},
+        rules::{count_players_present, validate_expression, RuleContext},
+        templates::{self, validate_template, TemplateContext},
+        zones::{fetch_location, validate_location_zone, visibility_denial},
+    },
 };
 
 use axum::{
-    extract::{Path, Query, State},
+    extract::{Path, State},
     Extension, Json,
 };
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Переход в том виде, в каком его видит игрок: без исходного условия,
+/// но с отметкой, открыт ли он сейчас.
+#[derive(Serialize)]
+pub struct LinkView {
+    pub id: Uuid,
+    pub target_location_id: Uuid,
+    pub link_text: String,
+    pub required_access_level: i32,
+    pub locked: bool,
+    pub denial_message: Option<String>,
+    pub travel_secs: i32,
+}
 
-
-#[derive(Deserialize)]
-pub struct AccessQuery {
-    access_level: Option<i32>,
+impl LinkView {
+    pub fn new(link: LocationLink, ctx: &RuleContext) -> Self {
+        let denial_message = link_denial(&link, ctx);
+        Self {
+            id: link.id,
+            target_location_id: link.target_location_id,
+            link_text: link.link_text,
+            required_access_level: link.required_access_level,
+            locked: denial_message.is_some(),
+            denial_message,
+            travel_secs: link.travel_secs,
+        }
+    }
 }
 
 #[derive(Serialize)]
 pub struct LocationResponse {
-    location: Location,
-    links: Vec<LocationLink>,
+    pub location: Location,
+    pub links: Vec<LinkView>,
 }
 
 pub async fn get_location(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
+    Language(language): Language,
+    // access_level из query-строки игнорируется, так как берем его из БД
     Path(id): Path<Uuid>,
-    // Игнорируем query, так как берем access_level из БД
-    Query(_query): Query<AccessQuery>, 
 ) -> Result<Json<LocationResponse>, AppError> {
     tracing::debug!("Запрос локации {} для пользователя {}", id, claims.sub);
+    Ok(Json(describe_location(&state, &claims, &language, id).await?))
+}
 
-    // --- ИСПРАВЛЕНИЕ: ПЕРЕИМЕНОВЫВАЕМ ПЕРЕМЕННЫЕ ---
-    let location_info = sqlx::query_as!(
-        Location,
-        "SELECT * FROM locations WHERE id = $1",
-        id
-    ).fetch_one(&state.pool).await?;
+/// Локация глазами игрока: локализованная, с отрисованным описанием и доступными ему переходами.
+/// Закрытая для игрока локация возвращается скрытой и без переходов.
+pub async fn describe_location(
+    state: &AppState,
+    claims: &Claims,
+    language: &str,
+    id: Uuid,
+) -> Result<LocationResponse, AppError> {
+    let mut location_info = fetch_location(&state.pool, id).await?;
+    localize_locations(&state.pool, std::slice::from_mut(&mut location_info), language, &state.config).await?;
 
+    // Порядок должен быть стабильным: `go <номер>` в терминале ссылается на номер перехода из `look`
     let links_info = sqlx::query_as!(
         LocationLink,
-        "SELECT target_location_id, link_text, required_access_level FROM location_links WHERE source_location_id = $1",
+        r#"
+        SELECT id, source_location_id, target_location_id, link_text, required_access_level, access_condition,
+               denial_message, created_at, visibility AS "visibility: _", search_chance, reveal_condition,
+               travel_secs
+        FROM location_links WHERE source_location_id = $1
+        ORDER BY created_at, id
+        "#,
         id
     ).fetch_all(&state.pool).await?;
+
+    let mut ctx = RuleContext::load(&state.pool, claims.sub, claims.role).await?;
+
+    // Шаблон описания считает игроков в просматриваемой локации, а не в текущей
+    ctx.players_present = count_players_present(&state.pool, claims.sub, id).await?;
+
+    if let Some(reason) = visibility_denial(&location_info, &ctx) {
+        location_info.description = templates::render(
+            &location_info.description,
+            &TemplateContext { player_name: &claims.username, rules: &ctx.outsider() },
+        );
+        let redacted_location = redact_location(location_info, reason, &ctx, claims.sub, &state.config);
+        let redacted_response = LocationResponse { location: redacted_location, links: vec![] };
+        return Ok(redacted_response);
+    }
+    if let Some(reason) = acl_denial(&state.pool, &location_info, claims.sub, claims.role).await? {
+        // Уровня доступа хватает, поэтому название видно; закрыто только содержимое
+        let location = Location { description: reason, ambient_description: None, ..location_info };
+        return Ok(LocationResponse { location, links: vec![] });
+    }
+    location_info.description = templates::render(
+        &location_info.description,
+        &TemplateContext { player_name: &claims.username, rules: &ctx },
+    );
     
-    let player = sqlx::query_as!(crate::models::player::Player, "SELECT * FROM players WHERE user_id = $1", claims.sub)
-        .fetch_one(&state.pool).await?;
-
-    if player.access_level < location_info.security_level {
-        let scrambled_location = Location {
-            name: "[[ДАННЫЕ ПОВРЕЖДЕНЫ]]".to_string(),
-            description: format!("ТРЕБУЕТСЯ УРОВЕНЬ ДОСТУПА: {}. ВАШ УРОВЕНЬ: {}.", location_info.security_level, player.access_level),
-            image_url: Some("/static/images/scrambled.gif".to_string()),
-            ..location_info
+    // Секретные переходы в список не попадают никогда, скрытые - только после обнаружения
+    let mut links = known_links(&state.pool, claims.sub, links_info, &ctx).await?;
+    localize_links(&state.pool, &mut links, language, &state.config).await?;
+    let links = links
+        .into_iter()
+        .filter(|link| link.visibility != LinkVisibility::Secret)
+        .map(|link| LinkView::new(link, &ctx))
+        .collect();
+    Ok(LocationResponse { location: location_info, links })
+}
+
+#[derive(Deserialize)]
+pub struct LocationPayload {
+    pub name: String,
+    pub description: String,
+    pub image_url: Option<String>,
+    // Если задано, image_url берется из загруженного изображения
+    pub image_asset_id: Option<Uuid>,
+    pub zone_id: Uuid,
+    // Незаданные поля наследуются от зоны
+    pub security_level: Option<i32>,
+    pub ambient_description: Option<String>,
+    pub access_condition: Option<String>,
+    #[serde(default)]
+    pub instanced: bool,
+    pub capacity: Option<i32>,
+    // Какие поля частично видны игрокам без доступа (см. world::redaction)
+    #[serde(default = "default_redaction_leaks")]
+    pub redaction_leaks: Vec<String>,
+    // Поля личных комнат. При изменении локации незаданное поле остается прежним,
+    // чтобы клиенты, которые о них не знают, не сбрасывали владельца и политику входа
+    pub owner_id: Option<Uuid>,
+    pub entry_policy: Option<EntryPolicy>,
+    pub public_hub: Option<bool>,
+}
+
+fn default_redaction_leaks() -> Vec<String> {
+    DEFAULT_LEAKS.iter().map(|field| field.to_string()).collect()
+}
+
+impl LocationPayload {
+    /// Возвращает проверенные политику доступа, адрес изображения и политику сокрытия.
+    async fn validate(&self, state: &AppState) -> Result<(Option<String>, Option<String>, Vec<String>), AppError> {
+        validate_location_zone(&state.pool, self.zone_id).await?;
+        if let Some(capacity) = self.capacity {
+            if capacity <= 0 {
+                return Err(AppError::BadRequest("Capacity must be positive".to_string()));
+            }
+            // У каждой копии свой набор игроков, общей очереди у них быть не может
+            if self.instanced {
+                return Err(AppError::BadRequest("Instanced locations cannot have a capacity".to_string()));
+            }
+        }
+        let access_condition = validate_expression("access condition", self.access_condition.as_deref())?;
+        validate_template("description", &self.description)?;
+
+        let image_url = match self.image_asset_id {
+            Some(asset_id) => {
+                let asset = sqlx::query_as!(Asset, "SELECT * FROM assets WHERE id = $1", asset_id)
+                    .fetch_optional(&state.pool)
+                    .await?
+                    .ok_or_else(|| AppError::BadRequest("Unknown image asset".to_string()))?;
+                Some(asset.url())
+            }
+            None => self.image_url.clone(),
         };
-        let scrambled_response = LocationResponse { location: scrambled_location, links: vec![] };
-        return Ok(Json(scrambled_response));
+
+        Ok((access_condition, image_url, validate_leaks(&self.redaction_leaks)?))
     }
-    
-    // Используем правильные имена переменных
-    Ok(Json(LocationResponse { location: location_info, links: links_info }))
-}
\ No newline at end of file
+}
+
+/// Создает локацию. Только для Архитекторов.
+pub async fn create_location(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<LocationPayload>,
+) -> Result<Json<Location>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let (access_condition, image_url, redaction_leaks) = payload.validate(&state).await?;
+
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO locations
+            (name, description, image_url, security_level, creator_id, zone_id, ambient_description, access_condition,
+             instanced, image_asset_id, redaction_leaks, capacity, owner_id, entry_policy, public_hub)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        RETURNING id
+        "#,
+        payload.name,
+        payload.description,
+        image_url,
+        payload.security_level,
+        claims.sub,
+        payload.zone_id,
+        payload.ambient_description,
+        access_condition,
+        payload.instanced,
+        payload.image_asset_id,
+        &redaction_leaks,
+        payload.capacity,
+        payload.owner_id,
+        payload.entry_policy.unwrap_or_default() as EntryPolicy,
+        payload.public_hub.unwrap_or_default()
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    tracing::info!("{} создал локацию {}", claims.username, id);
+    Ok(Json(fetch_location(&state.pool, id).await?))
+}
+
+/// Перезаписывает локацию (кроме незаданных полей личных комнат). Только для Архитекторов.
+pub async fn update_location(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<LocationPayload>,
+) -> Result<Json<Location>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let (access_condition, image_url, redaction_leaks) = payload.validate(&state).await?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE locations
+        SET name = $2, description = $3, image_url = $4, security_level = $5,
+            zone_id = $6, ambient_description = $7, access_condition = $8, instanced = $9, image_asset_id = $10,
+            redaction_leaks = $11, capacity = $12, owner_id = COALESCE($13, owner_id),
+            entry_policy = COALESCE($14, entry_policy), public_hub = COALESCE($15, public_hub)
+        WHERE id = $1
+        "#,
+        id,
+        payload.name,
+        payload.description,
+        image_url,
+        payload.security_level,
+        payload.zone_id,
+        payload.ambient_description,
+        access_condition,
+        payload.instanced,
+        payload.image_asset_id,
+        &redaction_leaks,
+        payload.capacity,
+        payload.owner_id,
+        payload.entry_policy as Option<EntryPolicy>,
+        payload.public_hub
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    // Вместимость могла вырасти - раздаем новые места очереди сразу
+    process_queue(&state, id).await?;
+
+    Ok(Json(fetch_location(&state.pool, id).await?))
+}
@@ -0,0 +1,183 @@
+// /server/src/handlers/map_handler.rs
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::link::{LinkVisibility, LocationLink},
+    state::AppState,
+    world::{
+        discovery::known_links,
+        i18n::{localize_links, localize_locations, Language},
+        links::link_denial,
+        redaction::redact_location,
+        rules::RuleContext,
+        visits::visited_location_ids,
+        zones::{fetch_locations, visibility_denial},
+    },
+};
+use axum::{
+    extract::{Query, State},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::Entry, HashMap};
+use uuid::Uuid;
+
+const DEFAULT_MAP_RADIUS: u32 = 2;
+const MAX_MAP_RADIUS: u32 = 5;
+
+#[derive(Deserialize)]
+pub struct MapQuery {
+    radius: Option<u32>,
+}
+
+/// Что игрок знает об узле карты.
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeVisibility {
+    /// Игрок бывал здесь и имеет достаточный уровень доступа.
+    Known,
+    /// Игрок бывал здесь, но доступа к локации у него нет.
+    Scrambled,
+    /// Игрок здесь еще не бывал.
+    Unknown,
+}
+
+#[derive(Serialize)]
+pub struct MapNode {
+    pub id: Uuid,
+    pub visibility: NodeVisibility,
+    /// Количество переходов от текущей локации игрока.
+    pub distance: u32,
+    pub name: Option<String>,
+    pub security_level: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct MapEdge {
+    pub source_location_id: Uuid,
+    pub target_location_id: Uuid,
+    /// Ребра выходят только из узлов, которые игрок знает, поэтому текст всегда виден.
+    pub link_text: String,
+    pub locked: bool,
+}
+
+#[derive(Serialize)]
+pub struct MapResponse {
+    pub center_location_id: Uuid,
+    pub radius: u32,
+    pub nodes: Vec<MapNode>,
+    pub edges: Vec<MapEdge>,
+}
+
+/// Возвращает подграф локаций в радиусе N переходов от игрока ("туман войны").
+pub async fn get_player_map(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Language(language): Language,
+    Query(query): Query<MapQuery>,
+) -> Result<Json<MapResponse>, AppError> {
+    let radius = query.radius.unwrap_or(DEFAULT_MAP_RADIUS).min(MAX_MAP_RADIUS);
+
+    let center = sqlx::query_scalar!("SELECT current_location_id FROM players WHERE user_id = $1", claims.sub)
+        .fetch_one(&state.pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let ctx = RuleContext::load(&state.pool, claims.sub, claims.role).await?;
+
+    let visited = visited_location_ids(&state.pool, claims.sub).await?;
+
+    // Обход в ширину по слоям. Переходы берутся только из известных узлов: у неизвестных
+    // и искаженных локаций выходы скрыты (как и в get_location), поэтому они остаются листьями
+    let mut distances: HashMap<Uuid, u32> = HashMap::from([(center, 0)]);
+    let mut frontier = vec![center];
+    let mut nodes = Vec::new();
+    let mut raw_edges = Vec::new();
+
+    for depth in 0..=radius {
+        let mut locations = fetch_locations(&state.pool, &frontier).await?;
+        localize_locations(&state.pool, &mut locations, &language, &state.config).await?;
+
+        let mut known = Vec::new();
+        for location in locations {
+            let node = if !visited.contains(&location.id) {
+                MapNode {
+                    id: location.id,
+                    visibility: NodeVisibility::Unknown,
+                    distance: depth,
+                    name: None,
+                    security_level: None,
+                }
+            } else if let Some(reason) = visibility_denial(&location, &ctx) {
+                let scrambled = redact_location(location, reason, &ctx, claims.sub, &state.config);
+                MapNode {
+                    id: scrambled.id,
+                    visibility: NodeVisibility::Scrambled,
+                    distance: depth,
+                    name: Some(scrambled.name),
+                    security_level: Some(scrambled.security_level),
+                }
+            } else {
+                known.push(location.id);
+                MapNode {
+                    id: location.id,
+                    visibility: NodeVisibility::Known,
+                    distance: depth,
+                    name: Some(location.name),
+                    security_level: Some(location.security_level),
+                }
+            };
+            nodes.push(node);
+        }
+        if depth == radius || known.is_empty() {
+            break;
+        }
+
+        // Одним запросом забираем исходящие связи известных узлов слоя
+        let links = sqlx::query_as!(
+            LocationLink,
+            r#"
+            SELECT id, source_location_id, target_location_id, link_text, required_access_level, access_condition,
+                   denial_message, created_at, visibility AS "visibility: _", search_chance, reveal_condition,
+                   travel_secs
+            FROM location_links WHERE source_location_id = ANY($1)
+            "#,
+            &known
+        )
+        .fetch_all(&state.pool)
+        .await?;
+        let links = known_links(&state.pool, claims.sub, links, &ctx).await?;
+
+        let mut next_frontier = Vec::new();
+        for link in links.into_iter().filter(|link| link.visibility != LinkVisibility::Secret) {
+            if let Entry::Vacant(entry) = distances.entry(link.target_location_id) {
+                entry.insert(depth + 1);
+                next_frontier.push(link.target_location_id);
+            }
+            raw_edges.push(link);
+        }
+        frontier = next_frontier;
+    }
+    nodes.sort_by_key(|node| node.distance);
+
+    localize_links(&state.pool, &mut raw_edges, &language, &state.config).await?;
+    let edges = raw_edges
+        .into_iter()
+        .map(|edge| {
+            let locked = link_denial(&edge, &ctx).is_some();
+            MapEdge {
+                source_location_id: edge.source_location_id,
+                target_location_id: edge.target_location_id,
+                link_text: edge.link_text,
+                locked,
+            }
+        })
+        .collect();
+
+    Ok(Json(MapResponse {
+        center_location_id: center,
+        radius,
+        nodes,
+        edges,
+    }))
+}
+
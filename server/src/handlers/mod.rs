@@ -1,3 +1,21 @@
 pub mod user_handler;
 pub mod player_handler;
-pub mod location_handler;
\ No newline at end of file
+pub mod location_handler;
+pub mod map_handler;
+pub mod link_handler;
+pub mod world_handler;
+pub mod zone_handler;
+pub mod party_handler;
+pub mod asset_handler;
+pub mod translation_handler;
+pub mod spawn_handler;
+pub mod generator_handler;
+pub mod item_handler;
+pub mod trade_handler;
+pub mod challenge_handler;
+pub mod quest_handler;
+pub mod grant_handler;
+pub mod access_request_handler;
+pub mod intrusion_handler;
+pub mod room_handler;
+pub mod npc_handler;
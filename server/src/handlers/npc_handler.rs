@@ -0,0 +1,170 @@
+// /server/src/handlers/npc_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{
+        npc::{Npc, NpcPresence},
+        user::UserRole,
+    },
+    state::AppState,
+    world::npcs::validate_dialogue,
+    ws::utils::broadcast_to_locations,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+const MAX_NAME_CHARS: usize = 100;
+
+#[derive(Deserialize)]
+pub struct NpcPayload {
+    pub location_id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // Дерево диалога, см. models::npc::Dialogue
+    pub dialogue: Value,
+}
+
+impl NpcPayload {
+    async fn validate(&self, state: &AppState) -> Result<(), AppError> {
+        let name_chars = self.name.trim().chars().count();
+        if name_chars == 0 || name_chars > MAX_NAME_CHARS {
+            return Err(AppError::BadRequest(format!("NPC name must be 1 to {} characters", MAX_NAME_CHARS)));
+        }
+        validate_dialogue(&state.pool, &self.dialogue).await
+    }
+}
+
+/// Сообщает игрокам локации, что NPC появился или исчез.
+async fn announce_presence(state: &AppState, npc: &Npc, joined: bool) {
+    let message = if joined {
+        serde_json::json!({ "type": "user_joined", "user": NpcPresence::from(npc) })
+    } else {
+        serde_json::json!({ "type": "user_left", "user_id": npc.id, "username": npc.name })
+    };
+    broadcast_to_locations(state, &[npc.location_id], message.to_string()).await;
+}
+
+/// NPC локации вместе с диалогами. Только для Архитекторов.
+pub async fn list_location_npcs(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+) -> Result<Json<Vec<Npc>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let npcs = sqlx::query_as!(Npc, "SELECT * FROM npcs WHERE location_id = $1 ORDER BY name", location_id)
+        .fetch_all(&state.pool)
+        .await?;
+
+    Ok(Json(npcs))
+}
+
+pub async fn get_npc(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Npc>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let npc = sqlx::query_as!(Npc, "SELECT * FROM npcs WHERE id = $1", id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(npc))
+}
+
+pub async fn create_npc(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<NpcPayload>,
+) -> Result<Json<Npc>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    payload.validate(&state).await?;
+
+    let npc = sqlx::query_as!(
+        Npc,
+        r#"
+        INSERT INTO npcs (location_id, name, description, dialogue, creator_id)
+        SELECT id, $2, $3, $4, $5 FROM locations WHERE id = $1
+        RETURNING *
+        "#,
+        payload.location_id,
+        payload.name.trim(),
+        payload.description,
+        payload.dialogue,
+        claims.sub
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Unknown location".to_string()))?;
+
+    announce_presence(&state, &npc, true).await;
+    tracing::info!("{} поставил NPC '{}' в локацию {}", claims.username, npc.name, npc.location_id);
+    Ok(Json(npc))
+}
+
+/// Изменение NPC. Начатые разговоры продолжаются с сохраненного узла, если он остался в новом диалоге.
+pub async fn update_npc(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<NpcPayload>,
+) -> Result<Json<Npc>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    payload.validate(&state).await?;
+
+    let mut tx = state.pool.begin().await?;
+    let old = sqlx::query_as!(Npc, "SELECT * FROM npcs WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let npc = sqlx::query_as!(
+        Npc,
+        r#"
+        UPDATE npcs SET location_id = l.id, name = $3, description = $4, dialogue = $5
+        FROM locations l WHERE npcs.id = $1 AND l.id = $2
+        RETURNING npcs.*
+        "#,
+        id,
+        payload.location_id,
+        payload.name.trim(),
+        payload.description,
+        payload.dialogue
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Unknown location".to_string()))?;
+    tx.commit().await?;
+
+    // Переезд или переименование: старое присутствие убирается, новое показывается
+    if old.location_id != npc.location_id || old.name != npc.name || old.description != npc.description {
+        announce_presence(&state, &old, false).await;
+        announce_presence(&state, &npc, true).await;
+    }
+    Ok(Json(npc))
+}
+
+pub async fn delete_npc(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let npc = sqlx::query_as!(Npc, "DELETE FROM npcs WHERE id = $1 RETURNING *", id)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    announce_presence(&state, &npc, false).await;
+    tracing::info!("{} убрал NPC '{}'", claims.username, npc.name);
+    Ok(StatusCode::NO_CONTENT)
+}
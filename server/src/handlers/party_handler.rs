@@ -0,0 +1,143 @@
+// /server/src/handlers/party_handler.rs
+use crate::{auth::Claims, error::AppError, models::party::Party, state::AppState};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct PartyMember {
+    pub user_id: Uuid,
+    pub username: String,
+}
+
+#[derive(Serialize)]
+pub struct PartyResponse {
+    pub party: Party,
+    pub members: Vec<PartyMember>,
+}
+
+async fn load_party(state: &AppState, party_id: Uuid) -> Result<PartyResponse, AppError> {
+    let party = sqlx::query_as!(Party, "SELECT * FROM parties WHERE id = $1", party_id)
+        .fetch_one(&state.pool)
+        .await?;
+    let members = sqlx::query_as!(
+        PartyMember,
+        r#"
+        SELECT m.user_id, u.username FROM party_members m
+        JOIN users u ON u.id = m.user_id
+        WHERE m.party_id = $1
+        ORDER BY m.joined_at
+        "#,
+        party_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(PartyResponse { party, members })
+}
+
+/// Группа, в которой состоит игрок.
+pub async fn get_party(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<PartyResponse>, AppError> {
+    let party_id = sqlx::query_scalar!("SELECT party_id FROM party_members WHERE user_id = $1", claims.sub)
+        .fetch_one(&state.pool)
+        .await?;
+
+    Ok(Json(load_party(&state, party_id).await?))
+}
+
+/// Создает группу с текущим игроком во главе. Id группы лидер передает остальным.
+pub async fn create_party(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<PartyResponse>, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let party_id = sqlx::query_scalar!("INSERT INTO parties (leader_id) VALUES ($1) RETURNING id", claims.sub)
+        .fetch_one(&mut *tx)
+        .await?;
+    let joined = sqlx::query!(
+        "INSERT INTO party_members (user_id, party_id) VALUES ($1, $2) ON CONFLICT (user_id) DO NOTHING",
+        claims.sub,
+        party_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if joined.rows_affected() == 0 {
+        return Err(AppError::BadRequest("Already in a party".to_string()));
+    }
+    tx.commit().await?;
+
+    Ok(Json(load_party(&state, party_id).await?))
+}
+
+pub async fn join_party(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(party_id): Path<Uuid>,
+) -> Result<Json<PartyResponse>, AppError> {
+    let joined = sqlx::query!(
+        r#"
+        INSERT INTO party_members (user_id, party_id)
+        SELECT $1, id FROM parties WHERE id = $2
+        ON CONFLICT (user_id) DO NOTHING
+        "#,
+        claims.sub,
+        party_id
+    )
+    .execute(&state.pool)
+    .await?;
+    if joined.rows_affected() == 0 {
+        return Err(AppError::BadRequest("Party not found or already in a party".to_string()));
+    }
+
+    Ok(Json(load_party(&state, party_id).await?))
+}
+
+/// Выход из группы. Опустевшая группа распускается, ушедший лидер передает лидерство
+/// самому давнему участнику.
+pub async fn leave_party(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let party_id = sqlx::query_scalar!(
+        "DELETE FROM party_members WHERE user_id = $1 RETURNING party_id",
+        claims.sub
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let next_leader = sqlx::query_scalar!(
+        "SELECT user_id FROM party_members WHERE party_id = $1 ORDER BY joined_at LIMIT 1",
+        party_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    match next_leader {
+        None => {
+            sqlx::query!("DELETE FROM parties WHERE id = $1", party_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Some(next_leader) => {
+            sqlx::query!(
+                "UPDATE parties SET leader_id = $2 WHERE id = $1 AND leader_id = $3",
+                party_id,
+                next_leader,
+                claims.sub
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,28 +1,39 @@
 // /var/www/structure/server/src/handlers/player_handler.rs
 
 use crate::{
-    auth::Claims,
+    auth::{require_role, Claims},
     error::AppError,
-    models::{location::Location, player::Player},
+    handlers::{location_handler::LinkView, world_handler::SetFlagPayload},
+    models::{location::Location, party::LocationInstance, player::Player, user::UserRole},
     state::AppState,
-    // Импортируем `change_room` напрямую из его нового места
-    ws::utils::change_room,
+    world::{
+        capacity::{process_queue, queue_status, QueueStatus},
+        discovery::{claim_search, search_location},
+        i18n::{localize_links, localize_locations, Language},
+        instances,
+        inventory::fetch_player,
+        rules::RuleContext,
+        spawn::ensure_player_location,
+        transit,
+        zones::{fetch_location, fetch_locations},
+    },
 };
-use axum::{extract::State, http::StatusCode, Extension, Json};
-use serde::Deserialize;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 pub async fn get_player_status(
     State(state): State<AppState>,
     Extension(claims): Extension<Claims>,
 ) -> Result<Json<Player>, AppError> {
-    let player = sqlx::query_as!(
-        Player,
-        "SELECT * FROM players WHERE user_id = $1",
-        claims.sub
-    )
-    .fetch_one(&state.pool)
-    .await?;
+    ensure_player_location(&state, &claims).await?;
+    let player = fetch_player(&state.pool, claims.sub).await?;
 
     Ok(Json(player))
 }
@@ -30,6 +41,9 @@ pub async fn get_player_status(
 #[derive(Deserialize)]
 pub struct MovePayload {
     pub target_location_id: Uuid,
+    // Встать в очередь, если локация заполнена
+    #[serde(default)]
+    pub queue: bool,
 }
 
 pub async fn move_player(
@@ -37,48 +51,294 @@ pub async fn move_player(
     Extension(claims): Extension<Claims>,
     Json(payload): Json<MovePayload>,
 ) -> Result<StatusCode, AppError> {
-    // 1. Получаем текущее состояние игрока
-    let player = sqlx::query_as!(Player, "SELECT * FROM players WHERE user_id = $1", claims.sub)
-        .fetch_one(&state.pool)
-        .await?;
+    match transit::travel(&state, &claims, payload.target_location_id, payload.queue).await? {
+        // Долгий переход: игрок в пути и прибудет по таймеру
+        Some(_) => Ok(StatusCode::ACCEPTED),
+        None => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+/// Отменяет переход, пока игрок в пути: он возвращается туда, откуда вышел.
+pub async fn cancel_transit(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    transit::cancel_transit(&state, claims.sub, &claims.username).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Serialize)]
+pub struct JournalEntry {
+    pub location_id: Uuid,
+    pub name: String,
+    pub first_visited_at: DateTime<Utc>,
+    pub last_visited_at: DateTime<Utc>,
+    pub visit_count: i32,
+}
+
+#[derive(Serialize)]
+pub struct ZoneCompletion {
+    pub zone_id: Uuid,
+    pub zone_name: String,
+    pub discovered: i64,
+    pub total: i64,
+    pub completion_percent: f64,
+}
+
+#[derive(Serialize)]
+pub struct JournalResponse {
+    pub entries: Vec<JournalEntry>,
+    pub discovered: i64,
+    pub total: i64,
+    pub completion_percent: f64,
+    pub zones: Vec<ZoneCompletion>,
+}
+
+/// Журнал открытий: все посещенные локации и процент исследования мира и каждой зоны.
+pub async fn get_journal(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Language(language): Language,
+) -> Result<Json<JournalResponse>, AppError> {
+    let ctx = RuleContext::load(&state.pool, claims.sub, claims.role).await?;
 
-    // 2. Получаем данные о целевой локации
-    let target_location =
-        sqlx::query_as!(Location, "SELECT * FROM locations WHERE id = $1", payload.target_location_id)
-            .fetch_one(&state.pool)
-            .await?;
-
-    // 3. Проверяем, достаточно ли у игрока прав доступа
-    if player.access_level < target_location.security_level {
-        tracing::warn!(
-            "Попытка несанкционированного доступа от {} к локации {}",
-            claims.sub,
-            target_location.id
-        );
+    let visits = sqlx::query!(
+        r#"
+        SELECT location_id, first_visited_at, last_visited_at, visit_count
+        FROM player_visits
+        WHERE user_id = $1
+        ORDER BY first_visited_at
+        "#,
+        claims.sub
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let ids: Vec<Uuid> = visits.iter().map(|visit| visit.location_id).collect();
+    let mut locations = fetch_locations(&state.pool, &ids).await?;
+    localize_locations(&state.pool, &mut locations, &language, &state.config).await?;
+    let locations: HashMap<Uuid, Location> = locations
+        .into_iter()
+        .map(|location| (location.id, location))
+        .collect();
+
+    let entries: Vec<JournalEntry> = visits
+        .into_iter()
+        .filter_map(|visit| {
+            let location = locations.get(&visit.location_id)?;
+            Some(JournalEntry {
+                location_id: visit.location_id,
+                // Если доступ с тех пор понизили, название снова становится закрытым
+                name: if !ctx.clears(location.id, location.security_level) {
+                    "[[ДАННЫЕ ПОВРЕЖДЕНЫ]]".to_string()
+                } else {
+                    location.name.clone()
+                },
+                first_visited_at: visit.first_visited_at,
+                last_visited_at: visit.last_visited_at,
+                visit_count: visit.visit_count,
+            })
+        })
+        .collect();
+
+    // Чужие личные комнаты в прогресс не входят: иначе процент падал бы с каждой новой комнатой
+    let zones: Vec<ZoneCompletion> = sqlx::query!(
+        r#"
+        SELECT z.id, z.name, COUNT(l.id) AS "total!", COUNT(v.location_id) AS "discovered!"
+        FROM zones z
+        JOIN locations l ON l.zone_id = z.id AND (NOT l.personal OR l.owner_id = $1)
+        LEFT JOIN player_visits v ON v.location_id = l.id AND v.user_id = $1
+        GROUP BY z.id, z.name
+        ORDER BY z.name
+        "#,
+        claims.sub
+    )
+    .fetch_all(&state.pool)
+    .await?
+    .into_iter()
+    .map(|row| ZoneCompletion {
+        zone_id: row.id,
+        zone_name: row.name,
+        discovered: row.discovered,
+        total: row.total,
+        completion_percent: completion_percent(row.discovered, row.total),
+    })
+    .collect();
+
+    // Общий итог считается по тем же локациям, что и по зонам, поэтому посещенные
+    // чужие комнаты есть в журнале, но не в проценте
+    let total = zones.iter().map(|zone| zone.total).sum();
+    let discovered = zones.iter().map(|zone| zone.discovered).sum();
+    Ok(Json(JournalResponse {
+        entries,
+        discovered,
+        total,
+        completion_percent: completion_percent(discovered, total),
+        zones,
+    }))
+}
+
+fn completion_percent(discovered: i64, total: i64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (discovered as f64 / total as f64 * 1000.0).round() / 10.0
+}
+
+#[derive(Serialize)]
+pub struct SearchResponse {
+    pub found: Vec<LinkView>,
+}
+
+/// Команда "поиск": бросок на обнаружение скрытых переходов в текущей локации.
+/// Повторно обыскать ту же локацию можно не раньше, чем через `search_cooldown_secs`.
+pub async fn search(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Language(language): Language,
+) -> Result<Json<SearchResponse>, AppError> {
+    let player = fetch_player(&state.pool, claims.sub).await?;
+    let location_id = player.current_location_id.ok_or(AppError::NotFound)?;
+
+    // Обыскивать можно только локацию, к которой есть доступ
+    let location = fetch_location(&state.pool, location_id).await?;
+    let ctx = RuleContext::load(&state.pool, claims.sub, claims.role).await?;
+    if !ctx.clears(location.id, location.security_level) {
         return Err(AppError::Unauthorized);
     }
 
-    // Запоминаем ID старой локации для передачи в change_room
-    let old_location_id = player.current_location_id;
+    if let Some(secs) = claim_search(&state.pool, claims.sub, location_id, state.config.search_cooldown_secs).await? {
+        return Err(AppError::AccessDenied(format!("ВЫ УЖЕ ОБЫСКИВАЛИ ЭТО МЕСТО. ПОВТОРИТЕ ЧЕРЕЗ {} С.", secs)));
+    }
+    let mut found = search_location(&state.pool, claims.sub, location_id, &ctx).await?;
+    if !found.is_empty() {
+        tracing::info!("{} обнаружил {} скрытых переходов в {}", claims.username, found.len(), location_id);
+    }
+
+    localize_links(&state.pool, &mut found, &language, &state.config).await?;
+    Ok(Json(SearchResponse {
+        found: found.into_iter().map(|link| LinkView::new(link, &ctx)).collect(),
+    }))
+}
 
-    // Обновляем позицию игрока в базе данных
-    sqlx::query!(
-        "UPDATE players SET current_location_id = $1 WHERE user_id = $2",
-        payload.target_location_id,
+/// Копия инстанцированной локации, в которой сейчас находится игрок.
+pub async fn get_current_instance(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<LocationInstance>, AppError> {
+    let instance = sqlx::query_as!(
+        LocationInstance,
+        r#"
+        SELECT i.* FROM location_instances i
+        JOIN players p ON p.current_instance_id = i.id
+        WHERE p.user_id = $1
+        "#,
         claims.sub
     )
-    .execute(&state.pool)
+    .fetch_one(&state.pool)
     .await?;
 
-    // Вызываем единую функцию для обновления состояния WebSocket.
-    // Она сама позаботится о рассылке уведомлений о выходе и входе.
-    change_room(
-        &state,
-        &claims, // Передаем `claims` целиком
-        old_location_id,
-        payload.target_location_id,
+    Ok(Json(instance))
+}
+
+/// Меняет ключ состояния копии локации - например, открывает дверь в копии одной группы,
+/// не трогая флаги мира. Только для Архитекторов.
+pub async fn set_instance_state(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((id, key)): Path<(Uuid, String)>,
+    Json(payload): Json<SetFlagPayload>,
+) -> Result<Json<LocationInstance>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    if key.trim().is_empty() {
+        return Err(AppError::BadRequest("State key cannot be empty".to_string()));
+    }
+    if !instances::set_instance_state(&state.pool, id, &key, &payload.value).await? {
+        return Err(AppError::NotFound);
+    }
+
+    let instance = sqlx::query_as!(LocationInstance, "SELECT * FROM location_instances WHERE id = $1", id)
+        .fetch_one(&state.pool)
+        .await?;
+    tracing::info!("{} установил в копии {} состояние {} = {}", claims.username, id, key, payload.value);
+    Ok(Json(instance))
+}
+
+#[derive(Deserialize)]
+pub struct LanguagePayload {
+    /// `None` - снова определять язык по заголовку Accept-Language.
+    pub language: Option<String>,
+}
+
+/// Сохраняет язык интерфейса и контента, выбранный игроком.
+pub async fn set_language(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<LanguagePayload>,
+) -> Result<StatusCode, AppError> {
+    let language = payload.language.map(|language| language.to_ascii_lowercase());
+    if let Some(language) = language.as_ref().filter(|language| !state.config.supported_languages.contains(language)) {
+        return Err(AppError::BadRequest(format!("Unsupported language '{}'", language)));
+    }
+
+    sqlx::query!("UPDATE users SET preferred_language = $1 WHERE id = $2", language, claims.sub)
+        .execute(&state.pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Место игрока в очереди на вход в заполненную локацию.
+pub async fn get_queue(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<QueueStatus>, AppError> {
+    let mut conn = state.pool.acquire().await?;
+    let status = queue_status(&mut conn, claims.sub).await?.ok_or(AppError::NotFound)?;
+    Ok(Json(status))
+}
+
+/// Выход из очереди. Если за игроком была бронь, место сразу достается следующему.
+pub async fn leave_queue(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, AppError> {
+    let location_id = sqlx::query_scalar!(
+        "DELETE FROM location_queue WHERE user_id = $1 RETURNING location_id",
+        claims.sub
     )
-    .await;
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
 
+    process_queue(&state, location_id).await?;
     Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize)]
+pub struct CreditsPayload {
+    /// Изменение баланса: положительное начисляет, отрицательное списывает.
+    pub amount: i64,
+}
+
+/// Начисляет или списывает кредиты игроку. Только для Архитекторов.
+pub async fn adjust_credits(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<CreditsPayload>,
+) -> Result<Json<i64>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let credits = sqlx::query_scalar!(
+        "UPDATE players SET credits = credits + $2 WHERE user_id = $1 AND credits + $2 >= 0 RETURNING credits",
+        user_id,
+        payload.amount
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Unknown player or insufficient credits".to_string()))?;
+
+    tracing::info!("{} изменил баланс игрока {} на {}", claims.username, user_id, payload.amount);
+    Ok(Json(credits))
+}
@@ -0,0 +1,422 @@
+// /server/src/handlers/quest_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{
+        quest::{ObjectiveKind, ObjectiveProgress, PlayerQuest, Quest, QuestDetails, QuestObjective, QuestRewardItem},
+        user::UserRole,
+    },
+    state::AppState,
+    world::quests::{emit_quest_events, QuestEvent},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Дополняет задания целями и наградами.
+async fn load_details(pool: &PgPool, quests: Vec<Quest>) -> Result<Vec<QuestDetails>, AppError> {
+    let ids: Vec<Uuid> = quests.iter().map(|quest| quest.id).collect();
+    let mut objectives = sqlx::query_as!(
+        QuestObjective,
+        r#"
+        SELECT id, quest_id, position, kind AS "kind: _", description, location_id, item_id, quantity,
+               npc_id, access_level
+        FROM quest_objectives WHERE quest_id = ANY($1)
+        ORDER BY quest_id, position
+        "#,
+        &ids
+    )
+    .fetch_all(pool)
+    .await?;
+    let rewards = sqlx::query!(
+        "SELECT quest_id, item_id, quantity FROM quest_reward_items WHERE quest_id = ANY($1) ORDER BY item_id",
+        &ids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let details = quests
+        .into_iter()
+        .map(|quest| {
+            let (own, rest): (Vec<_>, Vec<_>) = objectives.drain(..).partition(|o| o.quest_id == quest.id);
+            objectives = rest;
+            let reward_items = rewards
+                .iter()
+                .filter(|reward| reward.quest_id == quest.id)
+                .map(|reward| QuestRewardItem { item_id: reward.item_id.clone(), quantity: reward.quantity })
+                .collect();
+            QuestDetails { quest, objectives: own, reward_items }
+        })
+        .collect();
+
+    Ok(details)
+}
+
+/// Все задания. Только для Архитекторов.
+pub async fn list_quests(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<QuestDetails>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let quests = sqlx::query_as!(Quest, "SELECT * FROM quests ORDER BY title")
+        .fetch_all(&state.pool)
+        .await?;
+
+    Ok(Json(load_details(&state.pool, quests).await?))
+}
+
+/// Задания, которые игрок еще не брал.
+pub async fn list_available_quests(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<QuestDetails>>, AppError> {
+    let quests = sqlx::query_as!(
+        Quest,
+        r#"
+        SELECT * FROM quests q
+        WHERE NOT EXISTS (SELECT 1 FROM player_quests pq WHERE pq.quest_id = q.id AND pq.user_id = $1)
+        ORDER BY title
+        "#,
+        claims.sub
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(load_details(&state.pool, quests).await?))
+}
+
+pub async fn accept_quest(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let accepted = sqlx::query!(
+        r#"
+        INSERT INTO player_quests (user_id, quest_id)
+        SELECT $1, id FROM quests WHERE id = $2
+        ON CONFLICT DO NOTHING
+        "#,
+        claims.sub,
+        id
+    )
+    .execute(&state.pool)
+    .await?;
+    if accepted.rows_affected() == 0 {
+        let exists = sqlx::query_scalar!("SELECT id FROM quests WHERE id = $1", id)
+            .fetch_optional(&state.pool)
+            .await?;
+        return match exists {
+            Some(_) => Err(AppError::AccessDenied("ЗАДАНИЕ УЖЕ ВЗЯТО.".to_string())),
+            None => Err(AppError::NotFound),
+        };
+    }
+
+    emit_quest_events(&state, claims.sub, vec![QuestEvent::Accepted(id)]).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Serialize)]
+pub struct QuestLog {
+    pub active: Vec<PlayerQuest>,
+    pub completed: Vec<PlayerQuest>,
+}
+
+/// Журнал заданий игрока: активные и завершенные.
+pub async fn get_quest_log(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<QuestLog>, AppError> {
+    let quests = sqlx::query!(
+        r#"
+        SELECT q.id, q.title, q.description, pq.started_at, pq.completed_at
+        FROM player_quests pq
+        JOIN quests q ON q.id = pq.quest_id
+        WHERE pq.user_id = $1
+        ORDER BY pq.started_at DESC
+        "#,
+        claims.sub
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let objectives = sqlx::query!(
+        r#"
+        SELECT o.id, o.quest_id, o.kind AS "kind: ObjectiveKind", o.description,
+               (po.objective_id IS NOT NULL) AS "completed!"
+        FROM quest_objectives o
+        JOIN player_quests pq ON pq.quest_id = o.quest_id AND pq.user_id = $1
+        LEFT JOIN player_objectives po ON po.objective_id = o.id AND po.user_id = $1
+        ORDER BY o.quest_id, o.position
+        "#,
+        claims.sub
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let (completed, active): (Vec<PlayerQuest>, Vec<PlayerQuest>) = quests
+        .into_iter()
+        .map(|quest| PlayerQuest {
+            objectives: objectives
+                .iter()
+                .filter(|o| o.quest_id == quest.id)
+                .map(|o| ObjectiveProgress {
+                    id: o.id,
+                    kind: o.kind,
+                    description: o.description.clone(),
+                    completed: o.completed,
+                })
+                .collect(),
+            quest_id: quest.id,
+            title: quest.title,
+            description: quest.description,
+            started_at: quest.started_at,
+            completed_at: quest.completed_at,
+        })
+        .partition(|quest| quest.completed_at.is_some());
+
+    Ok(Json(QuestLog { active, completed }))
+}
+
+#[derive(Deserialize)]
+pub struct ObjectivePayload {
+    pub kind: ObjectiveKind,
+    #[serde(default)]
+    pub description: String,
+    pub location_id: Option<Uuid>,
+    pub item_id: Option<String>,
+    #[serde(default = "default_quantity")]
+    pub quantity: i32,
+    pub npc_id: Option<Uuid>,
+    pub access_level: Option<i32>,
+}
+
+fn default_quantity() -> i32 {
+    1
+}
+
+#[derive(Deserialize)]
+pub struct QuestPayload {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub objectives: Vec<ObjectivePayload>,
+    #[serde(default)]
+    pub reward_items: Vec<QuestRewardItem>,
+    pub reward_access_level: Option<i32>,
+    #[serde(default)]
+    pub reward_flags: Map<String, Value>,
+}
+
+impl QuestPayload {
+    async fn validate(&self, pool: &PgPool) -> Result<(), AppError> {
+        if self.title.trim().is_empty() {
+            return Err(AppError::BadRequest("Quest title cannot be empty".to_string()));
+        }
+        if self.objectives.is_empty() {
+            return Err(AppError::BadRequest("Quest needs at least one objective".to_string()));
+        }
+
+        let mut item_ids: Vec<String> = self.reward_items.iter().map(|reward| reward.item_id.clone()).collect();
+        let mut location_ids = Vec::new();
+        let mut npc_ids = Vec::new();
+        for (index, objective) in self.objectives.iter().enumerate() {
+            let missing = match objective.kind {
+                ObjectiveKind::VisitLocation => objective.location_id.is_none().then_some("location_id"),
+                ObjectiveKind::ObtainItem => objective.item_id.is_none().then_some("item_id"),
+                ObjectiveKind::TalkToNpc => objective.npc_id.is_none().then_some("npc_id"),
+                ObjectiveKind::ReachAccessLevel => objective.access_level.is_none().then_some("access_level"),
+            };
+            if let Some(field) = missing {
+                return Err(AppError::BadRequest(format!("Objective {} needs {}", index + 1, field)));
+            }
+            if objective.quantity <= 0 {
+                return Err(AppError::BadRequest(format!("Objective {} quantity must be positive", index + 1)));
+            }
+            item_ids.extend(objective.item_id.clone());
+            location_ids.extend(objective.location_id);
+            npc_ids.extend(objective.npc_id);
+        }
+        if self.reward_items.iter().any(|reward| reward.quantity <= 0) {
+            return Err(AppError::BadRequest("Reward quantities must be positive".to_string()));
+        }
+
+        item_ids.sort();
+        item_ids.dedup();
+        let known_items = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM item_definitions WHERE id = ANY($1)"#,
+            &item_ids
+        )
+        .fetch_one(pool)
+        .await?;
+        if known_items != item_ids.len() as i64 {
+            return Err(AppError::BadRequest("Quest refers to unknown items".to_string()));
+        }
+
+        location_ids.sort();
+        location_ids.dedup();
+        let known_locations = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM locations WHERE id = ANY($1)"#,
+            &location_ids
+        )
+        .fetch_one(pool)
+        .await?;
+        if known_locations != location_ids.len() as i64 {
+            return Err(AppError::BadRequest("Quest refers to unknown locations".to_string()));
+        }
+
+        npc_ids.sort();
+        npc_ids.dedup();
+        let known_npcs = sqlx::query_scalar!(r#"SELECT COUNT(*) AS "count!" FROM npcs WHERE id = ANY($1)"#, &npc_ids)
+            .fetch_one(pool)
+            .await?;
+        if known_npcs != npc_ids.len() as i64 {
+            return Err(AppError::BadRequest("Quest refers to unknown NPCs".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Записывает цели и награды задания, заменяя прежние.
+async fn store_quest_parts(
+    conn: &mut sqlx::PgConnection,
+    quest_id: Uuid,
+    payload: &QuestPayload,
+) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM quest_objectives WHERE quest_id = $1", quest_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!("DELETE FROM quest_reward_items WHERE quest_id = $1", quest_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for (position, objective) in payload.objectives.iter().enumerate() {
+        sqlx::query!(
+            r#"
+            INSERT INTO quest_objectives
+                (quest_id, position, kind, description, location_id, item_id, quantity, npc_id, access_level)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            quest_id,
+            position as i32,
+            objective.kind as ObjectiveKind,
+            objective.description,
+            objective.location_id,
+            objective.item_id,
+            objective.quantity,
+            objective.npc_id,
+            objective.access_level
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    for reward in &payload.reward_items {
+        sqlx::query!(
+            r#"
+            INSERT INTO quest_reward_items (quest_id, item_id, quantity) VALUES ($1, $2, $3)
+            ON CONFLICT (quest_id, item_id) DO UPDATE SET quantity = quest_reward_items.quantity + EXCLUDED.quantity
+            "#,
+            quest_id,
+            reward.item_id,
+            reward.quantity
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn fetch_details(pool: &PgPool, id: Uuid) -> Result<QuestDetails, AppError> {
+    let quest = sqlx::query_as!(Quest, "SELECT * FROM quests WHERE id = $1", id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    load_details(pool, vec![quest]).await?.pop().ok_or(AppError::NotFound)
+}
+
+pub async fn create_quest(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<QuestPayload>,
+) -> Result<Json<QuestDetails>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    payload.validate(&state.pool).await?;
+
+    let mut tx = state.pool.begin().await?;
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO quests (title, description, reward_access_level, reward_flags, creator_id)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+        payload.title.trim(),
+        payload.description,
+        payload.reward_access_level,
+        Value::Object(payload.reward_flags.clone()),
+        claims.sub
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    store_quest_parts(&mut tx, id, &payload).await?;
+    tx.commit().await?;
+
+    Ok(Json(fetch_details(&state.pool, id).await?))
+}
+
+/// Заменяет задание целиком. Прогресс игроков по целям при этом сбрасывается,
+/// завершенные задания остаются завершенными.
+pub async fn update_quest(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<QuestPayload>,
+) -> Result<Json<QuestDetails>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    payload.validate(&state.pool).await?;
+
+    let mut tx = state.pool.begin().await?;
+    let result = sqlx::query!(
+        r#"
+        UPDATE quests SET title = $2, description = $3, reward_access_level = $4, reward_flags = $5
+        WHERE id = $1
+        "#,
+        id,
+        payload.title.trim(),
+        payload.description,
+        payload.reward_access_level,
+        Value::Object(payload.reward_flags.clone())
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+    store_quest_parts(&mut tx, id, &payload).await?;
+    tx.commit().await?;
+
+    Ok(Json(fetch_details(&state.pool, id).await?))
+}
+
+pub async fn delete_quest(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let result = sqlx::query!("DELETE FROM quests WHERE id = $1", id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,442 @@
+// /server/src/handlers/room_handler.rs
+
+// Личные комнаты игроков и списки доступа локаций.
+//
+// Игрок может создать ограниченное число комнат (MAX_PERSONAL_ROOMS). Комната
+// появляется в зоне публичного узла, к которому привязана, с уровнем секретности 0:
+// кто в нее войдет, решает только ее список доступа (см. world::acl).
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::{
+        acl::{AclEntry, AclEntryKind, EntryPolicy},
+        location::Location,
+        user::UserRole,
+    },
+    state::AppState,
+    world::{
+        acl::require_location_manager,
+        quests::{emit_quest_events, QuestEvent},
+        spawn::respawn_player,
+        templates::validate_template,
+        trade::{cancel_trade, notify_trade_cancelled, CANCELLED_LEFT_ROOM},
+        zones::{fetch_location, fetch_locations},
+    },
+    ws::{
+        state::RoomKey,
+        utils::{change_room, send_to_user},
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+const MAX_ROOM_NAME_CHARS: usize = 100;
+
+/// Связывает комнату с публичным узлом переходами в обе стороны (если их еще нет).
+async fn link_to_hub(conn: &mut PgConnection, room: &Location, hub_id: Uuid) -> Result<(), AppError> {
+    let hub = fetch_location(&mut *conn, hub_id).await?;
+    if !hub.public_hub {
+        return Err(AppError::BadRequest("Rooms can only be linked to public hubs".to_string()));
+    }
+
+    for (source, target) in [(room, &hub), (&hub, room)] {
+        sqlx::query!(
+            r#"
+            INSERT INTO location_links (source_location_id, target_location_id, link_text)
+            SELECT $1, $2, $3
+            WHERE NOT EXISTS (
+                SELECT 1 FROM location_links WHERE source_location_id = $1 AND target_location_id = $2
+            )
+            "#,
+            source.id,
+            target.id,
+            target.name
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct RoomPayload {
+    pub name: String,
+    pub description: String,
+}
+
+impl RoomPayload {
+    fn validate(&self) -> Result<&str, AppError> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > MAX_ROOM_NAME_CHARS {
+            return Err(AppError::BadRequest(format!(
+                "Room name must be 1 to {} characters long",
+                MAX_ROOM_NAME_CHARS
+            )));
+        }
+        validate_template("description", &self.description)?;
+        Ok(name)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ClaimRoomPayload {
+    #[serde(flatten)]
+    pub room: RoomPayload,
+    pub hub_location_id: Uuid,
+}
+
+/// Личные комнаты игрока.
+pub async fn list_player_rooms(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<Location>>, AppError> {
+    let ids = sqlx::query_scalar!(
+        "SELECT id FROM locations WHERE owner_id = $1 AND personal ORDER BY created_at",
+        claims.sub
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(fetch_locations(&state.pool, &ids).await?))
+}
+
+/// Создает личную комнату игрока рядом с публичным узлом.
+pub async fn claim_room(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ClaimRoomPayload>,
+) -> Result<Json<Location>, AppError> {
+    let name = payload.room.validate()?;
+
+    // Строка игрока блокируется, чтобы одновременные запросы не превысили лимит
+    let mut tx = state.pool.begin().await?;
+    sqlx::query!("SELECT user_id FROM players WHERE user_id = $1 FOR UPDATE", claims.sub)
+        .fetch_one(&mut *tx)
+        .await?;
+    let owned = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM locations WHERE owner_id = $1 AND personal"#,
+        claims.sub
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if owned >= state.config.max_personal_rooms {
+        return Err(AppError::AccessDenied(format!(
+            "ЛИМИТ ЛИЧНЫХ КОМНАТ ИСЧЕРПАН: {}.",
+            state.config.max_personal_rooms
+        )));
+    }
+
+    let hub = fetch_location(&mut *tx, payload.hub_location_id).await?;
+    let id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO locations (name, description, security_level, creator_id, owner_id, zone_id, personal)
+        VALUES ($1, $2, 0, $3, $3, $4, TRUE)
+        RETURNING id
+        "#,
+        name,
+        payload.room.description,
+        claims.sub,
+        hub.zone_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let room = fetch_location(&mut *tx, id).await?;
+    link_to_hub(&mut tx, &room, hub.id).await?;
+    tx.commit().await?;
+
+    tracing::info!("{} создал личную комнату {} у узла {}", claims.username, id, hub.id);
+    Ok(Json(room))
+}
+
+/// Меняет название и описание комнаты. Владелец или редактор.
+pub async fn update_room(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<RoomPayload>,
+) -> Result<Json<Location>, AppError> {
+    let name = payload.validate()?;
+    let room = fetch_location(&state.pool, id).await?;
+    require_location_manager(&state.pool, &room, &claims, true).await?;
+
+    sqlx::query!(
+        "UPDATE locations SET name = $2, description = $3 WHERE id = $1",
+        id,
+        name,
+        payload.description
+    )
+    .execute(&state.pool)
+    .await?;
+
+    Ok(Json(fetch_location(&state.pool, id).await?))
+}
+
+/// Удаляет личную комнату. Только владелец; оказавшиеся внутри возвращаются в точку появления
+/// в той же транзакции, а их WS-клиенты переводятся в новую комнату.
+pub async fn delete_room(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let room = fetch_location(&state.pool, id).await?;
+    if !room.personal {
+        return Err(AppError::NotFound);
+    }
+    require_location_manager(&state.pool, &room, &claims, false).await?;
+
+    let mut tx = state.pool.begin().await?;
+    // Сделки блокируются раньше строк игроков, как и везде (см. world::trade)
+    let trade_ids = sqlx::query_scalar!("SELECT id FROM trades WHERE location_id = $1 ORDER BY id FOR UPDATE", id)
+        .fetch_all(&mut *tx)
+        .await?;
+    let occupants = sqlx::query!(
+        r#"
+        SELECT p.user_id, p.current_instance_id, u.username, u.role AS "role: UserRole"
+        FROM players p JOIN users u ON u.id = p.user_id
+        WHERE p.current_location_id = $1
+        ORDER BY p.user_id
+        FOR UPDATE OF p
+        "#,
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    // Игроки, шедшие в комнату: их путь удалится вместе с ней, и они останутся в исходной локации
+    let incoming = sqlx::query!(
+        r#"
+        SELECT t.user_id, u.username, p.current_location_id AS "location_id!", p.current_instance_id
+        FROM player_transits t
+        JOIN players p ON p.user_id = t.user_id
+        JOIN users u ON u.id = t.user_id
+        WHERE t.target_location_id = $1 AND t.source_location_id <> $1
+        FOR UPDATE OF t
+        "#,
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut cancelled_trades = Vec::with_capacity(trade_ids.len());
+    for trade_id in trade_ids {
+        cancelled_trades.push((trade_id, cancel_trade(&mut tx, trade_id).await?));
+    }
+    // Сначала удаляем комнату, чтобы точкой появления не оказалась она сама
+    sqlx::query!("DELETE FROM locations WHERE id = $1", id)
+        .execute(&mut *tx)
+        .await?;
+    let mut moves = Vec::with_capacity(occupants.len());
+    for occupant in &occupants {
+        let spawn_id = respawn_player(&mut tx, occupant.user_id, occupant.role, &state.config).await?;
+        moves.push(spawn_id);
+    }
+    tx.commit().await?;
+
+    for (trade_id, participants) in cancelled_trades {
+        notify_trade_cancelled(&state, trade_id, &participants, CANCELLED_LEFT_ROOM).await;
+    }
+    for (occupant, spawn_id) in occupants.iter().zip(moves) {
+        change_room(
+            &state,
+            occupant.user_id,
+            &occupant.username,
+            Some(RoomKey::new(id, occupant.current_instance_id)),
+            RoomKey::new(spawn_id, None),
+        )
+        .await;
+        emit_quest_events(&state, occupant.user_id, vec![QuestEvent::Visited(spawn_id)]).await;
+    }
+    for player in incoming {
+        let room = RoomKey::new(player.location_id, player.current_instance_id);
+        change_room(&state, player.user_id, &player.username, None, room).await;
+        let message = serde_json::json!({ "type": "transit_cancelled", "location_id": player.location_id });
+        send_to_user(&state, player.user_id, message.to_string()).await;
+    }
+
+    tracing::info!(
+        "{} удалил личную комнату {}, игроков возвращено в точку появления: {}",
+        claims.username,
+        id,
+        occupants.len()
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct HubPayload {
+    pub hub_location_id: Uuid,
+}
+
+/// Привязывает личную комнату еще к одному публичному узлу.
+pub async fn link_room(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<HubPayload>,
+) -> Result<StatusCode, AppError> {
+    let room = fetch_location(&state.pool, id).await?;
+    if !room.personal {
+        return Err(AppError::NotFound);
+    }
+    require_location_manager(&state.pool, &room, &claims, true).await?;
+
+    let mut tx = state.pool.begin().await?;
+    link_to_hub(&mut tx, &room, payload.hub_location_id).await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Убирает переходы между личной комнатой и узлом в обе стороны.
+pub async fn unlink_room(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((id, hub_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    let room = fetch_location(&state.pool, id).await?;
+    if !room.personal {
+        return Err(AppError::NotFound);
+    }
+    require_location_manager(&state.pool, &room, &claims, true).await?;
+
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM location_links
+        WHERE (source_location_id = $1 AND target_location_id = $2)
+           OR (source_location_id = $2 AND target_location_id = $1)
+        "#,
+        id,
+        hub_id
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Serialize)]
+pub struct AclResponse {
+    pub owner_id: Option<Uuid>,
+    pub entry_policy: EntryPolicy,
+    pub entries: Vec<AclEntry>,
+}
+
+/// Список доступа локации. Владелец, редакторы и Архитекторы.
+pub async fn get_acl(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+) -> Result<Json<AclResponse>, AppError> {
+    let location = fetch_location(&state.pool, location_id).await?;
+    require_location_manager(&state.pool, &location, &claims, true).await?;
+
+    let entries = sqlx::query_as!(
+        AclEntry,
+        r#"
+        SELECT a.user_id, u.username, a.kind AS "kind: _", a.added_by, a.created_at
+        FROM location_acl a JOIN users u ON u.id = a.user_id
+        WHERE a.location_id = $1
+        ORDER BY a.kind, u.username
+        "#,
+        location_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(AclResponse { owner_id: location.owner_id, entry_policy: location.entry_policy, entries }))
+}
+
+#[derive(Deserialize)]
+pub struct EntryPolicyPayload {
+    pub entry_policy: EntryPolicy,
+}
+
+pub async fn set_entry_policy(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+    Json(payload): Json<EntryPolicyPayload>,
+) -> Result<StatusCode, AppError> {
+    let location = fetch_location(&state.pool, location_id).await?;
+    require_location_manager(&state.pool, &location, &claims, true).await?;
+
+    sqlx::query!(
+        "UPDATE locations SET entry_policy = $2 WHERE id = $1",
+        location_id,
+        payload.entry_policy as EntryPolicy
+    )
+    .execute(&state.pool)
+    .await?;
+
+    tracing::info!("{} сменил режим входа в {} на {:?}", claims.username, location_id, payload.entry_policy);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct AclEntryPayload {
+    pub kind: AclEntryKind,
+}
+
+/// Добавляет игрока в список доступа. Назначать редакторов может только владелец.
+pub async fn add_acl_entry(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((location_id, user_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<AclEntryPayload>,
+) -> Result<StatusCode, AppError> {
+    let location = fetch_location(&state.pool, location_id).await?;
+    let editors = payload.kind != AclEntryKind::Editor;
+    require_location_manager(&state.pool, &location, &claims, editors).await?;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO location_acl (location_id, user_id, kind, added_by)
+        SELECT $1, id, $3, $4 FROM users WHERE id = $2
+        ON CONFLICT DO NOTHING
+        "#,
+        location_id,
+        user_id,
+        payload.kind as AclEntryKind,
+        claims.sub
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::BadRequest("Unknown user or entry already exists".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn remove_acl_entry(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((location_id, user_id, kind)): Path<(Uuid, Uuid, AclEntryKind)>,
+) -> Result<StatusCode, AppError> {
+    let location = fetch_location(&state.pool, location_id).await?;
+    let editors = kind != AclEntryKind::Editor;
+    require_location_manager(&state.pool, &location, &claims, editors).await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM location_acl WHERE location_id = $1 AND user_id = $2 AND kind = $3",
+        location_id,
+        user_id,
+        kind as AclEntryKind
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
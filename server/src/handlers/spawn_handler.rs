@@ -0,0 +1,90 @@
+// /server/src/handlers/spawn_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{spawn_point::SpawnPoint, user::UserRole},
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Точки появления. Только для Архитекторов.
+pub async fn list_spawn_points(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<SpawnPoint>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let points = sqlx::query_as!(
+        SpawnPoint,
+        r#"SELECT id, location_id, weight, role AS "role: _", created_at FROM spawn_points ORDER BY role NULLS FIRST, created_at"#
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(points))
+}
+
+#[derive(Deserialize)]
+pub struct SpawnPointPayload {
+    pub location_id: Uuid,
+    #[serde(default = "default_weight")]
+    pub weight: i32,
+    // Например, Architect - чтобы Архитекторы появлялись в отдельной зоне
+    pub role: Option<UserRole>,
+}
+
+fn default_weight() -> i32 {
+    1
+}
+
+pub async fn create_spawn_point(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<SpawnPointPayload>,
+) -> Result<Json<SpawnPoint>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    if payload.weight <= 0 {
+        return Err(AppError::BadRequest("Weight must be positive".to_string()));
+    }
+
+    let point = sqlx::query_as!(
+        SpawnPoint,
+        r#"
+        INSERT INTO spawn_points (location_id, weight, role)
+        SELECT id, $2, $3 FROM locations WHERE id = $1
+        RETURNING id, location_id, weight, role AS "role: _", created_at
+        "#,
+        payload.location_id,
+        payload.weight,
+        payload.role as Option<UserRole>
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Unknown location".to_string()))?;
+
+    tracing::info!("{} добавил точку появления в локации {}", claims.username, point.location_id);
+    Ok(Json(point))
+}
+
+pub async fn delete_spawn_point(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let result = sqlx::query!("DELETE FROM spawn_points WHERE id = $1", id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
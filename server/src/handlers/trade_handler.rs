@@ -0,0 +1,185 @@
+// /server/src/handlers/trade_handler.rs
+use crate::{
+    auth::Claims,
+    error::AppError,
+    state::AppState,
+    world::{
+        quests::{emit_quest_events, QuestEvent},
+        trade::{
+            any_confirmed, cancel_trade, confirm_trade, escrow_item, find_player_trade, load_trade, lock_trade,
+            notify_trade, notify_trade_cancelled, participants_together, propose_trade, release_item,
+            set_escrow_credits, Trade, CANCELLED_BY_PLAYER, CANCELLED_LEFT_ROOM, CANCELLED_OFFER_CHANGED,
+        },
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::Deserialize;
+use sqlx::{Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct ProposePayload {
+    pub partner_id: Uuid,
+}
+
+/// Предлагает обмен игроку в той же комнате.
+pub async fn propose(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ProposePayload>,
+) -> Result<Json<Trade>, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let trade_id = propose_trade(&mut tx, claims.sub, payload.partner_id).await?;
+    let trade = load_trade(&mut tx, trade_id).await?;
+    tx.commit().await?;
+
+    tracing::info!("{} предложил обмен игроку {}", claims.username, payload.partner_id);
+    notify_trade(&state, &trade, "trade_proposed").await;
+    Ok(Json(trade))
+}
+
+pub async fn get_current_trade(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Trade>, AppError> {
+    let mut conn = state.pool.acquire().await?;
+    let trade_id = find_player_trade(&mut conn, claims.sub).await?.ok_or(AppError::NotFound)?;
+    Ok(Json(load_trade(&mut conn, trade_id).await?))
+}
+
+/// Открывает транзакцию изменения предложения. Если сделку уже кто-то подтвердил,
+/// изменение ее отменяет (залог возвращается), а игрок получает отказ.
+async fn begin_offer_change(
+    state: &AppState,
+    claims: &Claims,
+    trade_id: Uuid,
+) -> Result<Transaction<'static, Postgres>, AppError> {
+    let mut tx = state.pool.begin().await?;
+    lock_trade(&mut tx, trade_id, claims.sub).await?;
+    if any_confirmed(&mut tx, trade_id).await? {
+        let participants = cancel_trade(&mut tx, trade_id).await?;
+        tx.commit().await?;
+        notify_trade_cancelled(state, trade_id, &participants, CANCELLED_OFFER_CHANGED).await;
+        return Err(AppError::AccessDenied(CANCELLED_OFFER_CHANGED.to_string()));
+    }
+    Ok(tx)
+}
+
+async fn finish_offer_change(
+    state: &AppState,
+    mut tx: Transaction<'static, Postgres>,
+    trade_id: Uuid,
+) -> Result<Json<Trade>, AppError> {
+    let trade = load_trade(&mut tx, trade_id).await?;
+    tx.commit().await?;
+
+    notify_trade(state, &trade, "trade_updated").await;
+    Ok(Json(trade))
+}
+
+#[derive(Deserialize)]
+pub struct OfferItemPayload {
+    /// Экземпляр из инвентаря.
+    pub inventory_item_id: Uuid,
+    /// Без количества выставляется вся стопка.
+    pub quantity: Option<i32>,
+}
+
+pub async fn offer_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(trade_id): Path<Uuid>,
+    Json(payload): Json<OfferItemPayload>,
+) -> Result<Json<Trade>, AppError> {
+    let mut tx = begin_offer_change(&state, &claims, trade_id).await?;
+    escrow_item(&mut tx, trade_id, claims.sub, payload.inventory_item_id, payload.quantity).await?;
+    finish_offer_change(&state, tx, trade_id).await
+}
+
+/// Забирает предмет из своего предложения (`escrow_id` - позиция в залоге).
+pub async fn withdraw_item(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((trade_id, escrow_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Trade>, AppError> {
+    let mut tx = begin_offer_change(&state, &claims, trade_id).await?;
+    release_item(&mut tx, trade_id, claims.sub, escrow_id).await?;
+    finish_offer_change(&state, tx, trade_id).await
+}
+
+#[derive(Deserialize)]
+pub struct OfferCreditsPayload {
+    pub credits: i64,
+}
+
+pub async fn offer_credits(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(trade_id): Path<Uuid>,
+    Json(payload): Json<OfferCreditsPayload>,
+) -> Result<Json<Trade>, AppError> {
+    let mut tx = begin_offer_change(&state, &claims, trade_id).await?;
+    set_escrow_credits(&mut tx, trade_id, claims.sub, payload.credits).await?;
+    finish_offer_change(&state, tx, trade_id).await
+}
+
+/// Подтверждает сделку. Когда подтвердили оба, обмен совершается в этой же транзакции.
+pub async fn confirm(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(trade_id): Path<Uuid>,
+) -> Result<Json<Trade>, AppError> {
+    let mut tx = state.pool.begin().await?;
+    lock_trade(&mut tx, trade_id, claims.sub).await?;
+
+    // Уход из комнаты отменяет сделку сам, но игрока могли переместить и иначе (например, при возрождении)
+    if !participants_together(&mut tx, trade_id).await? {
+        let participants = cancel_trade(&mut tx, trade_id).await?;
+        tx.commit().await?;
+        notify_trade_cancelled(&state, trade_id, &participants, CANCELLED_LEFT_ROOM).await;
+        return Err(AppError::AccessDenied(CANCELLED_LEFT_ROOM.to_string()));
+    }
+
+    // Состояние до обмена: после него сделки уже нет
+    let trade = load_trade(&mut tx, trade_id).await?;
+    let completed = confirm_trade(&mut tx, trade_id, claims.sub).await?;
+    let trade = if completed { trade } else { load_trade(&mut tx, trade_id).await? };
+    tx.commit().await?;
+
+    if completed {
+        tracing::info!("Сделка {} завершена", trade_id);
+        notify_trade(&state, &trade, "trade_completed").await;
+        // Каждая сторона получила предметы, выставленные другой
+        for offer in &trade.offers {
+            let received = trade
+                .offers
+                .iter()
+                .filter(|other| other.user_id != offer.user_id)
+                .flat_map(|other| &other.items)
+                .map(|item| QuestEvent::ObtainedItem(item.item_id.clone()))
+                .collect();
+            emit_quest_events(&state, offer.user_id, received).await;
+        }
+    } else {
+        notify_trade(&state, &trade, "trade_updated").await;
+    }
+    Ok(Json(trade))
+}
+
+pub async fn cancel(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(trade_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.pool.begin().await?;
+    lock_trade(&mut tx, trade_id, claims.sub).await?;
+    let participants = cancel_trade(&mut tx, trade_id).await?;
+    tx.commit().await?;
+
+    notify_trade_cancelled(&state, trade_id, &participants, CANCELLED_BY_PLAYER).await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,274 @@
+// /server/src/handlers/translation_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{
+        translation::{LinkTranslation, LocationTranslation},
+        user::UserRole,
+    },
+    state::AppState,
+    world::templates::validate_template,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Перевести можно только на поддерживаемый язык, отличный от основного:
+/// основной текст хранится в самой локации.
+fn validate_language(state: &AppState, language: &str) -> Result<(), AppError> {
+    if language == state.config.default_language {
+        return Err(AppError::BadRequest(format!(
+            "'{}' is the default language; edit the location itself instead",
+            language
+        )));
+    }
+    if !state.config.supported_languages.iter().any(|supported| supported == language) {
+        return Err(AppError::BadRequest(format!("Unsupported language '{}'", language)));
+    }
+    Ok(())
+}
+
+/// Все переводы локации и ее исходящих переходов.
+#[derive(Serialize)]
+pub struct LocationTranslations {
+    pub location: Vec<LocationTranslation>,
+    pub links: Vec<LinkTranslation>,
+}
+
+pub async fn list_location_translations(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(location_id): Path<Uuid>,
+) -> Result<Json<LocationTranslations>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let location = sqlx::query_as!(
+        LocationTranslation,
+        "SELECT * FROM location_translations WHERE location_id = $1 ORDER BY language",
+        location_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let links = sqlx::query_as!(
+        LinkTranslation,
+        r#"
+        SELECT t.* FROM link_translations t
+        JOIN location_links l ON l.id = t.link_id
+        WHERE l.source_location_id = $1
+        ORDER BY t.link_id, t.language
+        "#,
+        location_id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(LocationTranslations { location, links }))
+}
+
+#[derive(Deserialize)]
+pub struct LocationTranslationPayload {
+    pub name: String,
+    pub description: String,
+    pub ambient_description: Option<String>,
+}
+
+pub async fn set_location_translation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((location_id, language)): Path<(Uuid, String)>,
+    Json(payload): Json<LocationTranslationPayload>,
+) -> Result<Json<LocationTranslation>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let language = language.to_ascii_lowercase();
+    validate_language(&state, &language)?;
+    validate_template("description", &payload.description)?;
+
+    let translation = sqlx::query_as!(
+        LocationTranslation,
+        r#"
+        INSERT INTO location_translations (location_id, language, name, description, ambient_description)
+        SELECT id, $2, $3, $4, $5 FROM locations WHERE id = $1
+        ON CONFLICT (location_id, language) DO UPDATE
+        SET name = EXCLUDED.name, description = EXCLUDED.description,
+            ambient_description = EXCLUDED.ambient_description, updated_at = NOW()
+        RETURNING *
+        "#,
+        location_id,
+        language,
+        payload.name,
+        payload.description,
+        payload.ambient_description
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(translation))
+}
+
+pub async fn delete_location_translation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((location_id, language)): Path<(Uuid, String)>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let result = sqlx::query!(
+        "DELETE FROM location_translations WHERE location_id = $1 AND language = $2",
+        location_id,
+        language.to_ascii_lowercase()
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct LinkTranslationPayload {
+    pub link_text: String,
+}
+
+pub async fn set_link_translation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((link_id, language)): Path<(Uuid, String)>,
+    Json(payload): Json<LinkTranslationPayload>,
+) -> Result<Json<LinkTranslation>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let language = language.to_ascii_lowercase();
+    validate_language(&state, &language)?;
+
+    let translation = sqlx::query_as!(
+        LinkTranslation,
+        r#"
+        INSERT INTO link_translations (link_id, language, link_text)
+        SELECT id, $2, $3 FROM location_links WHERE id = $1
+        ON CONFLICT (link_id, language) DO UPDATE
+        SET link_text = EXCLUDED.link_text, updated_at = NOW()
+        RETURNING *
+        "#,
+        link_id,
+        language,
+        payload.link_text
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(Json(translation))
+}
+
+pub async fn delete_link_translation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((link_id, language)): Path<(Uuid, String)>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let result = sqlx::query!(
+        "DELETE FROM link_translations WHERE link_id = $1 AND language = $2",
+        link_id,
+        language.to_ascii_lowercase()
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct MissingQuery {
+    pub language: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MissingLocation {
+    pub id: Uuid,
+    pub name: String,
+    /// Перевод есть, но основной текст менялся после него.
+    pub outdated: bool,
+}
+
+#[derive(Serialize)]
+pub struct MissingLink {
+    pub id: Uuid,
+    pub source_location_id: Uuid,
+    pub link_text: String,
+}
+
+#[derive(Serialize)]
+pub struct MissingTranslations {
+    pub language: String,
+    pub locations: Vec<MissingLocation>,
+    pub links: Vec<MissingLink>,
+}
+
+/// Что осталось перевести: по одному языку из `?language=` или по всем поддерживаемым.
+pub async fn list_missing_translations(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(query): Query<MissingQuery>,
+) -> Result<Json<Vec<MissingTranslations>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let languages: Vec<String> = match query.language {
+        Some(language) => {
+            let language = language.to_ascii_lowercase();
+            validate_language(&state, &language)?;
+            vec![language]
+        }
+        None => state
+            .config
+            .supported_languages
+            .iter()
+            .filter(|language| **language != state.config.default_language)
+            .cloned()
+            .collect(),
+    };
+
+    let mut report = Vec::with_capacity(languages.len());
+    for language in languages {
+        let locations = sqlx::query_as!(
+            MissingLocation,
+            r#"
+            SELECT l.id, l.name, (t.location_id IS NOT NULL) AS "outdated!"
+            FROM locations l
+            LEFT JOIN location_translations t ON t.location_id = l.id AND t.language = $1
+            WHERE t.location_id IS NULL OR t.updated_at < l.updated_at
+            ORDER BY l.name
+            "#,
+            language
+        )
+        .fetch_all(&state.pool)
+        .await?;
+
+        let links = sqlx::query_as!(
+            MissingLink,
+            r#"
+            SELECT l.id, l.source_location_id, l.link_text
+            FROM location_links l
+            WHERE NOT EXISTS (SELECT 1 FROM link_translations t WHERE t.link_id = l.id AND t.language = $1)
+            ORDER BY l.source_location_id, l.link_text
+            "#,
+            language
+        )
+        .fetch_all(&state.pool)
+        .await?;
+
+        report.push(MissingTranslations { language, locations, links });
+    }
+
+    Ok(Json(report))
+}
@@ -3,6 +3,7 @@ use crate::{
     error::AppError,
     models::{user::{User, UserRole}}, // Убираем неиспользуемый Player
     state::AppState,
+    world::{spawn::pick_spawn_location, visits::record_visit},
 };
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHasher, PasswordVerifier, SaltString},
@@ -60,12 +61,13 @@ pub async fn register(
         payload.public_key, payload.encrypted_private_key
     ).fetch_one(&mut *tx).await?;
 
-    let start_location_id = Uuid::parse_str("a1b2c3d4-e5f6-7890-1234-567890abcdef").unwrap();
+    let start_location_id = pick_spawn_location(&mut tx, new_user.role, &state.config).await?;
     sqlx::query!(
         "INSERT INTO players (user_id, current_location_id) VALUES ($1, $2)",
         new_user.id,
         start_location_id
     ).execute(&mut *tx).await?;
+    record_visit(&mut *tx, new_user.id, start_location_id).await?;
 
     tx.commit().await?;
 
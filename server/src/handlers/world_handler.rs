@@ -0,0 +1,63 @@
+// /server/src/handlers/world_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::user::UserRole,
+    state::AppState,
+};
+use axum::{
+    extract::{Path, State},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize)]
+pub struct WorldFlag {
+    pub key: String,
+    pub value: Value,
+}
+
+#[derive(Deserialize)]
+pub struct SetFlagPayload {
+    pub value: Value,
+}
+
+/// Список флагов мира. Флаги управляют условиями переходов, поэтому видны только Архитекторам.
+pub async fn list_flags(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<WorldFlag>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let flags = sqlx::query_as!(WorldFlag, "SELECT key, value FROM world_flags ORDER BY key")
+        .fetch_all(&state.pool)
+        .await?;
+
+    Ok(Json(flags))
+}
+
+pub async fn set_flag(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(key): Path<String>,
+    Json(payload): Json<SetFlagPayload>,
+) -> Result<Json<WorldFlag>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let flag = sqlx::query_as!(
+        WorldFlag,
+        r#"
+        INSERT INTO world_flags (key, value) VALUES ($1, $2)
+        ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value
+        RETURNING key, value
+        "#,
+        key,
+        payload.value
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    tracing::info!("{} установил флаг мира {} = {}", claims.username, flag.key, flag.value);
+    Ok(Json(flag))
+}
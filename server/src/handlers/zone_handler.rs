@@ -0,0 +1,232 @@
+// /server/src/handlers/zone_handler.rs
+use crate::{
+    auth::{require_role, Claims},
+    error::AppError,
+    models::{
+        user::UserRole,
+        zone::{Zone, ZoneKind},
+    },
+    state::AppState,
+    ws::utils::broadcast_to_locations,
+    world::{rules::validate_expression, zones::zone_location_ids},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct CreateZonePayload {
+    pub parent_id: Option<Uuid>,
+    pub kind: ZoneKind,
+    pub name: String,
+    pub security_level: Option<i32>,
+    pub ambient_description: Option<String>,
+    pub access_condition: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateZonePayload {
+    pub name: String,
+    pub security_level: Option<i32>,
+    pub ambient_description: Option<String>,
+    pub access_condition: Option<String>,
+}
+
+pub async fn list_zones(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<Vec<Zone>>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+
+    let zones = sqlx::query_as!(
+        Zone,
+        r#"
+        SELECT id, parent_id, kind AS "kind: _", name, security_level, ambient_description,
+               access_condition, locked_down, created_at, updated_at
+        FROM zones ORDER BY kind, name
+        "#
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(zones))
+}
+
+/// Создает сектор (без родителя) или зону внутри сектора. Только для Архитекторов.
+pub async fn create_zone(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateZonePayload>,
+) -> Result<Json<Zone>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let access_condition = validate_expression("access condition", payload.access_condition.as_deref())?;
+
+    match (payload.kind, payload.parent_id) {
+        (ZoneKind::Sector, None) => {}
+        (ZoneKind::Zone, Some(parent_id)) => {
+            let parent_kind =
+                sqlx::query_scalar!(r#"SELECT kind AS "kind: ZoneKind" FROM zones WHERE id = $1"#, parent_id)
+                    .fetch_optional(&state.pool)
+                    .await?;
+            if parent_kind != Some(ZoneKind::Sector) {
+                return Err(AppError::BadRequest("Zone parent must be an existing sector".to_string()));
+            }
+        }
+        (ZoneKind::Sector, Some(_)) => {
+            return Err(AppError::BadRequest("Sectors cannot have a parent".to_string()));
+        }
+        (ZoneKind::Zone, None) => {
+            return Err(AppError::BadRequest("Zones must belong to a sector".to_string()));
+        }
+    }
+
+    let zone = sqlx::query_as!(
+        Zone,
+        r#"
+        INSERT INTO zones (parent_id, kind, name, security_level, ambient_description, access_condition)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, parent_id, kind AS "kind: _", name, security_level, ambient_description,
+                  access_condition, locked_down, created_at, updated_at
+        "#,
+        payload.parent_id,
+        payload.kind as ZoneKind,
+        payload.name,
+        payload.security_level,
+        payload.ambient_description,
+        access_condition
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    Ok(Json(zone))
+}
+
+pub async fn update_zone(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(zone_id): Path<Uuid>,
+    Json(payload): Json<UpdateZonePayload>,
+) -> Result<Json<Zone>, AppError> {
+    require_role(&claims, UserRole::Architect)?;
+    let access_condition = validate_expression("access condition", payload.access_condition.as_deref())?;
+
+    let zone = sqlx::query_as!(
+        Zone,
+        r#"
+        UPDATE zones
+        SET name = $2, security_level = $3, ambient_description = $4, access_condition = $5
+        WHERE id = $1
+        RETURNING id, parent_id, kind AS "kind: _", name, security_level, ambient_description,
+                  access_condition, locked_down, created_at, updated_at
+        "#,
+        zone_id,
+        payload.name,
+        payload.security_level,
+        payload.ambient_description,
+        access_condition
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    Ok(Json(zone))
+}
+
+#[derive(Serialize)]
+pub struct ZonePlayer {
+    pub user_id: Uuid,
+    pub username: String,
+    pub location_id: Uuid,
+}
+
+/// Игроки, находящиеся в зоне (для сектора - во всех его зонах).
+pub async fn list_zone_players(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(zone_id): Path<Uuid>,
+) -> Result<Json<Vec<ZonePlayer>>, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+
+    let location_ids = zone_location_ids(&state.pool, zone_id).await?;
+    let players = sqlx::query_as!(
+        ZonePlayer,
+        r#"
+        SELECT p.user_id, u.username, p.current_location_id AS "location_id!"
+        FROM players p
+        JOIN users u ON u.id = p.user_id
+        WHERE p.current_location_id = ANY($1)
+        ORDER BY u.username
+        "#,
+        &location_ids
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    Ok(Json(players))
+}
+
+#[derive(Deserialize)]
+pub struct BroadcastPayload {
+    pub message: String,
+}
+
+/// Рассылает сообщение всем, кто сейчас на связи в любой из локаций зоны.
+pub async fn broadcast_to_zone(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(zone_id): Path<Uuid>,
+    Json(payload): Json<BroadcastPayload>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+
+    let location_ids = zone_location_ids(&state.pool, zone_id).await?;
+    let message = serde_json::to_string(&serde_json::json!({
+        "type": "zone_broadcast",
+        "zone_id": zone_id,
+        "from": claims.username,
+        "message": payload.message,
+    }))
+    .unwrap_or_default();
+    broadcast_to_locations(&state, &location_ids, message).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct LockdownPayload {
+    pub locked: bool,
+}
+
+/// Включает или снимает режим изоляции зоны: пока он действует, входить в зону
+/// и выходить из нее могут только модераторы и выше.
+pub async fn set_zone_lockdown(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(zone_id): Path<Uuid>,
+    Json(payload): Json<LockdownPayload>,
+) -> Result<StatusCode, AppError> {
+    require_role(&claims, UserRole::Moderator)?;
+
+    let result = sqlx::query!("UPDATE zones SET locked_down = $2 WHERE id = $1", zone_id, payload.locked)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    tracing::warn!("{} изменил режим изоляции зоны {}: {}", claims.username, zone_id, payload.locked);
+
+    let location_ids = zone_location_ids(&state.pool, zone_id).await?;
+    let message = serde_json::to_string(&serde_json::json!({
+        "type": "zone_lockdown",
+        "zone_id": zone_id,
+        "locked": payload.locked,
+    }))
+    .unwrap_or_default();
+    broadcast_to_locations(&state, &location_ids, message).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -19,6 +19,7 @@ mod handlers;
 mod models;
 mod routes;
 mod state;
+mod world;
 pub mod ws;
 
 use config::Config;
@@ -46,6 +47,11 @@ async fn main() {
         ws_state: WsState::new(), 
     };
 
+    world::instances::spawn_instance_sweeper(app_state.clone());
+    world::capacity::spawn_queue_sweeper(app_state.clone());
+    world::grants::spawn_grant_sweeper(app_state.clone());
+    world::transit::spawn_transit_sweeper(app_state.clone());
+
     let cors = CorsLayer::new().allow_origin(Any).allow_headers(vec![
         axum::http::header::AUTHORIZATION,
         axum::http::header::CONTENT_TYPE,
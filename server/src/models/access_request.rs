@@ -0,0 +1,34 @@
+// /server/src/models/access_request.rs
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Type, PartialEq)]
+#[sqlx(type_name = "access_request_status", rename_all = "PascalCase")]
+pub enum AccessRequestStatus {
+    Pending,
+    Approved,
+    Denied,
+    /// Отозван самим игроком.
+    Cancelled,
+}
+
+/// Запрос игрока на доступ в локацию. Название локации не отдается:
+/// игроку без доступа оно не положено.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AccessRequest {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub location_id: Uuid,
+    pub message: String,
+    pub status: AccessRequestStatus,
+    // NULL - запрос адресован модераторам
+    pub approver_id: Option<Uuid>,
+    pub decided_by: Option<Uuid>,
+    pub decision_note: Option<String>,
+    pub grant_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,34 @@
+// /server/src/models/acl.rs
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// Как список доступа локации применяется ко входу.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Type, PartialEq, Default)]
+#[sqlx(type_name = "entry_policy", rename_all = "PascalCase")]
+pub enum EntryPolicy {
+    /// Входить могут все, кроме записей Deny.
+    #[default]
+    DenyList,
+    /// Входить могут только записи Allow (и владелец с редакторами).
+    AllowList,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Type, PartialEq)]
+#[sqlx(type_name = "acl_entry_kind", rename_all = "PascalCase")]
+pub enum AclEntryKind {
+    /// Может править комнату и ее список доступа; входит всегда.
+    Editor,
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AclEntry {
+    pub user_id: Uuid,
+    pub username: String,
+    pub kind: AclEntryKind,
+    pub added_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,36 @@
+use serde::Serialize;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Asset {
+    pub id: Uuid,
+    pub content_hash: String,
+    pub mime_type: String,
+    pub extension: String,
+    pub byte_size: i32,
+    pub width: i32,
+    pub height: i32,
+    pub ref_count: i32,
+    pub uploaded_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Asset {
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.content_hash, self.extension)
+    }
+
+    /// Миниатюры всегда хранятся в PNG.
+    pub fn thumbnail_file_name(&self) -> String {
+        format!("{}_thumb.png", self.content_hash)
+    }
+
+    pub fn url(&self) -> String {
+        format!("/api/assets/files/{}", self.file_name())
+    }
+
+    pub fn thumbnail_url(&self) -> String {
+        format!("/api/assets/files/{}", self.thumbnail_file_name())
+    }
+}
@@ -0,0 +1,78 @@
+// /server/src/models/challenge.rs
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Type, PartialEq)]
+#[sqlx(type_name = "challenge_kind", rename_all = "PascalCase")]
+pub enum ChallengeKind {
+    /// Ответ на загадку терминала.
+    Riddle,
+    /// Предъявление предмета.
+    Item,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ClearanceChallenge {
+    pub id: Uuid,
+    pub location_id: Uuid,
+    pub title: String,
+    pub prompt: String,
+    pub kind: ChallengeKind,
+    // Ответ виден только Архитекторам: игрокам отдается ChallengeView
+    pub answer: Option<String>,
+    pub required_item_id: Option<String>,
+    pub consume_item: bool,
+    pub min_access_level: i32,
+    pub grants_level: i32,
+    // Если задано, испытание выдает временный допуск на столько секунд, а не повышает уровень навсегда
+    pub grant_duration_secs: Option<i32>,
+    pub creator_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Испытание глазами игрока: без ответа.
+#[derive(Debug, Serialize)]
+pub struct ChallengeView {
+    pub id: Uuid,
+    pub title: String,
+    pub prompt: String,
+    pub kind: ChallengeKind,
+    pub required_item_id: Option<String>,
+    pub min_access_level: i32,
+    pub grants_level: i32,
+    pub grant_duration_secs: Option<i32>,
+    /// Уровень игрока подходит, и испытание еще может его повысить.
+    pub available: bool,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ClearanceAuditEntry {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub old_level: i32,
+    pub new_level: i32,
+    pub challenge_id: Option<Uuid>,
+    pub granted_by: Option<Uuid>,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Временный допуск: уровень доступа или доступ к одной локации до `expires_at`.
+/// Задано ровно одно из `access_level` и `location_id`.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ClearanceGrant {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub access_level: Option<i32>,
+    pub location_id: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    // Истечение уже обработано: игрок выведен из локаций, куда допуск больше не пускает
+    pub processed: bool,
+    // Разовый пропуск в локацию: сгорает, когда игрок ее покидает
+    pub single_use: bool,
+    pub granted_by: Option<Uuid>,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
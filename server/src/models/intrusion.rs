@@ -0,0 +1,26 @@
+// /server/src/models/intrusion.rs
+use serde::Serialize;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct IntrusionAttempt {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub location_id: Uuid,
+    pub required_level: i32,
+    pub access_level: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ZoneAlarm {
+    pub id: Uuid,
+    pub zone_id: Uuid,
+    pub location_id: Option<Uuid>,
+    pub triggered_by: Option<Uuid>,
+    pub raised_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub silenced_by: Option<Uuid>,
+}
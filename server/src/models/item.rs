@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ItemDefinition {
+    /// Читаемый код предмета, на который ссылаются условия (`has keycard_red`).
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub stackable: bool,
+    pub weight: f32,
+    pub tags: Vec<String>,
+    pub consumable: bool,
+    /// `None` - предмет нельзя использовать.
+    pub use_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Предмет в инвентаре игрока: экземпляр вместе с данными из описания.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct InventoryItem {
+    /// Идентификатор экземпляра (не путать с кодом предмета).
+    pub id: Uuid,
+    pub item_id: String,
+    pub name: String,
+    pub description: String,
+    pub quantity: i32,
+    pub stackable: bool,
+    pub weight: f32,
+    pub tags: Vec<String>,
+    pub consumable: bool,
+    pub usable: bool,
+    pub acquired_at: DateTime<Utc>,
+}
+
+/// Предмет, лежащий в локации (или в копии инстанцированной локации).
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LocationItem {
+    pub id: Uuid,
+    pub item_id: String,
+    pub name: String,
+    pub description: String,
+    pub quantity: i32,
+    pub stackable: bool,
+    pub weight: f32,
+    pub tags: Vec<String>,
+    pub placed_by: Option<Uuid>,
+    pub placed_at: DateTime<Utc>,
+}
@@ -1,10 +1,34 @@
 // /server/src/models/link.rs
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Type, PartialEq, Default)]
+#[sqlx(type_name = "link_visibility", rename_all = "PascalCase")]
+pub enum LinkVisibility {
+    #[default]
+    Visible,
+    /// Появляется в списке переходов только после обнаружения игроком.
+    Hidden,
+    /// Никогда не показывается, но пройти можно, зная цель.
+    Secret,
+}
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct LocationLink {
+    pub id: Uuid,
+    pub source_location_id: Uuid,
     pub target_location_id: Uuid,
     pub link_text: String,
     pub required_access_level: i32,
-}
\ No newline at end of file
+    // Выражение на языке правил (world::rules); None - переход без условий
+    pub access_condition: Option<String>,
+    pub denial_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub visibility: LinkVisibility,
+    pub search_chance: i32,
+    pub reveal_condition: Option<String>,
+    // Время в пути в секундах; 0 - переход мгновенный
+    pub travel_secs: i32,
+}
@@ -1,15 +1,37 @@
+use super::acl::EntryPolicy;
 use serde::Serialize;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+/// Локация с уже примененным наследованием от зоны и сектора
+/// (см. world::zones::fetch_locations).
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct Location {
     pub id: Uuid,
     pub name: String,
     pub description: String,
     pub image_url: Option<String>,
+    pub image_asset_id: Option<Uuid>,
     pub security_level: i32,
     pub creator_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
-}
\ No newline at end of file
+    pub zone_id: Uuid,
+    pub ambient_description: Option<String>,
+    // Политика доступа не раскрывается игрокам
+    #[serde(skip_serializing)]
+    pub access_condition: Option<String>,
+    pub locked_down: bool,
+    pub instanced: bool,
+    /// Сколько игроков может находиться в локации одновременно; `None` - без ограничений.
+    pub capacity: Option<i32>,
+    /// Поля, которые частично видны игроку без нужного уровня доступа (см. world::redaction).
+    pub redaction_leaks: Vec<String>,
+    /// Владелец управляет списком доступа (см. world::acl).
+    pub owner_id: Option<Uuid>,
+    pub entry_policy: EntryPolicy,
+    /// Личная комната игрока.
+    pub personal: bool,
+    /// Публичный узел, к которому игроки могут привязывать свои комнаты.
+    pub public_hub: bool,
+}
@@ -1,4 +1,16 @@
 pub mod user;
 pub mod location;
 pub mod player;
-pub mod link;
\ No newline at end of file
+pub mod link;
+pub mod zone;
+pub mod party;
+pub mod asset;
+pub mod translation;
+pub mod spawn_point;
+pub mod item;
+pub mod challenge;
+pub mod quest;
+pub mod access_request;
+pub mod intrusion;
+pub mod acl;
+pub mod npc;
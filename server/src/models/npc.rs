@@ -0,0 +1,84 @@
+// /server/src/models/npc.rs
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Npc {
+    pub id: Uuid,
+    pub location_id: Uuid,
+    pub name: String,
+    pub description: String,
+    // Дерево диалога в формате `Dialogue`
+    pub dialogue: Value,
+    pub creator_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// NPC в списке присутствующих: в room_state он стоит рядом с игроками, но с пометкой `npc`.
+#[derive(Debug, Serialize)]
+pub struct NpcPresence {
+    pub id: Uuid,
+    pub username: String,
+    pub description: String,
+    pub npc: bool,
+}
+
+/// Дерево диалога: узлы по идентификаторам и узел, с которого начинается разговор.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Dialogue {
+    pub start: String,
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+/// Реплика NPC. Эффекты срабатывают, когда разговор приходит в узел;
+/// узел без вариантов ответа заканчивает разговор.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DialogueNode {
+    // Шаблон (см. world::templates)
+    pub text: String,
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DialogueChoice {
+    pub text: String,
+    // Выражение на языке правил (world::rules); вариант без условия виден всегда
+    #[serde(default)]
+    pub condition: Option<String>,
+    // None - ответ заканчивает разговор
+    #[serde(default)]
+    pub next: Option<String>,
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DialogueEffect {
+    GiveItem {
+        item_id: String,
+        #[serde(default = "default_quantity")]
+        quantity: i32,
+    },
+    /// Если у игрока не хватает предметов, ответ не проходит.
+    TakeItem {
+        item_id: String,
+        #[serde(default = "default_quantity")]
+        quantity: i32,
+    },
+    /// Флаг самого игрока: в условиях проверяется через `player_flag`, флаги мира не трогает.
+    SetFlag { key: String, value: Value },
+    /// Состояние копии локации, в которой стоит игрок (дверь, рычаг); вне копии ничего не меняет.
+    SetInstanceState { key: String, value: Value },
+}
+
+fn default_quantity() -> i32 {
+    1
+}
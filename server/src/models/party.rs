@@ -0,0 +1,21 @@
+use serde::Serialize;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Party {
+    pub id: Uuid,
+    pub leader_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LocationInstance {
+    pub id: Uuid,
+    pub location_id: Uuid,
+    pub party_id: Option<Uuid>,
+    pub owner_id: Option<Uuid>,
+    pub state: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub emptied_at: Option<DateTime<Utc>>,
+}
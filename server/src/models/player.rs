@@ -1,11 +1,32 @@
+use super::item::InventoryItem;
+use chrono::{DateTime, Utc};
 use serde::Serialize;
-use serde_json::Value;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
+#[derive(Debug, Serialize)]
 pub struct Player {
     pub user_id: Uuid,
     pub current_location_id: Option<Uuid>,
     pub access_level: i32,
-    pub inventory: Option<Value>,
-}
\ No newline at end of file
+    // С учетом временных допусков (см. world::grants)
+    pub effective_access_level: i32,
+    pub credits: i64,
+    // Копия инстанцированной локации, в которой находится игрок
+    pub current_instance_id: Option<Uuid>,
+    pub inventory: Vec<InventoryItem>,
+    // Незавершенный переход, если игрок сейчас в пути
+    pub transit: Option<PlayerTransit>,
+}
+
+/// Переход, который занимает время. Пока игрок в пути, он числится в исходной локации,
+/// но не находится ни в одной комнате.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PlayerTransit {
+    pub user_id: Uuid,
+    pub link_id: Option<Uuid>,
+    pub source_location_id: Uuid,
+    pub target_location_id: Uuid,
+    pub queue: bool,
+    pub started_at: DateTime<Utc>,
+    pub arrives_at: DateTime<Utc>,
+}
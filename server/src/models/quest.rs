@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::Type;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Type, PartialEq)]
+#[sqlx(type_name = "objective_kind", rename_all = "PascalCase")]
+pub enum ObjectiveKind {
+    VisitLocation,
+    /// Иметь в инвентаре не меньше `quantity` штук предмета.
+    ObtainItem,
+    TalkToNpc,
+    ReachAccessLevel,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Quest {
+    pub id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub reward_access_level: Option<i32>,
+    pub reward_flags: Value,
+    pub creator_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct QuestObjective {
+    pub id: Uuid,
+    pub quest_id: Uuid,
+    pub position: i32,
+    pub kind: ObjectiveKind,
+    pub description: String,
+    pub location_id: Option<Uuid>,
+    pub item_id: Option<String>,
+    pub quantity: i32,
+    pub npc_id: Option<Uuid>,
+    pub access_level: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
+pub struct QuestRewardItem {
+    pub item_id: String,
+    pub quantity: i32,
+}
+
+/// Задание целиком: для Архитекторов и для списка доступных заданий.
+#[derive(Debug, Serialize)]
+pub struct QuestDetails {
+    #[serde(flatten)]
+    pub quest: Quest,
+    pub objectives: Vec<QuestObjective>,
+    pub reward_items: Vec<QuestRewardItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ObjectiveProgress {
+    pub id: Uuid,
+    pub kind: ObjectiveKind,
+    pub description: String,
+    pub completed: bool,
+}
+
+/// Задание в журнале игрока.
+#[derive(Debug, Serialize)]
+pub struct PlayerQuest {
+    pub quest_id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub objectives: Vec<ObjectiveProgress>,
+}
@@ -0,0 +1,14 @@
+use super::user::UserRole;
+use serde::Serialize;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SpawnPoint {
+    pub id: Uuid,
+    pub location_id: Uuid,
+    pub weight: i32,
+    /// `None` - точка для всех ролей, у которых нет собственных точек.
+    pub role: Option<UserRole>,
+    pub created_at: DateTime<Utc>,
+}
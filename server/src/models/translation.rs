@@ -0,0 +1,21 @@
+use serde::Serialize;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LocationTranslation {
+    pub location_id: Uuid,
+    pub language: String,
+    pub name: String,
+    pub description: String,
+    pub ambient_description: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LinkTranslation {
+    pub link_id: Uuid,
+    pub language: String,
+    pub link_text: String,
+    pub updated_at: DateTime<Utc>,
+}
@@ -2,8 +2,10 @@ use serde::{Serialize, Deserialize};
 use sqlx::Type;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize, Clone, Type, PartialEq)]
+// Порядок вариантов важен: роли сравниваются по старшинству (User < ... < Creator)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Type, PartialEq, Eq, PartialOrd, Ord)]
 #[sqlx(type_name = "user_role", rename_all = "PascalCase")]
 pub enum UserRole {
     User,
@@ -13,6 +15,21 @@ pub enum UserRole {
     Creator,
 }
 
+impl FromStr for UserRole {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "user" => Ok(UserRole::User),
+            "moderator" => Ok(UserRole::Moderator),
+            "architect" => Ok(UserRole::Architect),
+            "admin" => Ok(UserRole::Admin),
+            "creator" => Ok(UserRole::Creator),
+            _ => Err(()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct User {
     pub id: Uuid,
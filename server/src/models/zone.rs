@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use sqlx::Type;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Type, PartialEq)]
+#[sqlx(type_name = "zone_kind", rename_all = "PascalCase")]
+pub enum ZoneKind {
+    Sector,
+    Zone,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Zone {
+    pub id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub kind: ZoneKind,
+    pub name: String,
+    pub security_level: Option<i32>,
+    pub ambient_description: Option<String>,
+    pub access_condition: Option<String>,
+    pub locked_down: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -2,13 +2,19 @@
 
 use crate::{
     auth::auth_middleware,
-    handlers::{location_handler, player_handler, user_handler},
+    handlers::{
+        access_request_handler, asset_handler, challenge_handler, generator_handler, grant_handler, intrusion_handler,
+        item_handler, link_handler, location_handler, map_handler, npc_handler, party_handler, player_handler,
+        quest_handler, room_handler, spawn_handler, trade_handler, translation_handler, user_handler, world_handler,
+        zone_handler,
+    },
     state::AppState,
     ws::handler::ws_handler,
 };
 use axum::{
+    extract::DefaultBodyLimit,
     middleware,
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 
@@ -18,13 +24,117 @@ pub fn create_router(app_state: AppState) -> Router {
     // Публичные роуты, доступные всем
     let public_routes = Router::new()
         .route("/register", post(user_handler::register))
-        .route("/login", post(user_handler::login));
+        .route("/login", post(user_handler::login))
+        .route("/assets/files/:name", get(asset_handler::serve_asset));
 
     // Защищенные роуты, требующие валидного JWT-токена
     let protected_routes = Router::new()
         .route("/player/status", get(player_handler::get_player_status))
         .route("/player/move", post(player_handler::move_player))
-        .route("/locations/:id", get(location_handler::get_location))
+        .route("/player/transit", delete(player_handler::cancel_transit))
+        .route("/player/map", get(map_handler::get_player_map))
+        .route("/player/journal", get(player_handler::get_journal))
+        .route("/player/search", post(player_handler::search))
+        .route("/player/instance", get(player_handler::get_current_instance))
+        .route("/instances/:id/state/:key", put(player_handler::set_instance_state))
+        .route("/player/language", put(player_handler::set_language))
+        .route("/player/queue", get(player_handler::get_queue).delete(player_handler::leave_queue))
+        .route("/player/inventory", get(item_handler::get_inventory))
+        .route("/player/inventory/:id", get(item_handler::inspect_item))
+        .route("/player/inventory/:id/use", post(item_handler::use_item))
+        .route("/player/inventory/:id/drop", post(item_handler::drop_item))
+        .route("/player/room/items", get(item_handler::get_room_items))
+        .route("/player/room/items/:id/pickup", post(item_handler::pick_up))
+        .route("/player/quests", get(quest_handler::get_quest_log))
+        .route("/quests", get(quest_handler::list_quests).post(quest_handler::create_quest))
+        .route("/quests/available", get(quest_handler::list_available_quests))
+        .route("/quests/:id", put(quest_handler::update_quest).delete(quest_handler::delete_quest))
+        .route("/quests/:id/accept", post(quest_handler::accept_quest))
+        .route("/player/challenges", get(challenge_handler::list_player_challenges))
+        .route("/challenges/:id", put(challenge_handler::update_challenge).delete(challenge_handler::delete_challenge))
+        .route("/challenges/:id/attempt", post(challenge_handler::attempt_challenge))
+        .route("/players/:id/credits", post(player_handler::adjust_credits))
+        .route("/players/:id/access-level", put(challenge_handler::set_player_access_level))
+        .route("/players/:id/clearance-log", get(challenge_handler::get_clearance_log))
+        .route("/player/grants", get(grant_handler::list_player_grants))
+        .route("/players/:id/grants", get(grant_handler::list_grants).post(grant_handler::create_grant))
+        .route("/grants/:id", delete(grant_handler::revoke_grant))
+        .route("/player/access-requests", get(access_request_handler::list_player_access_requests))
+        .route("/locations/:id/access-requests", post(access_request_handler::create_access_request))
+        .route("/access-requests", get(access_request_handler::list_access_requests))
+        .route("/access-requests/:id", delete(access_request_handler::cancel_access_request))
+        .route("/access-requests/:id/approve", post(access_request_handler::approve_access_request))
+        .route("/access-requests/:id/deny", post(access_request_handler::deny_access_request))
+        .route(
+            "/locations/:id/intrusion-settings",
+            get(intrusion_handler::get_intrusion_settings).put(intrusion_handler::set_intrusion_settings),
+        )
+        .route("/locations/:id/intrusions", get(intrusion_handler::list_intrusions))
+        .route("/alarms", get(intrusion_handler::list_alarms))
+        .route("/alarms/:id", delete(intrusion_handler::silence_alarm))
+        .route("/players/:id/movement-lock", delete(intrusion_handler::clear_movement_lock))
+        .route("/player/rooms", get(room_handler::list_player_rooms).post(room_handler::claim_room))
+        .route("/rooms/:id", put(room_handler::update_room).delete(room_handler::delete_room))
+        .route("/rooms/:id/links", post(room_handler::link_room))
+        .route("/rooms/:id/links/:hub_id", delete(room_handler::unlink_room))
+        .route("/locations/:id/acl", get(room_handler::get_acl).put(room_handler::set_entry_policy))
+        .route("/locations/:id/acl/:user_id", put(room_handler::add_acl_entry))
+        .route("/locations/:id/acl/:user_id/:kind", delete(room_handler::remove_acl_entry))
+        .route("/trades", post(trade_handler::propose))
+        .route("/trades/current", get(trade_handler::get_current_trade))
+        .route("/trades/:id", delete(trade_handler::cancel))
+        .route("/trades/:id/items", post(trade_handler::offer_item))
+        .route("/trades/:id/items/:escrow_id", delete(trade_handler::withdraw_item))
+        .route("/trades/:id/credits", put(trade_handler::offer_credits))
+        .route("/trades/:id/confirm", post(trade_handler::confirm))
+        .route("/party", get(party_handler::get_party).post(party_handler::create_party))
+        .route("/party/leave", post(party_handler::leave_party))
+        .route("/party/:id/join", post(party_handler::join_party))
+        .route("/locations", post(location_handler::create_location))
+        .route("/locations/:id", get(location_handler::get_location).put(location_handler::update_location))
+        .route("/locations/:id/links", post(link_handler::create_link))
+        .route(
+            "/locations/:id/challenges",
+            get(challenge_handler::list_location_challenges).post(challenge_handler::create_challenge),
+        )
+        .route("/locations/:id/npcs", get(npc_handler::list_location_npcs))
+        .route("/locations/:id/items", post(item_handler::place_location_item))
+        .route("/locations/:id/items/:item_id", delete(item_handler::remove_location_item))
+        .route("/locations/:id/translations", get(translation_handler::list_location_translations))
+        .route(
+            "/locations/:id/translations/:language",
+            put(translation_handler::set_location_translation).delete(translation_handler::delete_location_translation),
+        )
+        .route("/links/:id", put(link_handler::update_link).delete(link_handler::delete_link))
+        .route(
+            "/links/:id/translations/:language",
+            put(translation_handler::set_link_translation).delete(translation_handler::delete_link_translation),
+        )
+        .route("/translations/missing", get(translation_handler::list_missing_translations))
+        .route("/world/flags", get(world_handler::list_flags))
+        .route("/world/flags/:key", put(world_handler::set_flag))
+        .route("/generator/drafts", post(generator_handler::create_draft))
+        .route("/generator/drafts/:id", get(generator_handler::get_draft).delete(generator_handler::delete_draft))
+        .route("/generator/drafts/:id/commit", post(generator_handler::commit_draft))
+        .route("/items", get(item_handler::list_items))
+        .route("/items/:id", put(item_handler::set_item).delete(item_handler::delete_item))
+        .route("/items/:id/grant", post(item_handler::grant))
+        .route("/npcs", post(npc_handler::create_npc))
+        .route("/npcs/:id", get(npc_handler::get_npc).put(npc_handler::update_npc).delete(npc_handler::delete_npc))
+        .route("/spawn-points", get(spawn_handler::list_spawn_points).post(spawn_handler::create_spawn_point))
+        .route("/spawn-points/:id", delete(spawn_handler::delete_spawn_point))
+        .route("/zones", get(zone_handler::list_zones).post(zone_handler::create_zone))
+        .route("/zones/:id", put(zone_handler::update_zone))
+        .route("/zones/:id/players", get(zone_handler::list_zone_players))
+        .route("/zones/:id/broadcast", post(zone_handler::broadcast_to_zone))
+        .route("/zones/:id/lockdown", post(zone_handler::set_zone_lockdown))
+        .route(
+            "/assets",
+            get(asset_handler::list_assets)
+                .post(asset_handler::upload_asset)
+                .layer(DefaultBodyLimit::max(app_state.config.asset_max_bytes)),
+        )
+        .route("/assets/collect", post(asset_handler::collect_assets))
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
             auth_middleware,
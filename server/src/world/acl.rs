@@ -0,0 +1,77 @@
+// /server/src/world/acl.rs
+
+// Списки доступа локаций.
+//
+// Помимо уровня секретности у локации может быть владелец, редакторы и список
+// на вход: в режиме DenyList не пускают только записи Deny, в режиме AllowList
+// пускают только записи Allow. Владелец, редакторы и модераторы входят всегда.
+// Редакторы правят комнату и список на вход, назначать редакторов может только владелец.
+
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::{
+        acl::{AclEntryKind, EntryPolicy},
+        location::Location,
+        user::UserRole,
+    },
+};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+async fn acl_kinds<'e>(
+    executor: impl PgExecutor<'e>,
+    location_id: Uuid,
+    user_id: Uuid,
+) -> Result<Vec<AclEntryKind>, AppError> {
+    let kinds = sqlx::query_scalar!(
+        r#"SELECT kind AS "kind: AclEntryKind" FROM location_acl WHERE location_id = $1 AND user_id = $2"#,
+        location_id,
+        user_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(kinds)
+}
+
+/// Причина, по которой список доступа не пускает игрока в локацию. `None` - вход открыт.
+pub async fn acl_denial<'e>(
+    executor: impl PgExecutor<'e>,
+    location: &Location,
+    user_id: Uuid,
+    role: UserRole,
+) -> Result<Option<String>, AppError> {
+    if role >= UserRole::Moderator || location.owner_id == Some(user_id) {
+        return Ok(None);
+    }
+    let kinds = acl_kinds(executor, location.id, user_id).await?;
+    if kinds.contains(&AclEntryKind::Editor) {
+        return Ok(None);
+    }
+
+    let denial = match location.entry_policy {
+        EntryPolicy::DenyList => kinds
+            .contains(&AclEntryKind::Deny)
+            .then(|| "ВЛАДЕЛЕЦ ЗАКРЫЛ ВАМ ВХОД В ЭТУ ЛОКАЦИЮ.".to_string()),
+        EntryPolicy::AllowList => (!kinds.contains(&AclEntryKind::Allow))
+            .then(|| "ВХОД ТОЛЬКО ПО СПИСКУ ВЛАДЕЛЬЦА.".to_string()),
+    };
+    Ok(denial)
+}
+
+/// Пускает к управлению локацией Архитекторов, владельца и, если `editors` - редакторов.
+pub async fn require_location_manager<'e>(
+    executor: impl PgExecutor<'e>,
+    location: &Location,
+    claims: &Claims,
+    editors: bool,
+) -> Result<(), AppError> {
+    if claims.role >= UserRole::Architect || location.owner_id == Some(claims.sub) {
+        return Ok(());
+    }
+    if editors && acl_kinds(executor, location.id, claims.sub).await?.contains(&AclEntryKind::Editor) {
+        return Ok(());
+    }
+    Err(AppError::Forbidden)
+}
@@ -0,0 +1,221 @@
+// /server/src/world/capacity.rs
+
+// Вместимость локаций и очереди на вход.
+//
+// Кто где находится, решает `players.current_location_id`; комнаты WsState лишь
+// повторяют это состояние после коммита. Место занимают только подключенные игроки:
+// отключившийся остается в локации по базе, но не держит место, иначе одна брошенная
+// сессия навсегда заперла бы кабинку на одного. Его уход освобождает место так же,
+// как выход (см. ws::handler), а вернувшись, он попадает в свою локацию сверх лимита.
+//
+// Все решения о местах принимаются под блокировкой строки локации (FOR UPDATE):
+// двое одновременно входящих игроков не займут последнее место вдвоем,
+// а раздача брони не пересечется с проверкой при входе.
+
+use crate::{
+    error::AppError,
+    state::AppState,
+    ws::utils::{connected_user_ids, send_to_user},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Как часто фоновая задача снимает просроченные брони и раздает освободившиеся места.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Пытается занять место в локации с ограниченной вместимостью. `online` - подключенные игроки
+/// (`connected_user_ids`), снятые до начала транзакции, чтобы не держать блокировку комнат.
+/// Вызывается внутри транзакции перемещения; при успехе бронь игрока (если была) погашается.
+pub async fn admit(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    location_id: Uuid,
+    capacity: i32,
+    online: &[Uuid],
+) -> Result<bool, AppError> {
+    sqlx::query!("SELECT id FROM locations WHERE id = $1 FOR UPDATE", location_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    let slots = sqlx::query!(
+        r#"
+        SELECT
+            (SELECT COUNT(*) FROM players
+             WHERE current_location_id = $1 AND user_id <> $2 AND user_id = ANY($3)) AS "occupants!",
+            (SELECT COUNT(*) FROM location_queue
+             WHERE location_id = $1 AND user_id <> $2 AND reserved_until > NOW()) AS "reserved!",
+            (SELECT COUNT(*) FROM location_queue
+             WHERE location_id = $1 AND user_id <> $2 AND reserved_until IS NULL) AS "waiting!",
+            EXISTS (SELECT 1 FROM location_queue
+                    WHERE location_id = $1 AND user_id = $2 AND reserved_until > NOW()) AS "has_reservation!"
+        "#,
+        location_id,
+        user_id,
+        online
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // Места под чужими бронями заняты. Без своей брони нельзя обойти тех, кто уже ждет в очереди.
+    let free = i64::from(capacity) - slots.occupants - slots.reserved;
+    if free <= 0 || (!slots.has_reservation && slots.waiting > 0) {
+        return Ok(false);
+    }
+
+    sqlx::query!("DELETE FROM location_queue WHERE user_id = $1 AND location_id = $2", user_id, location_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(true)
+}
+
+/// Ставит игрока в очередь (или оставляет на прежнем месте, если он уже в ней) и возвращает позицию.
+/// Очередь у игрока одна: встав в другую, он покидает прежнюю.
+pub async fn enqueue(conn: &mut PgConnection, user_id: Uuid, location_id: Uuid) -> Result<i64, AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO location_queue (user_id, location_id) VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE
+        SET location_id = EXCLUDED.location_id, enqueued_at = NOW(), reserved_until = NULL
+        WHERE location_queue.location_id <> EXCLUDED.location_id
+        "#,
+        user_id,
+        location_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let status = queue_status(conn, user_id).await?.ok_or(AppError::NotFound)?;
+    Ok(status.position)
+}
+
+#[derive(Serialize)]
+pub struct QueueStatus {
+    pub location_id: Uuid,
+    /// 1 - первый в очереди.
+    pub position: i64,
+    /// Если задано, место уже держится за игроком до этого момента.
+    pub reserved_until: Option<DateTime<Utc>>,
+}
+
+pub async fn queue_status(conn: &mut PgConnection, user_id: Uuid) -> Result<Option<QueueStatus>, AppError> {
+    let status = sqlx::query_as!(
+        QueueStatus,
+        r#"
+        SELECT q.location_id, q.reserved_until,
+               (SELECT COUNT(*) FROM location_queue o
+                WHERE o.location_id = q.location_id AND o.enqueued_at <= q.enqueued_at) AS "position!"
+        FROM location_queue q WHERE q.user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(status)
+}
+
+/// Снимает просроченные брони и выдает освободившиеся места следующим в очереди,
+/// затем уведомляет игроков через WebSocket.
+pub async fn process_queue(state: &AppState, location_id: Uuid) -> Result<(), AppError> {
+    let online = connected_user_ids(state).await;
+    let mut tx = state.pool.begin().await?;
+
+    let capacity = sqlx::query_scalar!("SELECT capacity FROM locations WHERE id = $1 FOR UPDATE", location_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(capacity) = capacity else {
+        return Ok(());
+    };
+
+    let expired = sqlx::query_scalar!(
+        "DELETE FROM location_queue WHERE location_id = $1 AND reserved_until <= NOW() RETURNING user_id",
+        location_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // Если вместимость сняли, пускаем всех, кто ждал
+    let free = match capacity {
+        Some(capacity) => {
+            let taken = sqlx::query_scalar!(
+                r#"
+                SELECT (SELECT COUNT(*) FROM players WHERE current_location_id = $1 AND user_id = ANY($2))
+                     + (SELECT COUNT(*) FROM location_queue WHERE location_id = $1 AND reserved_until IS NOT NULL)
+                     AS "taken!"
+                "#,
+                location_id,
+                &online
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            (i64::from(capacity) - taken).max(0)
+        }
+        None => i64::MAX,
+    };
+
+    let reserved = sqlx::query!(
+        r#"
+        UPDATE location_queue SET reserved_until = NOW() + make_interval(secs => $3)
+        WHERE user_id IN (
+            SELECT user_id FROM location_queue
+            WHERE location_id = $1 AND reserved_until IS NULL
+            ORDER BY enqueued_at
+            LIMIT $2
+        )
+        RETURNING user_id, reserved_until AS "reserved_until!"
+        "#,
+        location_id,
+        free,
+        state.config.queue_reservation_secs as f64
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    for user_id in expired {
+        let message = serde_json::json!({ "type": "queue_reservation_expired", "location_id": location_id });
+        send_to_user(state, user_id, message.to_string()).await;
+    }
+    for row in reserved {
+        let message = serde_json::json!({
+            "type": "queue_slot_open",
+            "location_id": location_id,
+            "reserved_until": row.reserved_until,
+        });
+        send_to_user(state, row.user_id, message.to_string()).await;
+    }
+
+    Ok(())
+}
+
+/// Фоновая задача: обрабатывает очереди всех локаций, чтобы просроченные брони
+/// освобождались, даже если никто не входит и не выходит.
+pub fn spawn_queue_sweeper(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep_queues(&state).await {
+                tracing::error!("Не удалось обработать очереди: {:?}", e);
+            }
+        }
+    });
+}
+
+async fn sweep_queues(state: &AppState) -> Result<(), AppError> {
+    let location_ids = sqlx::query_scalar!("SELECT DISTINCT location_id FROM location_queue")
+        .fetch_all(&state.pool)
+        .await?;
+
+    // Ошибка в одной локации не должна останавливать очереди остальных
+    for location_id in location_ids {
+        if let Err(e) = process_queue(state, location_id).await {
+            tracing::error!("Не удалось обработать очередь в локации {}: {:?}", location_id, e);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+// /server/src/world/clearance.rs
+
+// Изменение уровня доступа игроков.
+//
+// Уровень меняется только здесь: после испытания или вручную Архитектором.
+// Каждое изменение попадает в журнал clearance_audit. Обработчики каждый раз
+// читают уровень из базы, поэтому новый уровень действует со следующего запроса.
+//
+// Подбор ответа на загадку ограничен так же, как попытки проникновения: после порога
+// неверных ответов подряд ответы на это испытание блокируются, и блокировка растет.
+
+use super::intrusion::{cooldown_for, IntrusionSettings};
+use crate::{error::AppError, state::AppState, ws::utils::send_to_user};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+/// Откуда взялось изменение уровня - для журнала.
+pub struct ClearanceChange<'a> {
+    pub challenge_id: Option<Uuid>,
+    pub granted_by: Option<Uuid>,
+    pub reason: &'a str,
+}
+
+/// Блокирует строку игрока и возвращает его текущий уровень.
+pub async fn lock_access_level(conn: &mut PgConnection, user_id: Uuid) -> Result<i32, AppError> {
+    let level = sqlx::query_scalar!("SELECT access_level FROM players WHERE user_id = $1 FOR UPDATE", user_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(level)
+}
+
+/// Устанавливает уровень и записывает изменение в журнал. Возвращает прежний уровень.
+pub async fn set_access_level(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    new_level: i32,
+    change: ClearanceChange<'_>,
+) -> Result<i32, AppError> {
+    let old_level = lock_access_level(conn, user_id).await?;
+
+    sqlx::query!("UPDATE players SET access_level = $2 WHERE user_id = $1", user_id, new_level)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO clearance_audit (user_id, old_level, new_level, challenge_id, granted_by, reason)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+        user_id,
+        old_level,
+        new_level,
+        change.challenge_id,
+        change.granted_by,
+        change.reason
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(old_level)
+}
+
+/// Сообщает игроку о новом уровне доступа. Вызывается после коммита.
+pub async fn announce_clearance(state: &AppState, user_id: Uuid, old_level: i32, new_level: i32, reason: &str) {
+    let message = serde_json::json!({
+        "type": "clearance_changed",
+        "old_level": old_level,
+        "new_level": new_level,
+        "reason": reason,
+    });
+    send_to_user(state, user_id, message.to_string()).await;
+}
+
+/// Ответы сравниваются без учета регистра, пробелов по краям и повторных пробелов.
+pub fn normalize_answer(answer: &str) -> String {
+    answer.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Сколько секунд еще не принимаются ответы игрока на загадку, если не принимаются.
+pub async fn riddle_lockout(conn: &mut PgConnection, user_id: Uuid, challenge_id: Uuid) -> Result<Option<i64>, AppError> {
+    let remaining = sqlx::query_scalar!(
+        r#"
+        SELECT CEIL(EXTRACT(EPOCH FROM locked_until - NOW()))::BIGINT AS "remaining!"
+        FROM challenge_attempts WHERE user_id = $1 AND challenge_id = $2 AND locked_until > NOW()
+        "#,
+        user_id,
+        challenge_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(remaining)
+}
+
+/// Записывает неверный ответ. Ответы старше окна не считаются. Возвращает блокировку, если порог пройден.
+pub async fn record_wrong_answer(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    challenge_id: Uuid,
+    settings: &IntrusionSettings,
+) -> Result<Option<i64>, AppError> {
+    let failures = sqlx::query_scalar!(
+        r#"
+        INSERT INTO challenge_attempts (user_id, challenge_id, failures) VALUES ($1, $2, 1)
+        ON CONFLICT (user_id, challenge_id) DO UPDATE SET
+            failures = CASE
+                WHEN challenge_attempts.last_failed_at > NOW() - make_interval(secs => $3)
+                THEN challenge_attempts.failures + 1 ELSE 1
+            END,
+            last_failed_at = NOW()
+        RETURNING failures
+        "#,
+        user_id,
+        challenge_id,
+        f64::from(settings.window_secs)
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let lockout = cooldown_for(i64::from(failures), settings);
+    if let Some(secs) = lockout {
+        sqlx::query!(
+            r#"
+            UPDATE challenge_attempts SET locked_until = NOW() + make_interval(secs => $3)
+            WHERE user_id = $1 AND challenge_id = $2
+            "#,
+            user_id,
+            challenge_id,
+            secs as f64
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(lockout)
+}
+
+/// Верный ответ обнуляет счетчик неверных.
+pub async fn clear_wrong_answers(conn: &mut PgConnection, user_id: Uuid, challenge_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM challenge_attempts WHERE user_id = $1 AND challenge_id = $2", user_id, challenge_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
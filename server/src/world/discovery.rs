@@ -0,0 +1,168 @@
+// /var/www/structure/server/src/world/discovery.rs
+
+use super::rules::{self, RuleContext};
+use crate::{
+    error::AppError,
+    models::link::{LinkVisibility, LocationLink},
+};
+use rand::Rng;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Оставляет переходы, о которых игрок знает. Секретные переходы остаются в результате:
+/// по ним можно пройти, а из списков для игрока их убирает вызывающий код.
+/// Скрытые переходы с выполненным условием обнаружения считаются известными, но в базу
+/// не записываются: просмотр ничего не меняет, запоминают переходы поиск и перемещение.
+pub async fn known_links(
+    pool: &PgPool,
+    user_id: Uuid,
+    links: Vec<LocationLink>,
+    ctx: &RuleContext,
+) -> Result<Vec<LocationLink>, AppError> {
+    let hidden_ids: Vec<Uuid> = links
+        .iter()
+        .filter(|link| link.visibility == LinkVisibility::Hidden)
+        .map(|link| link.id)
+        .collect();
+    if hidden_ids.is_empty() {
+        return Ok(links);
+    }
+
+    let discovered: HashSet<Uuid> = sqlx::query_scalar!(
+        "SELECT link_id FROM player_discovered_links WHERE user_id = $1 AND link_id = ANY($2)",
+        user_id,
+        &hidden_ids
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    Ok(links
+        .into_iter()
+        .filter(|link| {
+            link.visibility != LinkVisibility::Hidden || discovered.contains(&link.id) || reveal_condition_met(link, ctx)
+        })
+        .collect())
+}
+
+/// Запоминает скрытые переходы из `links` как найденные - после того как игрок ими воспользовался.
+/// Дальше они остаются известными, даже если условие обнаружения перестанет выполняться.
+pub async fn remember_links(pool: &PgPool, user_id: Uuid, links: &[LocationLink]) -> Result<(), AppError> {
+    let ids: Vec<Uuid> = links
+        .iter()
+        .filter(|link| link.visibility == LinkVisibility::Hidden)
+        .map(|link| link.id)
+        .collect();
+    mark_discovered(pool, user_id, &ids).await
+}
+
+/// Один бросок поиска по всем еще не найденным скрытым переходам локации.
+/// Переходы с выполненным условием обнаружения находятся без броска.
+/// Возвращает переходы, обнаруженные этой попыткой.
+pub async fn search_location(
+    pool: &PgPool,
+    user_id: Uuid,
+    location_id: Uuid,
+    ctx: &RuleContext,
+) -> Result<Vec<LocationLink>, AppError> {
+    let candidates = sqlx::query_as!(
+        LocationLink,
+        r#"
+        SELECT id, source_location_id, target_location_id, link_text, required_access_level, access_condition,
+               denial_message, created_at, visibility AS "visibility: _", search_chance, reveal_condition,
+               travel_secs
+        FROM location_links l
+        WHERE source_location_id = $1 AND visibility = 'Hidden'
+          AND NOT EXISTS (SELECT 1 FROM player_discovered_links d WHERE d.user_id = $2 AND d.link_id = l.id)
+        "#,
+        location_id,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let found: Vec<LocationLink> = {
+        let mut rng = rand::thread_rng();
+        candidates
+            .into_iter()
+            .filter(|link| reveal_condition_met(link, ctx) || rng.gen_range(0..100) < link.search_chance)
+            .collect()
+    };
+
+    let ids: Vec<Uuid> = found.iter().map(|link| link.id).collect();
+    mark_discovered(pool, user_id, &ids).await?;
+
+    Ok(found)
+}
+
+/// Занимает попытку поиска в локации. Если с прошлой прошло меньше `cooldown_secs`,
+/// возвращает, сколько секунд еще ждать, и попытку не засчитывает.
+pub async fn claim_search(
+    pool: &PgPool,
+    user_id: Uuid,
+    location_id: Uuid,
+    cooldown_secs: u64,
+) -> Result<Option<i64>, AppError> {
+    let claimed = sqlx::query_scalar!(
+        r#"
+        INSERT INTO player_searches (user_id, location_id) VALUES ($1, $2)
+        ON CONFLICT (user_id, location_id) DO UPDATE SET searched_at = NOW()
+        WHERE player_searches.searched_at <= NOW() - make_interval(secs => $3)
+        RETURNING searched_at
+        "#,
+        user_id,
+        location_id,
+        cooldown_secs as f64
+    )
+    .fetch_optional(pool)
+    .await?;
+    if claimed.is_some() {
+        return Ok(None);
+    }
+
+    let remaining = sqlx::query_scalar!(
+        r#"
+        SELECT GREATEST(CEIL(EXTRACT(EPOCH FROM searched_at + make_interval(secs => $3) - NOW())), 1)::BIGINT
+            AS "remaining!"
+        FROM player_searches WHERE user_id = $1 AND location_id = $2
+        "#,
+        user_id,
+        location_id,
+        cooldown_secs as f64
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(Some(remaining))
+}
+
+async fn mark_discovered(pool: &PgPool, user_id: Uuid, link_ids: &[Uuid]) -> Result<(), AppError> {
+    if link_ids.is_empty() {
+        return Ok(());
+    }
+    sqlx::query!(
+        "INSERT INTO player_discovered_links (user_id, link_id)
+         SELECT $1, UNNEST($2::uuid[])
+         ON CONFLICT DO NOTHING",
+        user_id,
+        link_ids
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+fn reveal_condition_met(link: &LocationLink, ctx: &RuleContext) -> bool {
+    let Some(source) = link.reveal_condition.as_deref() else {
+        return false;
+    };
+    match rules::parse(source) {
+        Ok(condition) => condition.evaluate(ctx),
+        Err(e) => {
+            tracing::error!("Некорректное условие обнаружения у перехода {}: {}", link.id, e);
+            false
+        }
+    }
+}
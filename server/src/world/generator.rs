@@ -0,0 +1,280 @@
+// /server/src/world/generator.rs
+
+// Процедурный генератор кластеров локаций.
+//
+// По seed, размеру, кривой уровней доступа и грамматикам строится связный граф
+// локаций с названиями и описаниями. Генерация - чистая функция параметров:
+// один и тот же seed всегда дает ту же раскладку, поэтому черновик можно
+// спокойно пересоздать или закоммитить позже.
+//
+// Грамматика - набор символов с вариантами раскрытия; в вариантах можно
+// ссылаться на другие символы через {symbol}. Раскрытие начинается с "root":
+//   {"root": ["{adj} {noun}"], "adj": ["Сырой", "Темный"], "noun": ["тоннель", "коллектор"]}
+
+use super::templates::validate_template;
+use crate::error::AppError;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+pub const MAX_CLUSTER_SIZE: u32 = 100;
+/// Новая локация цепляется к одной из последних N созданных: получаются
+/// вытянутые тоннели с ответвлениями, а не "звезда" вокруг первой локации.
+const ATTACH_WINDOW: usize = 3;
+const MAX_EXPANSION_DEPTH: usize = 16;
+/// Ограничения на одно раскрытие "root": без них грамматика вида {"a": ["{a}{a}{a}{a}"]}
+/// за 16 уровней раскрывается миллиарды раз.
+const MAX_EXPANSION_SYMBOLS: usize = 1000;
+const MAX_EXPANSION_CHARS: usize = 4000;
+const ROOT_SYMBOL: &str = "root";
+/// Названия локаций и тексты переходов хранятся в VARCHAR(255).
+const MAX_NAME_CHARS: usize = 255;
+
+pub type Grammar = BTreeMap<String, Vec<String>>;
+
+/// Уровень доступа как функция удаленности от входа: `start` у входа, `end` в самой
+/// дальней точке. `exponent` > 1 оставляет большую часть кластера доступной,
+/// < 1 быстро поднимает уровень сразу за входом.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityCurve {
+    pub start: i32,
+    pub end: i32,
+    #[serde(default = "default_exponent")]
+    pub exponent: f64,
+}
+
+fn default_exponent() -> f64 {
+    1.0
+}
+
+impl SecurityCurve {
+    fn level_at(&self, t: f64) -> i32 {
+        let span = f64::from(self.end - self.start);
+        self.start + (span * t.clamp(0.0, 1.0).powf(self.exponent)).round() as i32
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorParams {
+    pub seed: u64,
+    pub size: u32,
+    pub security_curve: SecurityCurve,
+    pub name_grammar: Grammar,
+    pub description_grammar: Grammar,
+    /// Вероятность (0..1) дополнительной связи от каждой локации, образующей петлю.
+    #[serde(default)]
+    pub loop_chance: f64,
+}
+
+impl GeneratorParams {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.size == 0 || self.size > MAX_CLUSTER_SIZE {
+            return Err(AppError::BadRequest(format!("Size must be between 1 and {}", MAX_CLUSTER_SIZE)));
+        }
+        if !(0.0..=1.0).contains(&self.loop_chance) {
+            return Err(AppError::BadRequest("loop_chance must be between 0 and 1".to_string()));
+        }
+        if !self.security_curve.exponent.is_finite() || self.security_curve.exponent <= 0.0 {
+            return Err(AppError::BadRequest("Security curve exponent must be positive".to_string()));
+        }
+        validate_grammar("name", &self.name_grammar)?;
+        validate_grammar("description", &self.description_grammar)
+    }
+}
+
+/// Грамматика должна содержать "root", все символы - хотя бы один вариант,
+/// и ссылаться можно только на объявленные символы.
+fn validate_grammar(field: &str, grammar: &Grammar) -> Result<(), AppError> {
+    let invalid = |message: String| AppError::BadRequest(format!("Invalid {} grammar: {}", field, message));
+    if !grammar.contains_key(ROOT_SYMBOL) {
+        return Err(invalid(format!("missing '{}' symbol", ROOT_SYMBOL)));
+    }
+    for (symbol, options) in grammar {
+        if options.is_empty() {
+            return Err(invalid(format!("symbol '{}' has no options", symbol)));
+        }
+        for option in options {
+            for reference in references(option).map_err(&invalid)? {
+                if !grammar.contains_key(reference) {
+                    return Err(invalid(format!("'{}' refers to unknown symbol '{}'", symbol, reference)));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Имена символов, упомянутых в варианте через {symbol}.
+fn references(option: &str) -> Result<Vec<&str>, String> {
+    let mut found = Vec::new();
+    let mut rest = option;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').ok_or_else(|| format!("unclosed '{{' in \"{}\"", option))?;
+        found.push(&rest[start + 1..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    Ok(found)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedLocation {
+    /// Номер локации внутри кластера; 0 - вход.
+    pub index: usize,
+    pub name: String,
+    pub description: String,
+    pub security_level: i32,
+    /// Количество переходов от входа.
+    pub depth: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedLink {
+    pub source: usize,
+    pub target: usize,
+    pub link_text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedCluster {
+    pub locations: Vec<GeneratedLocation>,
+    pub links: Vec<GeneratedLink>,
+}
+
+/// Строит кластер. Параметры должны быть проверены через `GeneratorParams::validate`.
+pub fn generate(params: &GeneratorParams) -> Result<GeneratedCluster, AppError> {
+    let mut rng = ChaCha8Rng::seed_from_u64(params.seed);
+    let size = params.size as usize;
+
+    // Остов - дерево, поэтому кластер всегда связный; петли добавляются поверх
+    let mut edges: Vec<(usize, usize)> = Vec::with_capacity(size * 2);
+    for i in 1..size {
+        let parent = rng.gen_range(i.saturating_sub(ATTACH_WINDOW)..i);
+        edges.push((parent, i));
+    }
+    for i in 2..size {
+        if rng.gen_bool(params.loop_chance) {
+            let other = rng.gen_range(0..i);
+            let exists = edges.iter().any(|&(a, b)| (a, b) == (other, i) || (a, b) == (i, other));
+            if !exists {
+                edges.push((other, i));
+            }
+        }
+    }
+
+    let depths = bfs_depths(size, &edges);
+    let max_depth = depths.iter().copied().max().unwrap_or(0).max(1);
+
+    let mut used_names = HashSet::new();
+    let mut locations = Vec::with_capacity(size);
+    for (index, &depth) in depths.iter().enumerate() {
+        let base = expand_root(&params.name_grammar, &mut rng)?;
+        // Одинаковые названия в одном кластере путают игроков. Название с номером тоже
+        // могло уже выпасть, поэтому номер растет, пока название не станет уникальным
+        let mut name = base.clone();
+        let mut suffix = index + 1;
+        while used_names.contains(&name) {
+            name = format!("{} {}", base, suffix);
+            suffix += 1;
+        }
+        // Название становится и текстом переходов: длиннее столбца его не сохранить
+        if name.chars().count() > MAX_NAME_CHARS {
+            return Err(AppError::BadRequest(format!(
+                "Generated location name is longer than {} characters",
+                MAX_NAME_CHARS
+            )));
+        }
+        used_names.insert(name.clone());
+        // Описание потом отрисовывается как шаблон, поэтому сохранять можно только разбираемый текст
+        let description = expand_root(&params.description_grammar, &mut rng)?;
+        validate_template("generated description", &description)?;
+        locations.push(GeneratedLocation {
+            index,
+            name,
+            description,
+            security_level: params.security_curve.level_at(f64::from(depth) / f64::from(max_depth)),
+            depth,
+        });
+    }
+
+    // Переходы двусторонние; текст перехода - название локации, куда он ведет
+    let links = edges
+        .iter()
+        .flat_map(|&(a, b)| [(a, b), (b, a)])
+        .map(|(source, target)| GeneratedLink { source, target, link_text: locations[target].name.clone() })
+        .collect();
+
+    Ok(GeneratedCluster { locations, links })
+}
+
+fn bfs_depths(size: usize, edges: &[(usize, usize)]) -> Vec<u32> {
+    let mut adjacency = vec![Vec::new(); size];
+    for &(a, b) in edges {
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+    let mut depths = vec![u32::MAX; size];
+    let mut queue = VecDeque::from([0]);
+    depths[0] = 0;
+    while let Some(node) = queue.pop_front() {
+        for &next in &adjacency[node] {
+            if depths[next] == u32::MAX {
+                depths[next] = depths[node] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    depths
+}
+
+/// Сколько еще можно раскрыть символов и выдать символов текста.
+struct ExpansionBudget {
+    symbols: usize,
+    chars: usize,
+}
+
+fn expand_root(grammar: &Grammar, rng: &mut ChaCha8Rng) -> Result<String, AppError> {
+    let mut budget = ExpansionBudget { symbols: MAX_EXPANSION_SYMBOLS, chars: MAX_EXPANSION_CHARS };
+    expand(grammar, ROOT_SYMBOL, rng, 0, &mut budget)
+}
+
+fn expand(
+    grammar: &Grammar,
+    symbol: &str,
+    rng: &mut ChaCha8Rng,
+    depth: usize,
+    budget: &mut ExpansionBudget,
+) -> Result<String, AppError> {
+    if depth > MAX_EXPANSION_DEPTH {
+        return Err(AppError::BadRequest(format!(
+            "Grammar recursion is deeper than {} levels (at '{}')",
+            MAX_EXPANSION_DEPTH, symbol
+        )));
+    }
+    budget.symbols = budget.symbols.checked_sub(1).ok_or_else(|| {
+        AppError::BadRequest(format!("Grammar expands more than {} symbols", MAX_EXPANSION_SYMBOLS))
+    })?;
+    let options = &grammar[symbol];
+    let option = &options[rng.gen_range(0..options.len())];
+
+    let mut out = String::with_capacity(option.len());
+    let mut rest = option.as_str();
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}').unwrap_or(rest.len() - start);
+        push_limited(&mut out, &rest[..start], budget)?;
+        out.push_str(&expand(grammar, &rest[start + 1..end], rng, depth + 1, budget)?);
+        rest = rest.get(end + 1..).unwrap_or_default();
+    }
+    push_limited(&mut out, rest, budget)?;
+    Ok(out)
+}
+
+/// Дописывает литеральный текст варианта, расходуя бюджет. Раскрытия вложенных символов
+/// уже оплачены их собственными литералами.
+fn push_limited(out: &mut String, text: &str, budget: &mut ExpansionBudget) -> Result<(), AppError> {
+    budget.chars = budget.chars.checked_sub(text.chars().count()).ok_or_else(|| {
+        AppError::BadRequest(format!("Grammar expands to more than {} characters", MAX_EXPANSION_CHARS))
+    })?;
+    out.push_str(text);
+    Ok(())
+}
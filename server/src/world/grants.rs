@@ -0,0 +1,285 @@
+// /server/src/world/grants.rs
+
+// Временные допуски.
+//
+// Помимо постоянного уровня доступа игроку можно выдать допуск на время: уровень
+// ("уровень 3 на 15 минут") или доступ к конкретной локации ("до полуночи").
+// Действующий уровень - наибольший из постоянного и активных допусков; допуск
+// к локации снимает только проверку уровня, политика зоны и изоляция действуют как обычно.
+//
+// Разовый пропуск - допуск к локации на один вход: он сгорает, как только игрок
+// ее покидает, но не позже своего срока.
+//
+// Фоновая задача отмечает истекшие допуски обработанными и выводит игроков
+// из локаций, к которым у них больше нет допуска, в безопасную локацию.
+
+use super::{
+    capacity::process_queue,
+    instances::release_instance,
+    quests::{emit_quest_events, QuestEvent},
+    spawn::pick_spawn_location,
+    trade::{cancel_player_trade, find_player_trade, lock_player_trade, notify_trade_cancelled, CANCELLED_LEFT_ROOM},
+    visits::record_visit,
+    zones::fetch_location,
+};
+use crate::{
+    error::AppError,
+    models::{challenge::ClearanceGrant, location::Location, user::UserRole},
+    state::AppState,
+    ws::{
+        utils::{change_room, send_to_user},
+        RoomKey,
+    },
+};
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgExecutor};
+use std::time::Duration;
+use uuid::Uuid;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Что дает временный допуск.
+pub enum GrantScope {
+    AccessLevel(i32),
+    Location(Uuid),
+    /// Разовый пропуск в локацию.
+    Pass(Uuid),
+}
+
+/// Действующий допуск игрока с учетом временных.
+pub struct ActiveClearance {
+    pub access_level: i32,
+    pub locations: Vec<Uuid>,
+}
+
+impl ActiveClearance {
+    pub fn clears(&self, location: &Location) -> bool {
+        self.access_level >= location.security_level || self.locations.contains(&location.id)
+    }
+}
+
+pub async fn active_clearance<'e>(executor: impl PgExecutor<'e>, user_id: Uuid) -> Result<ActiveClearance, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT GREATEST(p.access_level,
+                        (SELECT MAX(g.access_level) FROM clearance_grants g
+                         WHERE g.user_id = p.user_id AND g.expires_at > NOW())) AS "access_level!",
+               ARRAY(SELECT g.location_id FROM clearance_grants g
+                     WHERE g.user_id = p.user_id AND g.location_id IS NOT NULL AND g.expires_at > NOW()) AS "locations!"
+        FROM players p WHERE p.user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(ActiveClearance { access_level: row.access_level, locations: row.locations })
+}
+
+/// Выдает допуск. `granted_by = None` - допуск выдан игровым событием.
+pub async fn issue_grant(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    scope: GrantScope,
+    expires_at: DateTime<Utc>,
+    granted_by: Option<Uuid>,
+    reason: &str,
+) -> Result<ClearanceGrant, AppError> {
+    let (access_level, location_id, single_use) = match scope {
+        GrantScope::AccessLevel(level) => (Some(level), None, false),
+        GrantScope::Location(location_id) => (None, Some(location_id), false),
+        GrantScope::Pass(location_id) => (None, Some(location_id), true),
+    };
+    let grant = sqlx::query_as!(
+        ClearanceGrant,
+        r#"
+        INSERT INTO clearance_grants (user_id, access_level, location_id, single_use, expires_at, granted_by, reason)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
+        "#,
+        user_id,
+        access_level,
+        location_id,
+        single_use,
+        expires_at,
+        granted_by,
+        reason
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(grant)
+}
+
+/// Гасит разовые пропуска в локацию, которую игрок покинул. Вызывается в транзакции перемещения.
+pub async fn consume_passes(conn: &mut PgConnection, user_id: Uuid, location_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE clearance_grants SET expires_at = NOW(), processed = TRUE
+        WHERE user_id = $1 AND location_id = $2 AND single_use AND expires_at > NOW()
+        "#,
+        user_id,
+        location_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Сообщает игроку о новом допуске. Вызывается после коммита.
+pub async fn announce_grant(state: &AppState, grant: &ClearanceGrant) {
+    let message = serde_json::json!({ "type": "clearance_grant_issued", "grant": grant });
+    send_to_user(state, grant.user_id, message.to_string()).await;
+}
+
+/// Фоновая задача: обрабатывает истекшие допуски.
+pub fn spawn_grant_sweeper(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep_grants(&state).await {
+                tracing::error!("Не удалось обработать истекшие допуски: {:?}", e);
+            }
+        }
+    });
+}
+
+async fn sweep_grants(state: &AppState) -> Result<(), AppError> {
+    let user_ids = sqlx::query_scalar!(
+        "SELECT DISTINCT user_id FROM clearance_grants WHERE NOT processed AND expires_at <= NOW()"
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    // Ошибка у одного игрока не должна задерживать выселение остальных
+    for user_id in user_ids {
+        if let Err(e) = expire_grants(state, user_id).await {
+            tracing::error!("Не удалось обработать истекшие допуски игрока {}: {:?}", user_id, e);
+        }
+    }
+    Ok(())
+}
+
+/// Обрабатывает истекшие допуски игрока: если его локация больше ему не доступна,
+/// он перемещается в безопасную локацию. Отметка об обработке и перемещение -
+/// в одной транзакции под блокировкой строки игрока.
+pub async fn expire_grants(state: &AppState, user_id: Uuid) -> Result<(), AppError> {
+    let mut tx = state.pool.begin().await?;
+    // Сделка блокируется раньше строки игрока (см. world::trade), иначе выселение
+    // взаимоблокируется с одновременным подтверждением сделки
+    let trade_id = lock_player_trade(&mut tx, user_id).await?;
+    let player = sqlx::query!(
+        r#"
+        SELECT p.current_location_id, p.current_instance_id, u.username, u.role AS "role: UserRole"
+        FROM players p JOIN users u ON u.id = p.user_id
+        WHERE p.user_id = $1
+        FOR UPDATE OF p
+        "#,
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    // Пока строка игрока была свободна, он мог открыть новую сделку. Ее блокировка сейчас нарушила бы
+    // порядок, поэтому допуски остаются необработанными и фоновая задача вернется к ним на следующем проходе
+    if find_player_trade(&mut tx, user_id).await? != trade_id {
+        return Ok(());
+    }
+
+    let expired = sqlx::query_scalar!(
+        r#"
+        UPDATE clearance_grants SET processed = TRUE
+        WHERE user_id = $1 AND NOT processed AND expires_at <= NOW()
+        RETURNING id
+        "#,
+        user_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    // Другой вызов уже успел все обработать
+    if expired.is_empty() {
+        return Ok(());
+    }
+
+    let mut eviction = None;
+    if let Some(location_id) = player.current_location_id {
+        let location = fetch_location(&mut *tx, location_id).await?;
+        if !active_clearance(&mut *tx, user_id).await?.clears(&location) {
+            let safe_location_id = safe_location(&mut tx, player.role, state).await?;
+            if safe_location_id == location_id {
+                tracing::warn!("Безопасная локация {} сама закрыта для игрока {}", location_id, user_id);
+            } else {
+                eviction = Some((location, safe_location_id));
+            }
+        }
+    }
+
+    let Some((location, safe_location_id)) = eviction else {
+        tx.commit().await?;
+        let message = serde_json::json!({ "type": "clearance_grant_expired", "grant_ids": expired });
+        send_to_user(state, user_id, message.to_string()).await;
+        return Ok(());
+    };
+
+    let cancelled_trade = cancel_player_trade(&mut tx, user_id).await?;
+    // Выведенный игрок никуда уже не прибудет
+    sqlx::query!("DELETE FROM player_transits WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!(
+        "UPDATE players SET current_location_id = $1, current_instance_id = NULL WHERE user_id = $2",
+        safe_location_id,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if let Some(instance_id) = player.current_instance_id {
+        release_instance(&mut tx, instance_id).await?;
+    }
+    record_visit(&mut *tx, user_id, safe_location_id).await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "У игрока {} истек допуск к локации {}, он перемещен в {}",
+        player.username,
+        location.id,
+        safe_location_id
+    );
+    if let Some((trade_id, participants)) = cancelled_trade {
+        notify_trade_cancelled(state, trade_id, &participants, CANCELLED_LEFT_ROOM).await;
+    }
+    change_room(
+        state,
+        user_id,
+        &player.username,
+        Some(RoomKey::new(location.id, player.current_instance_id)),
+        RoomKey::new(safe_location_id, None),
+    )
+    .await;
+    let message = serde_json::json!({
+        "type": "clearance_grant_expired",
+        "grant_ids": expired,
+        "evicted_to": safe_location_id,
+        "message": "СРОК ВРЕМЕННОГО ДОПУСКА ИСТЕК. ВЫ ВЫВЕДЕНЫ В БЕЗОПАСНУЮ ЗОНУ.",
+    });
+    send_to_user(state, user_id, message.to_string()).await;
+    emit_quest_events(state, user_id, vec![QuestEvent::Visited(safe_location_id)]).await;
+
+    if location.capacity.is_some() {
+        process_queue(state, location.id).await?;
+    }
+    Ok(())
+}
+
+/// SAFE_LOCATION, если такая локация существует, иначе точка появления для роли игрока.
+async fn safe_location(conn: &mut PgConnection, role: UserRole, state: &AppState) -> Result<Uuid, AppError> {
+    let configured = sqlx::query_scalar!("SELECT id FROM locations WHERE id = $1", state.config.safe_location_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match configured {
+        Some(location_id) => Ok(location_id),
+        None => pick_spawn_location(conn, role, &state.config).await,
+    }
+}
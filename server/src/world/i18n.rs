@@ -0,0 +1,151 @@
+// /server/src/world/i18n.rs
+
+use crate::{
+    auth::Claims,
+    config::Config,
+    error::AppError,
+    models::{link::LocationLink, location::Location},
+    state::AppState,
+};
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+use sqlx::PgExecutor;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Язык, на котором игроку показывается контент.
+/// Порядок выбора: настройка игрока, затем заголовок Accept-Language, затем язык по умолчанию.
+pub struct Language(pub String);
+
+#[async_trait]
+impl FromRequestParts<AppState> for Language {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if let Some(claims) = parts.extensions.get::<Claims>()
+            && let Some(language) = preferred_language(&state.pool, claims.sub, &state.config).await?
+        {
+            return Ok(Language(language));
+        }
+
+        let accept_language = parts
+            .headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        Ok(Language(negotiate_language(accept_language, &state.config)))
+    }
+}
+
+/// Язык, выбранный игроком в настройках, если он все еще поддерживается.
+/// Там, где нет HTTP-заголовков (WebSocket), без настройки берется язык по умолчанию.
+pub async fn preferred_language<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    config: &Config,
+) -> Result<Option<String>, AppError> {
+    let preferred = sqlx::query_scalar!("SELECT preferred_language FROM users WHERE id = $1", user_id)
+        .fetch_optional(executor)
+        .await?
+        .flatten();
+
+    Ok(preferred.filter(|language| config.supported_languages.contains(language)))
+}
+
+/// Выбирает из Accept-Language (например, `en-US,en;q=0.9,ru;q=0.8`) первый поддерживаемый язык.
+/// Региональные варианты сводятся к основному языку: `en-GB` -> `en`.
+pub fn negotiate_language(accept_language: &str, config: &Config) -> String {
+    let mut candidates: Vec<(f32, String)> = accept_language
+        .split(',')
+        .filter_map(|part| {
+            let mut pieces = part.split(';');
+            let tag = pieces.next()?.trim();
+            let quality = pieces
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            let primary = tag.split('-').next()?.to_ascii_lowercase();
+            (quality > 0.0 && !primary.is_empty() && primary != "*").then_some((quality, primary))
+        })
+        .collect();
+    // Стабильная сортировка: при равном q побеждает язык, указанный раньше
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    candidates
+        .into_iter()
+        .map(|(_, language)| language)
+        .find(|language| config.supported_languages.contains(language))
+        .unwrap_or_else(|| config.default_language.clone())
+}
+
+/// Подставляет переводы в локации. Поля без перевода остаются на языке по умолчанию.
+pub async fn localize_locations<'e>(
+    executor: impl PgExecutor<'e>,
+    locations: &mut [Location],
+    language: &str,
+    config: &Config,
+) -> Result<(), AppError> {
+    if language == config.default_language || locations.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<Uuid> = locations.iter().map(|location| location.id).collect();
+    let mut translations: HashMap<Uuid, _> = sqlx::query!(
+        r#"
+        SELECT location_id, name, description, ambient_description
+        FROM location_translations WHERE location_id = ANY($1) AND language = $2
+        "#,
+        &ids,
+        language
+    )
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(|row| (row.location_id, row))
+    .collect();
+
+    for location in locations.iter_mut() {
+        if let Some(translation) = translations.remove(&location.id) {
+            location.name = translation.name;
+            location.description = translation.description;
+            // Фоновое описание могло прийти из зоны - тогда перевода на уровне локации нет
+            if translation.ambient_description.is_some() {
+                location.ambient_description = translation.ambient_description;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Подставляет переводы в тексты переходов.
+pub async fn localize_links<'e>(
+    executor: impl PgExecutor<'e>,
+    links: &mut [LocationLink],
+    language: &str,
+    config: &Config,
+) -> Result<(), AppError> {
+    if language == config.default_language || links.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<Uuid> = links.iter().map(|link| link.id).collect();
+    let mut translations: HashMap<Uuid, String> = sqlx::query!(
+        "SELECT link_id, link_text FROM link_translations WHERE link_id = ANY($1) AND language = $2",
+        &ids,
+        language
+    )
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .map(|row| (row.link_id, row.link_text))
+    .collect();
+
+    for link in links.iter_mut() {
+        if let Some(link_text) = translations.remove(&link.id) {
+            link.link_text = link_text;
+        }
+    }
+    Ok(())
+}
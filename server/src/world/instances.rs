@@ -0,0 +1,154 @@
+// /var/www/structure/server/src/world/instances.rs
+
+use crate::{error::AppError, state::AppState, ws::RoomKey};
+use serde_json::Value;
+use sqlx::{PgConnection, PgExecutor};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Как часто фоновая задача ищет опустевшие копии.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Возвращает копию инстанцированной локации для игрока: общую копию его группы
+/// или личную, если он играет один. Копия создается при первом входе.
+pub async fn enter_instance(conn: &mut PgConnection, user_id: Uuid, location_id: Uuid) -> Result<Uuid, AppError> {
+    let party_id = sqlx::query_scalar!("SELECT party_id FROM party_members WHERE user_id = $1", user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    // Уникальные индексы не дают двум участникам группы одновременно создать две копии
+    let created = match party_id {
+        Some(party_id) => {
+            sqlx::query_scalar!(
+                "INSERT INTO location_instances (location_id, party_id) VALUES ($1, $2)
+                 ON CONFLICT (location_id, party_id) WHERE party_id IS NOT NULL DO NOTHING
+                 RETURNING id",
+                location_id,
+                party_id
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        None => {
+            sqlx::query_scalar!(
+                "INSERT INTO location_instances (location_id, owner_id) VALUES ($1, $2)
+                 ON CONFLICT (location_id, owner_id) WHERE owner_id IS NOT NULL DO NOTHING
+                 RETURNING id",
+                location_id,
+                user_id
+            )
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+    };
+
+    // Новая копия получает предметы, разложенные Архитекторами в самой локации
+    if let Some(instance_id) = created {
+        sqlx::query!(
+            r#"
+            INSERT INTO location_items (location_id, instance_id, item_id, quantity, stacked, placed_by)
+            SELECT location_id, $2, item_id, quantity, stacked, placed_by
+            FROM location_items WHERE location_id = $1 AND instance_id IS NULL
+            "#,
+            location_id,
+            instance_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    let instance_id = sqlx::query_scalar!(
+        r#"
+        UPDATE location_instances SET emptied_at = NULL
+        WHERE location_id = $1
+          AND (party_id = $2 OR (owner_id = $3 AND $2::uuid IS NULL))
+        RETURNING id
+        "#,
+        location_id,
+        party_id,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(instance_id)
+}
+
+/// Вызывается после того, как игрок покинул копию: если внутри никого не осталось,
+/// запускается отсчет до ее удаления.
+pub async fn release_instance(conn: &mut PgConnection, instance_id: Uuid) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE location_instances SET emptied_at = NOW()
+        WHERE id = $1 AND emptied_at IS NULL
+          AND NOT EXISTS (SELECT 1 FROM players WHERE current_instance_id = $1)
+        "#,
+        instance_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Меняет ключ в собственном состоянии копии (например, открытая дверь). Внутри копии
+/// состояние перекрывает флаги мира в условиях и пропадает вместе с копией.
+/// Возвращает `false`, если копии уже нет.
+pub async fn set_instance_state<'e>(
+    executor: impl PgExecutor<'e>,
+    instance_id: Uuid,
+    key: &str,
+    value: &Value,
+) -> Result<bool, AppError> {
+    let result = sqlx::query!(
+        "UPDATE location_instances SET state = state || jsonb_build_object($2::text, $3::jsonb) WHERE id = $1",
+        instance_id,
+        key,
+        value
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Фоновая задача: удаляет копии, которые пустуют дольше настроенного таймаута,
+/// вместе с их комнатами в WsState.
+pub fn spawn_instance_sweeper(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep_instances(&state).await {
+                tracing::error!("Не удалось удалить пустые копии локаций: {:?}", e);
+            }
+        }
+    });
+}
+
+async fn sweep_instances(state: &AppState) -> Result<(), AppError> {
+    let timeout_secs = state.config.instance_empty_timeout_secs as f64;
+    let removed = sqlx::query!(
+        r#"
+        DELETE FROM location_instances i
+        WHERE i.emptied_at < NOW() - make_interval(secs => $1)
+          AND NOT EXISTS (SELECT 1 FROM players p WHERE p.current_instance_id = i.id)
+        RETURNING id, location_id
+        "#,
+        timeout_secs
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    if removed.is_empty() {
+        return Ok(());
+    }
+
+    let mut rooms = state.ws_state.rooms.lock().await;
+    for instance in &removed {
+        rooms.remove(&RoomKey::new(instance.location_id, Some(instance.id)));
+    }
+    tracing::debug!("Удалено пустых копий локаций: {}", removed.len());
+
+    Ok(())
+}
@@ -0,0 +1,209 @@
+// /server/src/world/intrusion.rs
+
+// Система обнаружения вторжений.
+//
+// Каждая попытка войти в локацию без достаточного уровня доступа записывается.
+// Если один игрок пытается слишком часто, его перемещения блокируются, и каждая
+// следующая попытка удваивает блокировку. Если в локацию ломятся слишком часто
+// все вместе, в ее зоне объявляется тревога. Модераторы видят попытки сразу.
+// Пороги задаются для локации в intrusion_settings, незаданные берутся из конфигурации.
+
+use super::zones::zone_location_ids;
+use crate::{
+    config::Config,
+    error::AppError,
+    models::location::Location,
+    state::AppState,
+    ws::utils::{broadcast_to_locations, send_to_staff, send_to_user},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+/// Дольше блокировка не растет, сколько бы попыток ни было.
+const MAX_COOLDOWN_SECS: i64 = 3600;
+
+/// Действующие пороги для локации.
+#[derive(Debug, Serialize)]
+pub struct IntrusionSettings {
+    pub window_secs: i32,
+    pub cooldown_after: i32,
+    pub cooldown_secs: i32,
+    pub alarm_after: i32,
+    pub alarm_secs: i32,
+}
+
+pub async fn load_settings<'e>(
+    executor: impl PgExecutor<'e>,
+    location_id: Uuid,
+    config: &Config,
+) -> Result<IntrusionSettings, AppError> {
+    let own = sqlx::query!(
+        r#"
+        SELECT window_secs, cooldown_after, cooldown_secs, alarm_after, alarm_secs
+        FROM intrusion_settings WHERE location_id = $1
+        "#,
+        location_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(IntrusionSettings {
+        window_secs: own.as_ref().and_then(|s| s.window_secs).unwrap_or(config.intrusion_window_secs),
+        cooldown_after: own.as_ref().and_then(|s| s.cooldown_after).unwrap_or(config.intrusion_cooldown_after),
+        cooldown_secs: own.as_ref().and_then(|s| s.cooldown_secs).unwrap_or(config.intrusion_cooldown_secs),
+        alarm_after: own.as_ref().and_then(|s| s.alarm_after).unwrap_or(config.intrusion_alarm_after),
+        alarm_secs: own.as_ref().and_then(|s| s.alarm_secs).unwrap_or(config.intrusion_alarm_secs),
+    })
+}
+
+/// Сколько секунд еще заблокированы перемещения игрока, если заблокированы.
+pub async fn movement_cooldown(pool: &PgPool, user_id: Uuid) -> Result<Option<i64>, AppError> {
+    let remaining = sqlx::query_scalar!(
+        r#"
+        SELECT CEIL(EXTRACT(EPOCH FROM movement_locked_until - NOW()))::BIGINT AS "remaining!"
+        FROM players WHERE user_id = $1 AND movement_locked_until > NOW()
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(remaining)
+}
+
+/// Блокировка после `attempts` попыток: с порога - базовое время, дальше удваивается.
+pub fn cooldown_for(attempts: i64, settings: &IntrusionSettings) -> Option<i64> {
+    let extra = attempts - i64::from(settings.cooldown_after);
+    if extra < 0 {
+        return None;
+    }
+    let secs = i64::from(settings.cooldown_secs).saturating_mul(1 << extra.min(20));
+    Some(secs.min(MAX_COOLDOWN_SECS))
+}
+
+/// Записывает попытку войти в `location` с уровнем `access_level`, блокирует
+/// перемещения нарушителя и поднимает тревогу в зоне, если пороги превышены.
+pub async fn record_intrusion(
+    state: &AppState,
+    user_id: Uuid,
+    username: &str,
+    location: &Location,
+    access_level: i32,
+) -> Result<(), AppError> {
+    let settings = load_settings(&state.pool, location.id, &state.config).await?;
+    let window_secs = f64::from(settings.window_secs);
+
+    let mut tx = state.pool.begin().await?;
+    // Попытки в одной зоне обрабатываются по очереди, чтобы не поднять две тревоги сразу
+    sqlx::query!("SELECT id FROM zones WHERE id = $1 FOR UPDATE", location.zone_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO intrusion_attempts (user_id, location_id, required_level, access_level)
+        VALUES ($1, $2, $3, $4)
+        "#,
+        user_id,
+        location.id,
+        location.security_level,
+        access_level
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let counts = sqlx::query!(
+        r#"
+        SELECT COUNT(*) FILTER (WHERE user_id = $1) AS "own!",
+               COUNT(*) FILTER (WHERE location_id = $2) AS "location!"
+        FROM intrusion_attempts
+        WHERE (user_id = $1 OR location_id = $2) AND created_at > NOW() - make_interval(secs => $3)
+        "#,
+        user_id,
+        location.id,
+        window_secs
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let cooldown_secs = cooldown_for(counts.own, &settings);
+    if let Some(secs) = cooldown_secs {
+        sqlx::query!(
+            "UPDATE players SET movement_locked_until = NOW() + make_interval(secs => $2) WHERE user_id = $1",
+            user_id,
+            secs as f64
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let mut alarm: Option<(Uuid, DateTime<Utc>)> = None;
+    if counts.location >= i64::from(settings.alarm_after) {
+        alarm = sqlx::query!(
+            r#"
+            INSERT INTO zone_alarms (zone_id, location_id, triggered_by, expires_at)
+            SELECT $1, $2, $3, NOW() + make_interval(secs => $4)
+            WHERE NOT EXISTS (SELECT 1 FROM zone_alarms WHERE zone_id = $1 AND expires_at > NOW())
+            RETURNING id, expires_at
+            "#,
+            location.zone_id,
+            location.id,
+            user_id,
+            f64::from(settings.alarm_secs)
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|row| (row.id, row.expires_at));
+    }
+    tx.commit().await?;
+
+    tracing::warn!(
+        "Попытка несанкционированного доступа от {} к локации {} ({} за окно, блокировка {:?})",
+        username,
+        location.id,
+        counts.own,
+        cooldown_secs
+    );
+
+    let warning = match cooldown_secs {
+        Some(secs) => format!("НЕСАНКЦИОНИРОВАННЫЙ ДОСТУП ЗАФИКСИРОВАН. ПЕРЕМЕЩЕНИЯ ЗАБЛОКИРОВАНЫ НА {} С.", secs),
+        None => "НЕСАНКЦИОНИРОВАННЫЙ ДОСТУП ЗАФИКСИРОВАН.".to_string(),
+    };
+    let message = serde_json::json!({
+        "type": "security_warning",
+        "location_id": location.id,
+        "cooldown_secs": cooldown_secs,
+        "message": warning,
+    });
+    send_to_user(state, user_id, message.to_string()).await;
+
+    let message = serde_json::json!({
+        "type": "intrusion_detected",
+        "user_id": user_id,
+        "username": username,
+        "location_id": location.id,
+        "location_name": location.name,
+        "required_level": location.security_level,
+        "access_level": access_level,
+        "attempts": counts.own,
+        "cooldown_secs": cooldown_secs,
+        "alarm_id": alarm.map(|(id, _)| id),
+    });
+    send_to_staff(state, message.to_string()).await;
+
+    if let Some((alarm_id, expires_at)) = alarm {
+        tracing::warn!("Тревога в зоне {}: попытки проникновения в {}", location.zone_id, location.id);
+        let location_ids = zone_location_ids(&state.pool, location.zone_id).await?;
+        let message = serde_json::json!({
+            "type": "zone_alarm",
+            "alarm_id": alarm_id,
+            "zone_id": location.zone_id,
+            "location_id": location.id,
+            "expires_at": expires_at,
+            "message": "ТРЕВОГА! ЗАФИКСИРОВАНА ПОПЫТКА ПРОНИКНОВЕНИЯ.",
+        });
+        broadcast_to_locations(state, &location_ids, message.to_string()).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,196 @@
+// /server/src/world/inventory.rs
+
+// Инвентарь игроков.
+//
+// Складываемые предметы хранятся одной строкой с количеством, остальные -
+// отдельной строкой на каждую штуку, чтобы у экземпляра могла появиться своя история.
+// Все изменения идут через `grant_item` и `take_item` внутри транзакции вызывающего:
+// передача предмета между игроками или локациями - это пара вызовов в одной транзакции.
+
+use super::{grants::active_clearance, transit::active_transit};
+use crate::{
+    error::AppError,
+    models::{item::InventoryItem, player::Player},
+};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Больше штук нескладываемого предмета за раз не выдается: каждая штука - отдельная строка.
+pub const MAX_GRANT_QUANTITY: i32 = 1000;
+
+/// Игрок вместе с инвентарем.
+pub async fn fetch_player(pool: &PgPool, user_id: Uuid) -> Result<Player, AppError> {
+    let row = sqlx::query!(
+        "SELECT user_id, current_location_id, access_level, credits, current_instance_id FROM players WHERE user_id = $1",
+        user_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Player {
+        user_id: row.user_id,
+        current_location_id: row.current_location_id,
+        access_level: row.access_level,
+        effective_access_level: active_clearance(pool, user_id).await?.access_level,
+        credits: row.credits,
+        current_instance_id: row.current_instance_id,
+        inventory: load_inventory(pool, user_id).await?,
+        transit: active_transit(pool, user_id).await?,
+    })
+}
+
+pub async fn load_inventory(pool: &PgPool, user_id: Uuid) -> Result<Vec<InventoryItem>, AppError> {
+    let items = sqlx::query_as!(
+        InventoryItem,
+        r#"
+        SELECT pi.id, pi.item_id, d.name, d.description, pi.quantity, d.stackable, d.weight, d.tags,
+               d.consumable, (d.use_message IS NOT NULL) AS "usable!", pi.acquired_at
+        FROM player_items pi
+        JOIN item_definitions d ON d.id = pi.item_id
+        WHERE pi.user_id = $1
+        ORDER BY d.name, pi.acquired_at
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(items)
+}
+
+/// Один экземпляр из инвентаря игрока; чужие экземпляры не находятся.
+pub async fn find_inventory_item(pool: &PgPool, user_id: Uuid, instance_id: Uuid) -> Result<InventoryItem, AppError> {
+    let item = sqlx::query_as!(
+        InventoryItem,
+        r#"
+        SELECT pi.id, pi.item_id, d.name, d.description, pi.quantity, d.stackable, d.weight, d.tags,
+               d.consumable, (d.use_message IS NOT NULL) AS "usable!", pi.acquired_at
+        FROM player_items pi
+        JOIN item_definitions d ON d.id = pi.item_id
+        WHERE pi.id = $1 AND pi.user_id = $2
+        "#,
+        instance_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(item)
+}
+
+/// Кладет предметы в инвентарь. Складываемые добавляются к существующей стопке.
+pub async fn grant_item(conn: &mut PgConnection, user_id: Uuid, item_id: &str, quantity: i32) -> Result<(), AppError> {
+    if quantity <= 0 {
+        return Err(AppError::BadRequest("Quantity must be positive".to_string()));
+    }
+    let stackable = sqlx::query_scalar!("SELECT stackable FROM item_definitions WHERE id = $1", item_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown item '{}'", item_id)))?;
+
+    if stackable {
+        sqlx::query!(
+            r#"
+            INSERT INTO player_items (user_id, item_id, quantity, stacked) VALUES ($1, $2, $3, TRUE)
+            ON CONFLICT (user_id, item_id) WHERE stacked
+            DO UPDATE SET quantity = player_items.quantity + EXCLUDED.quantity
+            "#,
+            user_id,
+            item_id,
+            quantity
+        )
+        .execute(&mut *conn)
+        .await?;
+    } else {
+        if quantity > MAX_GRANT_QUANTITY {
+            return Err(AppError::BadRequest(format!(
+                "At most {} non-stackable items can be granted at once",
+                MAX_GRANT_QUANTITY
+            )));
+        }
+        sqlx::query!(
+            "INSERT INTO player_items (user_id, item_id) SELECT $1, $2 FROM generate_series(1, $3)",
+            user_id,
+            item_id,
+            quantity
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Забирает `quantity` штук (`None` - все) из экземпляра игрока под блокировкой строки
+/// и возвращает код предмета и сколько штук забрано. Опустевший экземпляр удаляется.
+pub async fn take_item(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    instance_id: Uuid,
+    quantity: Option<i32>,
+) -> Result<(String, i32), AppError> {
+    let item = sqlx::query!(
+        "SELECT item_id, quantity FROM player_items WHERE id = $1 AND user_id = $2 FOR UPDATE",
+        instance_id,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let quantity = checked_quantity(quantity, item.quantity, &item.item_id)?;
+    if item.quantity == quantity {
+        sqlx::query!("DELETE FROM player_items WHERE id = $1", instance_id)
+            .execute(&mut *conn)
+            .await?;
+    } else {
+        sqlx::query!("UPDATE player_items SET quantity = quantity - $2 WHERE id = $1", instance_id, quantity)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok((item.item_id, quantity))
+}
+
+/// Забирает одну штуку предмета с кодом `item_id` из любого экземпляра игрока (самого старого).
+/// Возвращает `false`, если такого предмета у игрока нет.
+pub async fn take_one(conn: &mut PgConnection, user_id: Uuid, item_id: &str) -> Result<bool, AppError> {
+    let instance_id = sqlx::query_scalar!(
+        "SELECT id FROM player_items WHERE user_id = $1 AND item_id = $2 ORDER BY acquired_at LIMIT 1 FOR UPDATE",
+        user_id,
+        item_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(instance_id) = instance_id else {
+        return Ok(false);
+    };
+
+    take_item(conn, user_id, instance_id, Some(1)).await?;
+    Ok(true)
+}
+
+/// Сколько штук забрать из экземпляра, в котором лежит `available`.
+pub(crate) fn checked_quantity(requested: Option<i32>, available: i32, item_id: &str) -> Result<i32, AppError> {
+    match requested {
+        None => Ok(available),
+        Some(quantity) if quantity <= 0 => Err(AppError::BadRequest("Quantity must be positive".to_string())),
+        Some(quantity) if quantity > available => {
+            Err(AppError::BadRequest(format!("Only {} of '{}' available", available, item_id)))
+        }
+        Some(quantity) => Ok(quantity),
+    }
+}
+
+/// Код предмета допустим в условиях доступа: `has <код>` разбирается как одно слово.
+pub fn validate_item_id(id: &str) -> Result<(), AppError> {
+    let mut chars = id.chars();
+    let valid_start = chars.next().is_some_and(|c| c.is_ascii_lowercase() || c == '_');
+    let valid_rest = chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.'));
+    if !valid_start || !valid_rest || id.len() > 64 {
+        return Err(AppError::BadRequest(
+            "Item id must be up to 64 lowercase letters, digits, '_', '-' or '.', starting with a letter or '_'"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
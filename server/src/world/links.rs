@@ -0,0 +1,35 @@
+// /var/www/structure/server/src/world/links.rs
+
+use super::rules::{self, RuleContext};
+use crate::models::link::LocationLink;
+
+/// Сообщение по умолчанию, если у перехода не задан собственный текст отказа.
+pub const DEFAULT_DENIAL_MESSAGE: &str = "ПЕРЕХОД ЗАБЛОКИРОВАН: УСЛОВИЯ ДОСТУПА НЕ ВЫПОЛНЕНЫ.";
+
+/// Возвращает причину, по которой переход закрыт для игрока, или `None`, если он открыт.
+/// Временный допуск к целевой локации снимает требование уровня и для перехода.
+pub fn link_denial(link: &LocationLink, ctx: &RuleContext) -> Option<String> {
+    if !ctx.clears(link.target_location_id, link.required_access_level) {
+        return Some(format!(
+            "ТРЕБУЕТСЯ УРОВЕНЬ ДОСТУПА: {}. ВАШ УРОВЕНЬ: {}.",
+            link.required_access_level, ctx.access_level
+        ));
+    }
+
+    let source = link.access_condition.as_deref()?;
+    let passed = match rules::parse(source) {
+        Ok(condition) => condition.evaluate(ctx),
+        Err(e) => {
+            // Условия проверяются при сохранении, так что сюда попадаем только
+            // при ручной правке БД. Закрываем переход, а не открываем.
+            tracing::error!("Некорректное условие у перехода {}: {}", link.id, e);
+            false
+        }
+    };
+
+    if passed {
+        None
+    } else {
+        Some(link.denial_message.clone().unwrap_or_else(|| DEFAULT_DENIAL_MESSAGE.to_string()))
+    }
+}
@@ -0,0 +1,26 @@
+// /var/www/structure/server/src/world/mod.rs
+
+// Игровая логика, общая для HTTP-обработчиков и WebSocket:
+// то, что нельзя отнести к одному конкретному эндпоинту.
+pub mod acl;
+pub mod capacity;
+pub mod clearance;
+pub mod discovery;
+pub mod generator;
+pub mod grants;
+pub mod i18n;
+pub mod inventory;
+pub mod instances;
+pub mod intrusion;
+pub mod links;
+pub mod npcs;
+pub mod quests;
+pub mod redaction;
+pub mod room_items;
+pub mod rules;
+pub mod spawn;
+pub mod templates;
+pub mod trade;
+pub mod transit;
+pub mod visits;
+pub mod zones;
@@ -0,0 +1,368 @@
+// /server/src/world/npcs.rs
+
+// NPC и разговоры с ними.
+//
+// Диалог хранится у NPC данными (models::npc::Dialogue): узлы с репликами, варианты
+// ответа с условиями на языке правил и эффекты - выдать или забрать предмет, выставить
+// флаг игрока. Дерево целиком проверяется при сохранении, поэтому во время разговора
+// ссылки на узлы и условия считаются корректными.
+//
+// Где игрок остановился, хранится в npc_conversations: разговор можно прервать
+// и продолжить позже, в том числе после переподключения.
+
+use super::{
+    instances::set_instance_state,
+    inventory::{grant_item, take_one, validate_item_id, MAX_GRANT_QUANTITY},
+    quests::{emit_quest_events, QuestEvent},
+    rules::{self, validate_expression, RuleContext},
+    templates::{self, validate_template, TemplateContext},
+};
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::npc::{Dialogue, DialogueEffect, DialogueNode, Npc, NpcPresence},
+    state::AppState,
+    ws::state::RoomKey,
+};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use uuid::Uuid;
+
+/// NPC, стоящие в локации, в том виде, в каком их видят игроки.
+pub async fn location_npcs<'e>(
+    executor: impl PgExecutor<'e>,
+    location_id: Uuid,
+) -> Result<Vec<NpcPresence>, AppError> {
+    let npcs = sqlx::query_as!(
+        NpcPresence,
+        r#"
+        SELECT id, name AS username, description, TRUE AS "npc!"
+        FROM npcs WHERE location_id = $1 ORDER BY name
+        "#,
+        location_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(npcs)
+}
+
+impl From<&Npc> for NpcPresence {
+    fn from(npc: &Npc) -> Self {
+        Self { id: npc.id, username: npc.name.clone(), description: npc.description.clone(), npc: true }
+    }
+}
+
+fn check_effects(effects: &[DialogueEffect], item_ids: &mut Vec<String>) -> Result<(), AppError> {
+    for effect in effects {
+        match effect {
+            DialogueEffect::GiveItem { item_id, quantity } | DialogueEffect::TakeItem { item_id, quantity } => {
+                validate_item_id(item_id)?;
+                if !(1..=MAX_GRANT_QUANTITY).contains(quantity) {
+                    return Err(AppError::BadRequest(format!(
+                        "Effect quantity must be between 1 and {}",
+                        MAX_GRANT_QUANTITY
+                    )));
+                }
+                item_ids.push(item_id.clone());
+            }
+            DialogueEffect::SetFlag { key, .. } | DialogueEffect::SetInstanceState { key, .. } => {
+                if key.trim().is_empty() {
+                    return Err(AppError::BadRequest("Flag key cannot be empty".to_string()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Проверяет дерево диалога перед сохранением: формат, ссылки между узлами,
+/// шаблоны реплик, условия и предметы в эффектах.
+pub async fn validate_dialogue(pool: &PgPool, dialogue: &Value) -> Result<(), AppError> {
+    let dialogue: Dialogue = serde_json::from_value(dialogue.clone())
+        .map_err(|e| AppError::BadRequest(format!("Invalid dialogue: {}", e)))?;
+    if !dialogue.nodes.contains_key(&dialogue.start) {
+        return Err(AppError::BadRequest(format!("Start node '{}' does not exist", dialogue.start)));
+    }
+
+    let mut item_ids = Vec::new();
+    for (id, node) in &dialogue.nodes {
+        validate_template(&format!("node '{}' text", id), &node.text)?;
+        check_effects(&node.effects, &mut item_ids)?;
+        for choice in &node.choices {
+            validate_expression(&format!("node '{}' choice condition", id), choice.condition.as_deref())?;
+            if let Some(next) = choice.next.as_ref().filter(|next| !dialogue.nodes.contains_key(*next)) {
+                return Err(AppError::BadRequest(format!("Node '{}' leads to unknown node '{}'", id, next)));
+            }
+            check_effects(&choice.effects, &mut item_ids)?;
+        }
+    }
+
+    item_ids.sort();
+    item_ids.dedup();
+    let known_items = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM item_definitions WHERE id = ANY($1)"#,
+        &item_ids
+    )
+    .fetch_one(pool)
+    .await?;
+    if known_items != item_ids.len() as i64 {
+        return Err(AppError::BadRequest("Dialogue refers to unknown items".to_string()));
+    }
+    Ok(())
+}
+
+fn parse_dialogue(npc: &Npc) -> Result<Dialogue, AppError> {
+    serde_json::from_value(npc.dialogue.clone()).map_err(|e| {
+        tracing::error!("Испорченный диалог у NPC {}: {}", npc.id, e);
+        AppError::InternalServerError
+    })
+}
+
+/// Вариант ответа, доступный игроку. Номер - позиция в узле, он не меняется,
+/// даже если часть вариантов скрыта условиями.
+#[derive(Serialize)]
+pub struct ChoiceView {
+    pub number: usize,
+    pub text: String,
+}
+
+/// Текущая реплика NPC и доступные ответы.
+#[derive(Serialize)]
+pub struct DialogueView {
+    pub npc_id: Uuid,
+    pub npc_name: String,
+    pub node_id: String,
+    pub text: String,
+    pub choices: Vec<ChoiceView>,
+    pub ended: bool,
+}
+
+fn condition_holds(condition: Option<&str>, ctx: &RuleContext) -> bool {
+    // Условия проверены при сохранении; неразбираемое считается невыполненным
+    condition.is_none_or(|source| rules::parse(source).is_ok_and(|condition| condition.evaluate(ctx)))
+}
+
+fn view_node(npc: &Npc, node_id: &str, node: &DialogueNode, ctx: &RuleContext, player_name: &str) -> DialogueView {
+    let choices = node
+        .choices
+        .iter()
+        .enumerate()
+        .filter(|(_, choice)| condition_holds(choice.condition.as_deref(), ctx))
+        .map(|(index, choice)| ChoiceView { number: index + 1, text: choice.text.clone() })
+        .collect();
+    DialogueView {
+        npc_id: npc.id,
+        npc_name: npc.name.clone(),
+        node_id: node_id.to_string(),
+        text: templates::render(&node.text, &TemplateContext { player_name, rules: ctx }),
+        choices,
+        ended: node.choices.is_empty(),
+    }
+}
+
+/// Применяет эффекты в транзакции разговора. Выданные предметы дописываются в `obtained`.
+async fn apply_effects(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    effects: &[DialogueEffect],
+    obtained: &mut Vec<String>,
+) -> Result<(), AppError> {
+    for effect in effects {
+        match effect {
+            DialogueEffect::GiveItem { item_id, quantity } => {
+                grant_item(conn, user_id, item_id, *quantity).await?;
+                obtained.push(item_id.clone());
+            }
+            DialogueEffect::TakeItem { item_id, quantity } => {
+                for _ in 0..*quantity {
+                    if !take_one(conn, user_id, item_id).await? {
+                        return Err(AppError::AccessDenied("У ВАС НЕТ НУЖНЫХ ПРЕДМЕТОВ.".to_string()));
+                    }
+                }
+            }
+            DialogueEffect::SetFlag { key, value } => {
+                sqlx::query!(
+                    "UPDATE players SET flags = flags || jsonb_build_object($2::text, $3::jsonb) WHERE user_id = $1",
+                    user_id,
+                    key,
+                    value
+                )
+                .execute(&mut *conn)
+                .await?;
+            }
+            DialogueEffect::SetInstanceState { key, value } => {
+                let instance_id =
+                    sqlx::query_scalar!("SELECT current_instance_id FROM players WHERE user_id = $1", user_id)
+                        .fetch_one(&mut *conn)
+                        .await?;
+                if let Some(instance_id) = instance_id {
+                    set_instance_state(&mut *conn, instance_id, key, value).await?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Переводит разговор в узел `node_id` и применяет его эффекты. Узел без ответов
+/// заканчивает разговор: следующий начнется сначала.
+async fn enter_node(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    npc_id: Uuid,
+    node_id: &str,
+    node: &DialogueNode,
+    obtained: &mut Vec<String>,
+) -> Result<(), AppError> {
+    apply_effects(conn, user_id, &node.effects, obtained).await?;
+    let stored = (!node.choices.is_empty()).then_some(node_id);
+    sqlx::query!(
+        r#"
+        INSERT INTO npc_conversations (user_id, npc_id, node_id) VALUES ($1, $2, $3)
+        ON CONFLICT (user_id, npc_id) DO UPDATE SET node_id = EXCLUDED.node_id, updated_at = NOW()
+        "#,
+        user_id,
+        npc_id,
+        stored
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// NPC в локации по идентификатору, имени или однозначному началу имени.
+pub async fn find_npc(pool: &PgPool, location_id: Uuid, wanted: &str) -> Result<Npc, AppError> {
+    let mut npcs = sqlx::query_as!(Npc, "SELECT * FROM npcs WHERE location_id = $1 ORDER BY name", location_id)
+        .fetch_all(pool)
+        .await?;
+
+    let wanted_id = wanted.parse::<Uuid>().ok();
+    let wanted = wanted.to_lowercase();
+    if let Some(index) = npcs
+        .iter()
+        .position(|npc| Some(npc.id) == wanted_id || npc.name.to_lowercase() == wanted)
+    {
+        return Ok(npcs.swap_remove(index));
+    }
+    let mut candidates: Vec<Npc> = npcs.into_iter().filter(|npc| npc.name.to_lowercase().starts_with(&wanted)).collect();
+    match candidates.len() {
+        1 => Ok(candidates.remove(0)),
+        0 => Err(AppError::AccessDenied("ЗДЕСЬ НЕТ ТАКОГО ПЕРСОНАЖА.".to_string())),
+        _ => {
+            let names: Vec<&str> = candidates.iter().map(|npc| npc.name.as_str()).collect();
+            Err(AppError::BadRequest(format!("НЕОДНОЗНАЧНОЕ ИМЯ: {}.", names.join(", "))))
+        }
+    }
+}
+
+/// Начинает или продолжает разговор с NPC, стоящим в комнате игрока.
+pub async fn talk(state: &AppState, claims: &Claims, room: RoomKey, wanted: &str) -> Result<DialogueView, AppError> {
+    let npc = find_npc(&state.pool, room.location_id, wanted).await?;
+    let dialogue = parse_dialogue(&npc)?;
+
+    let mut tx = state.pool.begin().await?;
+    let stored = sqlx::query_scalar!(
+        "SELECT node_id FROM npc_conversations WHERE user_id = $1 AND npc_id = $2 FOR UPDATE",
+        claims.sub,
+        npc.id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .flatten();
+
+    // Прерванный разговор продолжается с того же места, если узел еще существует
+    let mut obtained = Vec::new();
+    let node_id = match stored.filter(|node_id| dialogue.nodes.contains_key(node_id)) {
+        Some(node_id) => node_id,
+        None => {
+            let node = &dialogue.nodes[&dialogue.start];
+            enter_node(&mut tx, claims.sub, npc.id, &dialogue.start, node, &mut obtained).await?;
+            dialogue.start.clone()
+        }
+    };
+    tx.commit().await?;
+
+    let mut events = vec![QuestEvent::TalkedTo(npc.id)];
+    events.extend(obtained.into_iter().map(QuestEvent::ObtainedItem));
+    emit_quest_events(state, claims.sub, events).await;
+
+    let ctx = RuleContext::load(&state.pool, claims.sub, claims.role).await?;
+    Ok(view_node(&npc, &node_id, &dialogue.nodes[&node_id], &ctx, &claims.username))
+}
+
+/// Отвечает в текущем разговоре игрока (последнем незаконченном с NPC из его комнаты).
+pub async fn answer(state: &AppState, claims: &Claims, room: RoomKey, number: usize) -> Result<DialogueView, AppError> {
+    let npc = sqlx::query_as!(
+        Npc,
+        r#"
+        SELECT n.* FROM npc_conversations c JOIN npcs n ON n.id = c.npc_id
+        WHERE c.user_id = $1 AND n.location_id = $2 AND c.node_id IS NOT NULL
+        ORDER BY c.updated_at DESC LIMIT 1
+        "#,
+        claims.sub,
+        room.location_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::AccessDenied("ВЫ НИ С КЕМ НЕ РАЗГОВАРИВАЕТЕ.".to_string()))?;
+    let dialogue = parse_dialogue(&npc)?;
+    let ctx = RuleContext::load(&state.pool, claims.sub, claims.role).await?;
+
+    let mut tx = state.pool.begin().await?;
+    // Повторная отправка того же ответа не должна сработать дважды
+    let node_id = sqlx::query_scalar!(
+        "SELECT node_id FROM npc_conversations WHERE user_id = $1 AND npc_id = $2 FOR UPDATE",
+        claims.sub,
+        npc.id
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::AccessDenied("РАЗГОВОР УЖЕ ОКОНЧЕН.".to_string()))?;
+    let node = dialogue.nodes.get(&node_id).ok_or_else(|| {
+        AppError::AccessDenied("СОБЕСЕДНИК ПОТЕРЯЛ НИТЬ РАЗГОВОРА. НАЧНИТЕ ЗАНОВО.".to_string())
+    })?;
+    let choice = number
+        .checked_sub(1)
+        .and_then(|index| node.choices.get(index))
+        .filter(|choice| condition_holds(choice.condition.as_deref(), &ctx))
+        .ok_or_else(|| AppError::AccessDenied("ТАКОГО ОТВЕТА НЕТ.".to_string()))?;
+
+    let mut obtained = Vec::new();
+    apply_effects(&mut tx, claims.sub, &choice.effects, &mut obtained).await?;
+    let next = match &choice.next {
+        Some(next_id) => {
+            let next = &dialogue.nodes[next_id];
+            enter_node(&mut tx, claims.sub, npc.id, next_id, next, &mut obtained).await?;
+            Some((next_id, next))
+        }
+        None => {
+            sqlx::query!(
+                "UPDATE npc_conversations SET node_id = NULL, updated_at = NOW() WHERE user_id = $1 AND npc_id = $2",
+                claims.sub,
+                npc.id
+            )
+            .execute(&mut *tx)
+            .await?;
+            None
+        }
+    };
+    tx.commit().await?;
+
+    emit_quest_events(state, claims.sub, obtained.into_iter().map(QuestEvent::ObtainedItem).collect()).await;
+
+    // Эффекты могли изменить предметы и флаги, поэтому варианты считаются по новому состоянию
+    let ctx = RuleContext::load(&state.pool, claims.sub, claims.role).await?;
+    Ok(match next {
+        Some((next_id, next)) => view_node(&npc, next_id, next, &ctx, &claims.username),
+        None => DialogueView {
+            npc_id: npc.id,
+            npc_name: npc.name.clone(),
+            node_id: node_id.clone(),
+            text: String::new(),
+            choices: Vec::new(),
+            ended: true,
+        },
+    })
+}
@@ -0,0 +1,215 @@
+// /server/src/world/quests.rs
+
+// Продвижение заданий по событиям мира.
+//
+// Действия игрока (переход, получение предмета, разговор, повышение уровня) после
+// своего коммита сообщают о событиях через `emit_quest_events`. Цели проверяются
+// по текущему состоянию: "получить предмет" выполнена, если нужное количество
+// сейчас в инвентаре. Завершенное задание сразу выдает награды, и награды сами
+// порождают события (полученный предмет может закрыть цель другого задания).
+
+use super::{
+    clearance::{announce_clearance, lock_access_level, set_access_level, ClearanceChange},
+    inventory::grant_item,
+};
+use crate::{error::AppError, state::AppState, ws::utils::send_to_user};
+use serde_json::Value;
+use sqlx::PgConnection;
+use std::collections::VecDeque;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub enum QuestEvent {
+    /// Игрок взял задание: цели, которые уже выполнены, засчитываются сразу.
+    Accepted(Uuid),
+    Visited(Uuid),
+    ObtainedItem(String),
+    TalkedTo(Uuid),
+    AccessLevelChanged(i32),
+}
+
+/// Продвигает задания игрока. Ошибки только логируются: действие игрока уже состоялось.
+pub async fn emit_quest_events(state: &AppState, user_id: Uuid, events: Vec<QuestEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    if let Err(e) = process_events(state, user_id, events).await {
+        tracing::error!("Не удалось обработать события заданий игрока {}: {:?}", user_id, e);
+    }
+}
+
+async fn process_events(state: &AppState, user_id: Uuid, events: Vec<QuestEvent>) -> Result<(), AppError> {
+    let mut tx = state.pool.begin().await?;
+    // События одного игрока обрабатываются по очереди, иначе награду можно получить дважды
+    lock_access_level(&mut tx, user_id).await?;
+
+    let mut messages = Vec::new();
+    let mut clearance_changes = Vec::new();
+    let mut queue = VecDeque::from(events);
+    while let Some(event) = queue.pop_front() {
+        let completed = complete_objectives(&mut tx, user_id, &event).await?;
+        for row in &completed {
+            messages.push(serde_json::json!({
+                "type": "quest_objective_completed",
+                "quest_id": row.0,
+                "objective_id": row.1,
+            }));
+        }
+
+        let mut quest_ids: Vec<Uuid> = completed.into_iter().map(|(quest_id, _)| quest_id).collect();
+        if let QuestEvent::Accepted(quest_id) = event {
+            quest_ids.push(quest_id);
+        }
+        for quest_id in finish_quests(&mut tx, user_id, &quest_ids).await? {
+            let reward = grant_rewards(&mut tx, user_id, quest_id).await?;
+            messages.push(serde_json::json!({
+                "type": "quest_completed",
+                "quest_id": quest_id,
+                "title": reward.title,
+            }));
+            queue.extend(reward.items.into_iter().map(QuestEvent::ObtainedItem));
+            if let Some((old_level, new_level, reason)) = reward.clearance {
+                queue.push_back(QuestEvent::AccessLevelChanged(new_level));
+                clearance_changes.push((old_level, new_level, reason));
+            }
+        }
+    }
+    tx.commit().await?;
+
+    for message in messages {
+        send_to_user(state, user_id, message.to_string()).await;
+    }
+    for (old_level, new_level, reason) in clearance_changes {
+        announce_clearance(state, user_id, old_level, new_level, &reason).await;
+    }
+    Ok(())
+}
+
+/// Засчитывает цели активных заданий, которые выполняет событие.
+/// Возвращает пары (задание, цель) для впервые выполненных целей.
+async fn complete_objectives(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    event: &QuestEvent,
+) -> Result<Vec<(Uuid, Uuid)>, AppError> {
+    let (accepted, location_id, item_id, npc_id, level) = match event {
+        QuestEvent::Accepted(quest_id) => (Some(*quest_id), None, None, None, None),
+        QuestEvent::Visited(location_id) => (None, Some(*location_id), None, None, None),
+        QuestEvent::ObtainedItem(item_id) => (None, None, Some(item_id.as_str()), None, None),
+        QuestEvent::TalkedTo(npc_id) => (None, None, None, Some(*npc_id), None),
+        QuestEvent::AccessLevelChanged(level) => (None, None, None, None, Some(*level)),
+    };
+
+    let rows = sqlx::query!(
+        r#"
+        INSERT INTO player_objectives (user_id, objective_id)
+        SELECT $1, o.id
+        FROM quest_objectives o
+        JOIN player_quests pq ON pq.quest_id = o.quest_id AND pq.user_id = $1 AND pq.completed_at IS NULL
+        JOIN players p ON p.user_id = $1
+        WHERE CASE
+            -- При взятии задания проверяется текущее состояние: где игрок стоит, что у него есть
+            WHEN $2::uuid IS NOT NULL THEN o.quest_id = $2 AND (
+                (o.kind = 'VisitLocation' AND o.location_id = p.current_location_id)
+                OR (o.kind = 'ObtainItem' AND (
+                    SELECT COALESCE(SUM(i.quantity), 0) FROM player_items i
+                    WHERE i.user_id = $1 AND i.item_id = o.item_id) >= o.quantity)
+                OR (o.kind = 'ReachAccessLevel' AND p.access_level >= o.access_level))
+            ELSE
+                (o.kind = 'VisitLocation' AND o.location_id = $3)
+                OR (o.kind = 'ObtainItem' AND o.item_id = $4 AND (
+                    SELECT COALESCE(SUM(i.quantity), 0) FROM player_items i
+                    WHERE i.user_id = $1 AND i.item_id = o.item_id) >= o.quantity)
+                OR (o.kind = 'TalkToNpc' AND o.npc_id = $5)
+                OR (o.kind = 'ReachAccessLevel' AND o.access_level <= $6)
+        END
+        ON CONFLICT DO NOTHING
+        RETURNING objective_id,
+                  (SELECT quest_id FROM quest_objectives WHERE id = objective_id) AS "quest_id!"
+        "#,
+        user_id,
+        accepted,
+        location_id,
+        item_id,
+        npc_id,
+        level
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| (row.quest_id, row.objective_id)).collect())
+}
+
+/// Отмечает завершенными активные задания из списка, у которых выполнены все цели.
+async fn finish_quests(conn: &mut PgConnection, user_id: Uuid, quest_ids: &[Uuid]) -> Result<Vec<Uuid>, AppError> {
+    if quest_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let finished = sqlx::query_scalar!(
+        r#"
+        UPDATE player_quests pq SET completed_at = NOW()
+        WHERE pq.user_id = $1 AND pq.quest_id = ANY($2) AND pq.completed_at IS NULL
+          AND NOT EXISTS (
+              SELECT 1 FROM quest_objectives o
+              WHERE o.quest_id = pq.quest_id
+                AND NOT EXISTS (SELECT 1 FROM player_objectives po WHERE po.user_id = $1 AND po.objective_id = o.id))
+        RETURNING quest_id
+        "#,
+        user_id,
+        quest_ids
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(finished)
+}
+
+struct Reward {
+    title: String,
+    items: Vec<String>,
+    /// (старый уровень, новый уровень, причина), если награда повысила уровень.
+    clearance: Option<(i32, i32, String)>,
+}
+
+async fn grant_rewards(conn: &mut PgConnection, user_id: Uuid, quest_id: Uuid) -> Result<Reward, AppError> {
+    let quest = sqlx::query!(
+        "SELECT title, reward_access_level, reward_flags FROM quests WHERE id = $1",
+        quest_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let reason = format!("Задание «{}»", quest.title);
+
+    let items = sqlx::query!("SELECT item_id, quantity FROM quest_reward_items WHERE quest_id = $1", quest_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    for item in &items {
+        grant_item(conn, user_id, &item.item_id, item.quantity).await?;
+    }
+
+    if let Value::Object(flags) = quest.reward_flags {
+        for (key, value) in flags {
+            sqlx::query!(
+                "INSERT INTO world_flags (key, value) VALUES ($1, $2) ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value",
+                key,
+                value
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    // Награда только повышает уровень: игрок, уже поднявшийся выше, ничего не теряет
+    let mut clearance = None;
+    if let Some(level) = quest.reward_access_level {
+        let current = lock_access_level(conn, user_id).await?;
+        if level > current {
+            let change = ClearanceChange { challenge_id: None, granted_by: None, reason: &reason };
+            let old_level = set_access_level(conn, user_id, level, change).await?;
+            clearance = Some((old_level, level, reason.clone()));
+        }
+    }
+
+    tracing::info!("Игрок {} завершил задание {}", user_id, quest_id);
+    Ok(Reward { title: quest.title, items: items.into_iter().map(|item| item.item_id).collect(), clearance })
+}
@@ -0,0 +1,153 @@
+// /server/src/world/redaction.rs
+
+// Частичное сокрытие содержимого локации для игрока без нужного уровня доступа.
+//
+// Чем больше разрыв между уровнем локации и уровнем игрока, тем меньше он видит:
+// при разрыве 1 название читается целиком, а в описании искажена четверть слов;
+// начиная с MAX_LEAK_GAP не видно ничего. Какие поля вообще могут просочиться,
+// задает политика локации (`redaction_leaks`).
+//
+// Искажения зависят только от игрока, локации и поля, поэтому при повторных
+// запросах текст не "мерцает".
+
+use super::{rules::RuleContext, zones::policy_denial};
+use crate::{config::Config, error::AppError, models::location::Location};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Поля, которые можно перечислить в политике локации.
+pub const LEAKABLE_FIELDS: [&str; 4] = ["name", "description", "ambient_description", "image"];
+/// Политика по умолчанию (совпадает с DEFAULT в миграции).
+pub const DEFAULT_LEAKS: [&str; 2] = ["name", "description"];
+
+const REDACTED_NAME: &str = "[[ДАННЫЕ ПОВРЕЖДЕНЫ]]";
+/// Начиная с такого разрыва в уровнях доступа не просачивается ничего.
+const MAX_LEAK_GAP: i32 = 4;
+const GLITCH_CHARS: [char; 5] = ['█', '▓', '▒', '░', '#'];
+
+/// Какой процент слов искажается при данном разрыве; `None` - поле скрыто полностью.
+fn corruption_percent(gap: i32) -> Option<u64> {
+    match gap {
+        ..=0 => Some(0),
+        1 => Some(25),
+        2 => Some(50),
+        3 => Some(80),
+        _ => None,
+    }
+}
+
+/// Проверка политики перед сохранением: только известные поля, без повторов.
+pub fn validate_leaks(leaks: &[String]) -> Result<Vec<String>, AppError> {
+    let mut validated: Vec<String> = Vec::with_capacity(leaks.len());
+    for field in leaks {
+        let field = field.trim().to_ascii_lowercase();
+        if !LEAKABLE_FIELDS.contains(&field.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown redaction field '{}', expected one of: {}",
+                field,
+                LEAKABLE_FIELDS.join(", ")
+            )));
+        }
+        if !validated.contains(&field) {
+            validated.push(field);
+        }
+    }
+    Ok(validated)
+}
+
+/// Заменяет содержимое локации тем, что игрок может разглядеть без доступа к ней.
+/// Если доступ закрыт политикой зоны, а не уровнем, скрывается все.
+pub fn redact_location(
+    location: Location,
+    reason: String,
+    ctx: &RuleContext,
+    user_id: Uuid,
+    config: &Config,
+) -> Location {
+    let gap = if policy_denial(&location, ctx).is_some() {
+        MAX_LEAK_GAP
+    } else {
+        location.security_level - ctx.access_level
+    };
+    let redactor = Redactor::new(user_id, location.id);
+    let leaks = |field: &str| location.redaction_leaks.iter().any(|leak| leak == field);
+
+    // Название искажается на ступень слабее описания, фоновое описание - на ступень сильнее
+    let name = match corruption_percent(gap - 1).filter(|_| leaks("name") && gap < MAX_LEAK_GAP) {
+        Some(percent) => redactor.glitch(&location.name, 1, percent),
+        None => REDACTED_NAME.to_string(),
+    };
+    let description = match corruption_percent(gap).filter(|_| leaks("description")) {
+        Some(percent) => format!("{}\n\n{}", reason, redactor.glitch(&location.description, 2, percent)),
+        None => reason,
+    };
+    let ambient_description = corruption_percent(gap + 1)
+        .filter(|_| leaks("ambient_description"))
+        .and_then(|percent| Some(redactor.glitch(location.ambient_description.as_deref()?, 3, percent)));
+    let leak_image = leaks("image") && gap <= 1;
+
+    Location {
+        name,
+        description,
+        image_url: if leak_image { location.image_url.clone() } else { Some(config.scrambled_image_url.clone()) },
+        image_asset_id: if leak_image { location.image_asset_id } else { None },
+        ambient_description,
+        ..location
+    }
+}
+
+/// Детерминированный источник искажений для пары игрок-локация.
+struct Redactor {
+    seed: u64,
+}
+
+impl Redactor {
+    fn new(user_id: Uuid, location_id: Uuid) -> Self {
+        let digest = Sha256::new()
+            .chain_update(user_id.as_bytes())
+            .chain_update(location_id.as_bytes())
+            .finalize();
+        let mut seed = [0u8; 8];
+        seed.copy_from_slice(&digest[..8]);
+        Self { seed: u64::from_le_bytes(seed) }
+    }
+
+    /// splitmix64: дешевое и стабильное между версиями перемешивание.
+    fn roll(&self, salt: u64, index: u64) -> u64 {
+        let mut z = self
+            .seed
+            .wrapping_add(salt.wrapping_mul(0xD1B5_4A32_D192_ED03))
+            .wrapping_add(index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Искажает примерно `percent` процентов слов: буквы и цифры заменяются помехами,
+    /// пробелы и знаки препинания остаются на месте, так что форма текста сохраняется.
+    fn glitch(&self, text: &str, salt: u64, percent: u64) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut word_index = 0;
+        let mut in_word = false;
+        let mut corrupt = false;
+        for (i, c) in text.chars().enumerate() {
+            if c.is_whitespace() {
+                in_word = false;
+                out.push(c);
+                continue;
+            }
+            if !in_word {
+                in_word = true;
+                word_index += 1;
+                corrupt = self.roll(salt, word_index) % 100 < percent;
+            }
+            if corrupt && c.is_alphanumeric() {
+                let glyph = self.roll(salt.wrapping_add(1 << 32), i as u64) % GLITCH_CHARS.len() as u64;
+                out.push(GLITCH_CHARS[glyph as usize]);
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,208 @@
+// /server/src/world/room_items.rs
+
+// Предметы, лежащие в локациях.
+//
+// Подобрать и выбросить - это `take_*` с одной стороны и `place_item`/`grant_item`
+// с другой в одной транзакции. Строка лежащего предмета берется FOR UPDATE,
+// поэтому из двух игроков, схвативших последнюю карту, ее получает ровно один:
+// второй дождется коммита первого и уже не найдет строку.
+
+use super::inventory::{checked_quantity, MAX_GRANT_QUANTITY};
+use crate::{
+    error::AppError,
+    models::item::LocationItem,
+    state::AppState,
+    ws::{utils::broadcast_message, RoomKey},
+};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Комната, в которой сейчас находится игрок. Строка игрока блокируется до конца
+/// транзакции, чтобы он не ушел, пока поднимает или бросает предмет.
+pub async fn lock_player_room(conn: &mut PgConnection, user_id: Uuid) -> Result<RoomKey, AppError> {
+    let player = sqlx::query!(
+        r#"
+        SELECT current_location_id, current_instance_id,
+               EXISTS (SELECT 1 FROM player_transits WHERE user_id = $1) AS "in_transit!"
+        FROM players WHERE user_id = $1 FOR UPDATE
+        "#,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // Игрок в пути не находится ни в одной комнате
+    if player.in_transit {
+        return Err(AppError::AccessDenied("ВЫ В ПУТИ.".to_string()));
+    }
+    let location_id = player.current_location_id.ok_or(AppError::NotFound)?;
+    Ok(RoomKey::new(location_id, player.current_instance_id))
+}
+
+pub async fn load_room_items(
+    pool: &PgPool,
+    location_id: Uuid,
+    instance_id: Option<Uuid>,
+) -> Result<Vec<LocationItem>, AppError> {
+    let items = sqlx::query_as!(
+        LocationItem,
+        r#"
+        SELECT li.id, li.item_id, d.name, d.description, li.quantity, d.stackable, d.weight, d.tags,
+               li.placed_by, li.placed_at
+        FROM location_items li
+        JOIN item_definitions d ON d.id = li.item_id
+        WHERE li.location_id = $1 AND li.instance_id IS NOT DISTINCT FROM $2
+        ORDER BY d.name, li.placed_at
+        "#,
+        location_id,
+        instance_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(items)
+}
+
+/// Кладет предметы в локацию. Складываемые добавляются к уже лежащей стопке.
+pub async fn place_item(
+    conn: &mut PgConnection,
+    room: RoomKey,
+    item_id: &str,
+    quantity: i32,
+    placed_by: Uuid,
+) -> Result<(), AppError> {
+    if quantity <= 0 {
+        return Err(AppError::BadRequest("Quantity must be positive".to_string()));
+    }
+    let stackable = sqlx::query_scalar!("SELECT stackable FROM item_definitions WHERE id = $1", item_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown item '{}'", item_id)))?;
+
+    if !stackable {
+        if quantity > MAX_GRANT_QUANTITY {
+            return Err(AppError::BadRequest(format!(
+                "At most {} non-stackable items can be placed at once",
+                MAX_GRANT_QUANTITY
+            )));
+        }
+        sqlx::query!(
+            r#"
+            INSERT INTO location_items (location_id, instance_id, item_id, placed_by)
+            SELECT $1, $2, $3, $4 FROM generate_series(1, $5)
+            "#,
+            room.location_id,
+            room.instance_id,
+            item_id,
+            placed_by,
+            quantity
+        )
+        .execute(&mut *conn)
+        .await?;
+        return Ok(());
+    }
+
+    // Уникальность стопки держат два частичных индекса: для самой локации и для копий
+    match room.instance_id {
+        None => {
+            sqlx::query!(
+                r#"
+                INSERT INTO location_items (location_id, item_id, quantity, stacked, placed_by)
+                VALUES ($1, $2, $3, TRUE, $4)
+                ON CONFLICT (location_id, item_id) WHERE stacked AND instance_id IS NULL
+                DO UPDATE SET quantity = location_items.quantity + EXCLUDED.quantity
+                "#,
+                room.location_id,
+                item_id,
+                quantity,
+                placed_by
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+        Some(instance_id) => {
+            sqlx::query!(
+                r#"
+                INSERT INTO location_items (location_id, instance_id, item_id, quantity, stacked, placed_by)
+                VALUES ($1, $2, $3, $4, TRUE, $5)
+                ON CONFLICT (instance_id, item_id) WHERE stacked AND instance_id IS NOT NULL
+                DO UPDATE SET quantity = location_items.quantity + EXCLUDED.quantity
+                "#,
+                room.location_id,
+                instance_id,
+                item_id,
+                quantity,
+                placed_by
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Забирает `quantity` штук (`None` - все) лежащего в локации предмета и возвращает
+/// код предмета и сколько штук забрано. Если предмет уже унесли, `NotFound`.
+pub async fn take_room_item(
+    conn: &mut PgConnection,
+    room: RoomKey,
+    id: Uuid,
+    quantity: Option<i32>,
+) -> Result<(String, i32), AppError> {
+    let item = sqlx::query!(
+        r#"
+        SELECT item_id, quantity FROM location_items
+        WHERE id = $1 AND location_id = $2 AND instance_id IS NOT DISTINCT FROM $3
+        FOR UPDATE
+        "#,
+        id,
+        room.location_id,
+        room.instance_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let quantity = checked_quantity(quantity, item.quantity, &item.item_id)?;
+    if item.quantity == quantity {
+        sqlx::query!("DELETE FROM location_items WHERE id = $1", id)
+            .execute(&mut *conn)
+            .await?;
+    } else {
+        sqlx::query!("UPDATE location_items SET quantity = quantity - $2 WHERE id = $1", id, quantity)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok((item.item_id, quantity))
+}
+
+/// Что произошло с содержимым комнаты - для рассылки присутствующим.
+pub struct RoomItemsChange<'a> {
+    /// "picked_up", "dropped", "placed" или "removed".
+    pub action: &'a str,
+    pub user_id: Uuid,
+    pub username: &'a str,
+    pub item_id: &'a str,
+    pub quantity: i32,
+}
+
+/// Рассылает всем в комнате ее новое содержимое вместе с описанием изменения.
+/// Вызывается после коммита, чтобы клиенты не увидели откатившееся состояние.
+pub async fn broadcast_room_items(state: &AppState, room: RoomKey, change: RoomItemsChange<'_>) -> Result<(), AppError> {
+    let items = load_room_items(&state.pool, room.location_id, room.instance_id).await?;
+    let message = serde_json::json!({
+        "type": "room_items_changed",
+        "action": change.action,
+        "user_id": change.user_id,
+        "username": change.username,
+        "item_id": change.item_id,
+        "quantity": change.quantity,
+        "items": items,
+    });
+
+    let rooms = state.ws_state.rooms.lock().await;
+    if let Some(clients) = rooms.get(&room) {
+        broadcast_message(clients, message.to_string(), Uuid::nil());
+    }
+    Ok(())
+}
@@ -0,0 +1,721 @@
+// /var/www/structure/server/src/world/rules.rs
+
+// Язык условий для переходов между локациями и ответов в диалогах NPC.
+//
+// Примеры выражений:
+//   has item keycard_red
+//   flag reactor_online = true
+//   player_flag met_guard
+//   time between 22:00 and 04:00
+//   role >= Architect
+//   access >= 2 and not flag lockdown
+//   players >= 2
+//
+// Выражения разбираются при сохранении (чтобы Архитектор сразу увидел ошибку)
+// и вычисляются на сервере при каждом запросе локации или перемещении.
+//
+// Время в `time between` - время UTC, а не местное время сервера или игрока:
+// интервал "ночь с 22:00 до 06:00" по Москве записывается как `time between 19:00 and 03:00`.
+
+use super::grants::active_clearance;
+use crate::{error::AppError, models::user::UserRole};
+use chrono::{NaiveTime, Utc};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CmpOp {
+    fn compare<T: PartialOrd>(self, left: &T, right: &T) -> bool {
+        match self {
+            CmpOp::Eq => left == right,
+            CmpOp::Ne => left != right,
+            CmpOp::Lt => left < right,
+            CmpOp::Le => left <= right,
+            CmpOp::Gt => left > right,
+            CmpOp::Ge => left >= right,
+        }
+    }
+}
+
+/// Разобранное условие.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    HasItem(String),
+    /// `flag name` без сравнения: флаг установлен и не "ложный".
+    FlagSet(String),
+    Flag { name: String, op: CmpOp, value: Value },
+    /// Флаги самого игрока (из разговоров с NPC) живут отдельно от флагов мира,
+    /// чтобы NPC не мог открыть переход, закрытый флагом мира.
+    PlayerFlagSet(String),
+    PlayerFlag { name: String, op: CmpOp, value: Value },
+    /// Интервал в UTC, может переходить через полночь: `between 22:00 and 04:00`.
+    TimeBetween { start: NaiveTime, end: NaiveTime },
+    Role { op: CmpOp, role: UserRole },
+    AccessLevel { op: CmpOp, level: i32 },
+    /// Сколько игроков находится в локации (в той же копии, если она инстанцирована).
+    Players { op: CmpOp, count: i64 },
+}
+
+/// Ошибка разбора с позицией (в символах) в исходном выражении.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at position {}: {}", self.position, self.message)
+    }
+}
+
+/// Все, что нужно знать об игроке и мире для вычисления условия.
+pub struct RuleContext {
+    /// Действующий уровень: постоянный или выше, если есть временный допуск.
+    pub access_level: i32,
+    /// Локации, доступ к которым выдан временным допуском независимо от уровня.
+    pub granted_locations: HashSet<Uuid>,
+    pub role: UserRole,
+    pub items: HashSet<String>,
+    pub flags: HashMap<String, Value>,
+    pub player_flags: HashMap<String, Value>,
+    /// Текущее время UTC.
+    pub now: NaiveTime,
+    pub players_present: i64,
+}
+
+impl RuleContext {
+    /// Собирает контекст для игрока: его уровень доступа (с временными допусками), предметы, собственные флаги
+    /// и флаги мира (с учетом состояния копии локации, если игрок внутри нее).
+    pub async fn load(pool: &PgPool, user_id: Uuid, role: UserRole) -> Result<Self, AppError> {
+        let player = sqlx::query!(
+            r#"
+            SELECT p.flags AS player_flags, i.state AS "instance_state?",
+                   ARRAY(SELECT item_id FROM player_items WHERE user_id = p.user_id) AS "items!",
+                   (SELECT COUNT(*) FROM players o
+                    WHERE o.current_location_id = p.current_location_id
+                      AND o.current_instance_id IS NOT DISTINCT FROM p.current_instance_id) AS "players_present!"
+            FROM players p
+            LEFT JOIN location_instances i ON i.id = p.current_instance_id
+            WHERE p.user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(pool)
+        .await?;
+        let clearance = active_clearance(pool, user_id).await?;
+
+        let mut flags: HashMap<String, Value> = sqlx::query!("SELECT key, value FROM world_flags")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|row| (row.key, row.value))
+            .collect();
+        // Внутри копии локации ее собственное состояние перекрывает флаги мира
+        if let Some(Value::Object(instance_state)) = player.instance_state {
+            flags.extend(instance_state);
+        }
+
+        Ok(Self {
+            access_level: clearance.access_level,
+            granted_locations: clearance.locations.into_iter().collect(),
+            role,
+            items: player.items.into_iter().collect(),
+            flags,
+            player_flags: match player.player_flags {
+                Value::Object(player_flags) => player_flags.into_iter().collect(),
+                _ => HashMap::new(),
+            },
+            now: Utc::now().time(),
+            players_present: player.players_present,
+        })
+    }
+
+    /// Контекст постороннего: без допуска, предметов и флагов, но с тем же временем и числом игроков.
+    /// По нему отрисовывается описание закрытой локации, чтобы в искаженный текст не попали
+    /// значения флагов и ветки шаблона для допущенных.
+    pub fn outsider(&self) -> Self {
+        Self {
+            access_level: 0,
+            granted_locations: HashSet::new(),
+            role: UserRole::User,
+            items: HashSet::new(),
+            flags: HashMap::new(),
+            player_flags: HashMap::new(),
+            now: self.now,
+            players_present: self.players_present,
+        }
+    }
+
+    /// Пропускает ли допуск игрока в локацию с таким уровнем секретности.
+    pub fn clears(&self, location_id: Uuid, security_level: i32) -> bool {
+        self.access_level >= security_level || self.granted_locations.contains(&location_id)
+    }
+}
+
+/// Сколько игроков находится в локации с точки зрения данного игрока:
+/// если он сам внутри копии этой локации, считаются только игроки той же копии.
+pub async fn count_players_present(pool: &PgPool, user_id: Uuid, location_id: Uuid) -> Result<i64, AppError> {
+    let count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM players o, players me
+        WHERE me.user_id = $1 AND o.current_location_id = $2
+          AND o.current_instance_id IS NOT DISTINCT FROM
+              (CASE WHEN me.current_location_id = $2 THEN me.current_instance_id END)
+        "#,
+        user_id,
+        location_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(count)
+}
+
+impl Condition {
+    pub fn evaluate(&self, ctx: &RuleContext) -> bool {
+        match self {
+            Condition::And(left, right) => left.evaluate(ctx) && right.evaluate(ctx),
+            Condition::Or(left, right) => left.evaluate(ctx) || right.evaluate(ctx),
+            Condition::Not(inner) => !inner.evaluate(ctx),
+            Condition::HasItem(item) => ctx.items.contains(item),
+            Condition::FlagSet(name) => ctx.flags.get(name).is_some_and(is_truthy),
+            Condition::Flag { name, op, value } => {
+                let actual = ctx.flags.get(name).unwrap_or(&Value::Null);
+                compare_values(*op, actual, value)
+            }
+            Condition::PlayerFlagSet(name) => ctx.player_flags.get(name).is_some_and(is_truthy),
+            Condition::PlayerFlag { name, op, value } => {
+                let actual = ctx.player_flags.get(name).unwrap_or(&Value::Null);
+                compare_values(*op, actual, value)
+            }
+            Condition::TimeBetween { start, end } => {
+                if start <= end {
+                    *start <= ctx.now && ctx.now < *end
+                } else {
+                    ctx.now >= *start || ctx.now < *end
+                }
+            }
+            Condition::Role { op, role } => op.compare(&ctx.role, role),
+            Condition::AccessLevel { op, level } => op.compare(&ctx.access_level, level),
+            Condition::Players { op, count } => op.compare(&ctx.players_present, count),
+        }
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+/// Равенство работает для любых значений, порядок - только для чисел.
+fn compare_values(op: CmpOp, actual: &Value, expected: &Value) -> bool {
+    match op {
+        CmpOp::Eq => actual == expected,
+        CmpOp::Ne => actual != expected,
+        _ => match (actual.as_f64(), expected.as_f64()) {
+            (Some(a), Some(b)) => op.compare(&a, &b),
+            _ => false,
+        },
+    }
+}
+
+/// Проверка выражения перед сохранением: пустое считается отсутствующим,
+/// непустое должно разбираться без ошибок.
+pub fn validate_expression(field: &str, source: Option<&str>) -> Result<Option<String>, AppError> {
+    let Some(source) = source.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    parse(source).map_err(|e| AppError::BadRequest(format!("Invalid {} {}", field, e)))?;
+    Ok(Some(source.to_string()))
+}
+
+/// Разбирает выражение условия.
+pub fn parse(source: &str) -> Result<Condition, RuleError> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0, end: source.chars().count() };
+    let condition = parser.parse_or()?;
+    if let Some((position, token)) = parser.tokens.get(parser.pos) {
+        return Err(RuleError {
+            position: *position,
+            message: format!("unexpected {}", token.describe()),
+        });
+    }
+    Ok(condition)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(i64),
+    Time(NaiveTime),
+    Str(String),
+    Op(CmpOp),
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(w) => format!("'{}'", w),
+            Token::Number(n) => format!("number {}", n),
+            Token::Time(t) => format!("time {}", t.format("%H:%M")),
+            Token::Str(s) => format!("string \"{}\"", s),
+            Token::Op(_) => "comparison operator".to_string(),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, RuleError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = match c {
+            '(' => {
+                i += 1;
+                Token::LParen
+            }
+            ')' => {
+                i += 1;
+                Token::RParen
+            }
+            '=' | '!' | '<' | '>' => {
+                let next = chars.get(i + 1).copied();
+                let (op, len) = match (c, next) {
+                    ('=', Some('=')) => (CmpOp::Eq, 2),
+                    ('=', _) => (CmpOp::Eq, 1),
+                    ('!', Some('=')) => (CmpOp::Ne, 2),
+                    ('<', Some('=')) => (CmpOp::Le, 2),
+                    ('<', _) => (CmpOp::Lt, 1),
+                    ('>', Some('=')) => (CmpOp::Ge, 2),
+                    ('>', _) => (CmpOp::Gt, 1),
+                    _ => {
+                        return Err(RuleError { position: start, message: "expected '!='".to_string() });
+                    }
+                };
+                i += len;
+                Token::Op(op)
+            }
+            '"' => {
+                i += 1;
+                let mut value = String::new();
+                loop {
+                    match chars.get(i) {
+                        Some('"') => break,
+                        Some(ch) => value.push(*ch),
+                        None => {
+                            return Err(RuleError { position: start, message: "unterminated string".to_string() });
+                        }
+                    }
+                    i += 1;
+                }
+                i += 1;
+                Token::Str(value)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == ':') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                if text.contains(':') {
+                    let time = NaiveTime::parse_from_str(&text, "%H:%M").map_err(|_| RuleError {
+                        position: start,
+                        message: format!("invalid time '{}', expected HH:MM", text),
+                    })?;
+                    Token::Time(time)
+                } else {
+                    let number = text.parse().map_err(|_| RuleError {
+                        position: start,
+                        message: format!("invalid number '{}'", text),
+                    })?;
+                    Token::Number(number)
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '-' | '.')) {
+                    i += 1;
+                }
+                Token::Word(chars[start..i].iter().collect())
+            }
+            other => {
+                return Err(RuleError { position: start, message: format!("unexpected character '{}'", other) });
+            }
+        };
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+/// Слова-связки, которые не могут быть именами предметов, флагов или ролей.
+const RESERVED_WORDS: [&str; 3] = ["and", "or", "not"];
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(position, _)| *position)
+    }
+
+    fn error(&self, message: impl Into<String>) -> RuleError {
+        RuleError { position: self.position(), message: message.into() }
+    }
+
+    fn next(&mut self, expected: &str) -> Result<Token, RuleError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|(_, token)| token.clone())
+            .ok_or_else(|| self.error(format!("expected {}, found end of expression", expected)))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek().is_some_and(|token| token.is_keyword(keyword)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), RuleError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", keyword)))
+        }
+    }
+
+    fn expect_op(&mut self) -> Result<CmpOp, RuleError> {
+        match self.next("comparison operator")? {
+            Token::Op(op) => Ok(op),
+            _ => {
+                self.pos -= 1;
+                Err(self.error("expected comparison operator"))
+            }
+        }
+    }
+
+    fn expect_word(&mut self, what: &str) -> Result<String, RuleError> {
+        match self.next(what)? {
+            Token::Word(word) if !RESERVED_WORDS.iter().any(|r| word.eq_ignore_ascii_case(r)) => Ok(word),
+            _ => {
+                self.pos -= 1;
+                Err(self.error(format!("expected {}", what)))
+            }
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Condition, RuleError> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("or") {
+            let right = self.parse_and()?;
+            left = Condition::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Condition, RuleError> {
+        let mut left = self.parse_unary()?;
+        while self.eat_keyword("and") {
+            let right = self.parse_unary()?;
+            left = Condition::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Condition, RuleError> {
+        if self.eat_keyword("not") {
+            return Ok(Condition::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let inner = self.parse_or()?;
+            if self.peek() != Some(&Token::RParen) {
+                return Err(self.error("expected ')'"));
+            }
+            self.pos += 1;
+            return Ok(inner);
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Condition, RuleError> {
+        let start = self.pos;
+        let word = self.expect_word("condition")?.to_ascii_lowercase();
+        match word.as_str() {
+            "has" => {
+                self.expect_keyword("item")?;
+                Ok(Condition::HasItem(self.expect_word("item id")?))
+            }
+            "flag" => {
+                let name = self.expect_word("flag name")?;
+                Ok(match self.parse_flag_comparison()? {
+                    Some((op, value)) => Condition::Flag { name, op, value },
+                    None => Condition::FlagSet(name),
+                })
+            }
+            "player_flag" => {
+                let name = self.expect_word("flag name")?;
+                Ok(match self.parse_flag_comparison()? {
+                    Some((op, value)) => Condition::PlayerFlag { name, op, value },
+                    None => Condition::PlayerFlagSet(name),
+                })
+            }
+            "time" => {
+                self.expect_keyword("between")?;
+                let start = self.expect_time()?;
+                self.expect_keyword("and")?;
+                let end = self.expect_time()?;
+                Ok(Condition::TimeBetween { start, end })
+            }
+            "role" => {
+                let op = self.expect_op()?;
+                let name = self.expect_word("role name")?;
+                let role = name.parse::<UserRole>().map_err(|_| {
+                    self.pos -= 1;
+                    self.error(format!("unknown role '{}'", name))
+                })?;
+                Ok(Condition::Role { op, role })
+            }
+            "access" => {
+                let op = self.expect_op()?;
+                match self.next("access level")? {
+                    Token::Number(n) => {
+                        let level = i32::try_from(n).map_err(|_| self.error("access level out of range"))?;
+                        Ok(Condition::AccessLevel { op, level })
+                    }
+                    _ => {
+                        self.pos -= 1;
+                        Err(self.error("expected access level"))
+                    }
+                }
+            }
+            "players" => {
+                let op = self.expect_op()?;
+                match self.next("number of players")? {
+                    Token::Number(count) => Ok(Condition::Players { op, count }),
+                    _ => {
+                        self.pos -= 1;
+                        Err(self.error("expected number of players"))
+                    }
+                }
+            }
+            _ => {
+                self.pos = start;
+                Err(self.error(format!("unknown condition '{}'", word)))
+            }
+        }
+    }
+
+    /// Необязательное сравнение после имени флага: `= true`, `>= 3`, `!= "red"`.
+    fn parse_flag_comparison(&mut self) -> Result<Option<(CmpOp, Value)>, RuleError> {
+        if !matches!(self.peek(), Some(Token::Op(_))) {
+            return Ok(None);
+        }
+        let op = self.expect_op()?;
+        let value = match self.next("flag value")? {
+            Token::Number(n) => Value::from(n),
+            Token::Str(s) => Value::String(s),
+            Token::Word(w) if w.eq_ignore_ascii_case("true") => Value::Bool(true),
+            Token::Word(w) if w.eq_ignore_ascii_case("false") => Value::Bool(false),
+            Token::Word(w) => Value::String(w),
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("expected flag value"));
+            }
+        };
+        Ok(Some((op, value)))
+    }
+
+    fn expect_time(&mut self) -> Result<NaiveTime, RuleError> {
+        match self.next("time")? {
+            Token::Time(time) => Ok(time),
+            _ => {
+                self.pos -= 1;
+                Err(self.error("expected time in HH:MM format"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> RuleContext {
+        RuleContext {
+            access_level: 2,
+            granted_locations: HashSet::new(),
+            role: UserRole::Moderator,
+            items: HashSet::from(["keycard_red".to_string()]),
+            flags: HashMap::from([
+                ("reactor_online".to_string(), Value::Bool(true)),
+                ("lockdown".to_string(), Value::Bool(false)),
+                ("alert".to_string(), Value::from(3)),
+                ("sector".to_string(), Value::from("b")),
+            ]),
+            player_flags: HashMap::from([("met_guard".to_string(), Value::Bool(true))]),
+            now: NaiveTime::from_hms_opt(23, 30, 0).unwrap(),
+            players_present: 2,
+        }
+    }
+
+    fn eval(source: &str) -> bool {
+        parse(source).unwrap_or_else(|e| panic!("{}: {}", source, e)).evaluate(&context())
+    }
+
+    fn error(source: &str) -> RuleError {
+        parse(source).expect_err(source)
+    }
+
+    #[test]
+    fn parse_errors_report_position() {
+        assert_eq!(error("").position, 0);
+        assert_eq!(error("has keycard").message, "expected 'item'");
+        assert_eq!(error("flag").message, "expected flag name, found end of expression");
+        assert_eq!(error("unknown x").message, "unknown condition 'unknown'");
+        assert_eq!(error("access >= 2 and").position, 15);
+        assert_eq!(error("(access >= 2").message, "expected ')'");
+        assert_eq!(error("access >= 2 )").message, "unexpected ')'");
+        assert_eq!(error("flag sector = \"b").message, "unterminated string");
+        assert_eq!(error("time between 25:00 and 04:00").position, 13);
+        assert_eq!(error("role >= Janitor").message, "unknown role 'Janitor'");
+        assert_eq!(error("flag and").message, "expected flag name");
+        assert_eq!(error("access ! 2").message, "expected '!='");
+        assert_eq!(error("players >= many").message, "expected number of players");
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let parsed = parse("has item a or has item b and has item c").unwrap();
+        let expected = Condition::Or(
+            Box::new(Condition::HasItem("a".to_string())),
+            Box::new(Condition::And(
+                Box::new(Condition::HasItem("b".to_string())),
+                Box::new(Condition::HasItem("c".to_string())),
+            )),
+        );
+        assert_eq!(parsed, expected);
+
+        assert!(eval("has item keycard_red or has item a and has item b"));
+        assert!(!eval("(has item keycard_red or has item a) and has item b"));
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert!(eval("not flag lockdown and access >= 2"));
+        assert!(!eval("not (flag lockdown or access >= 2)"));
+        assert!(eval("not not flag reactor_online"));
+        assert!(eval("NOT flag lockdown AND has item keycard_red"));
+    }
+
+    #[test]
+    fn has_item() {
+        assert!(eval("has item keycard_red"));
+        assert!(!eval("has item keycard_blue"));
+    }
+
+    #[test]
+    fn world_flags() {
+        assert!(eval("flag reactor_online"));
+        assert!(!eval("flag lockdown"));
+        assert!(!eval("flag missing"));
+        assert!(eval("flag reactor_online = true"));
+        assert!(eval("flag alert >= 3"));
+        assert!(!eval("flag alert > 3"));
+        assert!(eval("flag sector == \"b\""));
+        assert!(eval("flag sector != c"));
+        assert!(!eval("flag sector > 1"));
+    }
+
+    #[test]
+    fn player_flags_are_separate_from_world_flags() {
+        assert!(eval("player_flag met_guard"));
+        assert!(eval("player_flag met_guard = true"));
+        assert!(!eval("flag met_guard"));
+        assert!(!eval("player_flag reactor_online"));
+    }
+
+    #[test]
+    fn time_between() {
+        assert!(eval("time between 22:00 and 04:00"));
+        assert!(!eval("time between 04:00 and 22:00"));
+        assert!(eval("time between 23:30 and 23:31"));
+        assert!(!eval("time between 23:00 and 23:30"));
+    }
+
+    #[test]
+    fn role_and_access() {
+        assert!(eval("role >= Moderator"));
+        assert!(eval("role < architect"));
+        assert!(!eval("role = User"));
+        assert!(eval("access = 2"));
+        assert!(!eval("access > 2"));
+        assert!(eval("access >= -1"));
+    }
+
+    #[test]
+    fn players_present() {
+        assert!(eval("players >= 2"));
+        assert!(!eval("players < 2"));
+    }
+
+    #[test]
+    fn outsider_sees_nothing_private() {
+        let ctx = context().outsider();
+        for source in ["has item keycard_red", "flag reactor_online", "player_flag met_guard", "access >= 1"] {
+            assert!(!parse(source).unwrap().evaluate(&ctx), "{}", source);
+        }
+        assert!(parse("players >= 2").unwrap().evaluate(&ctx));
+    }
+}
@@ -0,0 +1,114 @@
+// /server/src/world/spawn.rs
+
+use super::{
+    quests::{emit_quest_events, QuestEvent},
+    visits::record_visit,
+};
+use crate::{
+    auth::Claims,
+    config::Config,
+    error::AppError,
+    models::user::UserRole,
+    state::AppState,
+    ws::{state::RoomKey, utils::change_room},
+};
+use rand::seq::SliceRandom;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+/// Выбирает локацию появления для роли: случайную точку с учетом веса среди точек
+/// этой роли, а если их нет - среди общих. Без единой точки используется
+/// FALLBACK_SPAWN_LOCATION, затем самая старая локация мира.
+pub async fn pick_spawn_location(conn: &mut PgConnection, role: UserRole, config: &Config) -> Result<Uuid, AppError> {
+    let points = sqlx::query!(
+        r#"SELECT location_id, weight, role AS "role: UserRole" FROM spawn_points WHERE role IS NULL OR role = $1"#,
+        role as UserRole
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let (own, general): (Vec<_>, Vec<_>) = points.into_iter().partition(|point| point.role == Some(role));
+    let candidates = if own.is_empty() { general } else { own };
+    if let Ok(point) = candidates.choose_weighted(&mut rand::thread_rng(), |point| point.weight) {
+        return Ok(point.location_id);
+    }
+
+    tracing::warn!("Не настроено ни одной точки появления для роли {:?}, используется запасная", role);
+    let fallback = sqlx::query_scalar!(
+        "SELECT id FROM locations ORDER BY (id = $1) IS TRUE DESC, created_at LIMIT 1",
+        config.fallback_spawn_location_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    fallback.ok_or_else(|| {
+        tracing::error!("В мире нет ни одной локации, игроку негде появиться");
+        AppError::InternalServerError
+    })
+}
+
+/// Переносит игрока в точку появления его роли и возвращает ее. Строка игрока должна быть заблокирована,
+/// а перевод WS-клиента в новую комнату остается вызывающему.
+pub async fn respawn_player(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    role: UserRole,
+    config: &Config,
+) -> Result<Uuid, AppError> {
+    let location_id = pick_spawn_location(conn, role, config).await?;
+    sqlx::query!(
+        "UPDATE players SET current_location_id = $1, current_instance_id = NULL WHERE user_id = $2",
+        location_id,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    record_visit(&mut *conn, user_id, location_id).await?;
+
+    Ok(location_id)
+}
+
+/// Позиция игрока: локация, копия и был ли он только что возвращен в точку появления.
+pub struct PlayerPosition {
+    pub location_id: Uuid,
+    pub instance_id: Option<Uuid>,
+    pub respawned: bool,
+}
+
+/// Возвращает текущую позицию игрока (локация и копия). Если его локацию удалили
+/// и `current_location_id` стал NULL, игрок возвращается в точку появления, и туда же
+/// переводится его WS-клиент, оставшийся в комнате удаленной локации.
+pub async fn ensure_player_location(state: &AppState, claims: &Claims) -> Result<(Uuid, Option<Uuid>), AppError> {
+    let position = locate_player(&state.pool, claims.sub, claims.role, &state.config).await?;
+    if position.respawned {
+        change_room(state, claims.sub, &claims.username, None, RoomKey::new(position.location_id, None)).await;
+        emit_quest_events(state, claims.sub, vec![QuestEvent::Visited(position.location_id)]).await;
+    }
+    Ok((position.location_id, position.instance_id))
+}
+
+/// То же, что `ensure_player_location`, но без событий заданий: для вызова под блокировкой комнат,
+/// под которой события отправлять нельзя. Если `respawned`, вызывающий сам сообщает о посещении.
+pub async fn locate_player(
+    pool: &PgPool,
+    user_id: Uuid,
+    role: UserRole,
+    config: &Config,
+) -> Result<PlayerPosition, AppError> {
+    let mut tx = pool.begin().await?;
+    let player = sqlx::query!(
+        "SELECT current_location_id, current_instance_id FROM players WHERE user_id = $1 FOR UPDATE",
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if let Some(location_id) = player.current_location_id {
+        return Ok(PlayerPosition { location_id, instance_id: player.current_instance_id, respawned: false });
+    }
+
+    let location_id = respawn_player(&mut tx, user_id, role, config).await?;
+    tx.commit().await?;
+
+    tracing::info!("Игрок {} потерял локацию и возвращен в точку появления {}", user_id, location_id);
+    Ok(PlayerPosition { location_id, instance_id: None, respawned: true })
+}
@@ -0,0 +1,313 @@
+// /server/src/world/templates.rs
+
+// Шаблоны в описаниях локаций.
+//
+// Подстановки:
+//   {{ player.name }}, {{ player.access_level }}, {{ player.role }}
+//   {{ time }}       - текущее время UTC, HH:MM
+//   {{ players }}    - сколько игроков в локации
+//   {{ flag.NAME }}  - значение флага мира (пусто, если флаг не задан)
+//
+// Условные блоки используют тот же язык, что и условия переходов:
+//   {% if time between 22:00 and 06:00 %}Свет погашен.{% elif players > 1 %}Здесь шумно.{% else %}Тихо.{% endif %}
+//
+// Шаблон разбирается при сохранении. Если в базе все же оказался шаблон с ошибкой
+// (например, записанный до появления шаблонов), игрок видит текст без тегов, а не ошибку.
+
+use super::rules::{self, Condition, RuleContext, RuleError};
+use crate::error::AppError;
+use serde_json::Value;
+
+/// Глубже этого вложенные {% if %} не разбираем.
+const MAX_NESTING: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Variable {
+    PlayerName,
+    PlayerAccessLevel,
+    PlayerRole,
+    Time,
+    Players,
+    Flag(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Var(Variable),
+    If { branches: Vec<(Condition, Vec<Node>)>, otherwise: Vec<Node> },
+}
+
+/// Все, что может понадобиться шаблону при отрисовке.
+pub struct TemplateContext<'a> {
+    pub player_name: &'a str,
+    pub rules: &'a RuleContext,
+}
+
+/// Проверка шаблона перед сохранением.
+pub fn validate_template(field: &str, source: &str) -> Result<(), AppError> {
+    parse(source).map_err(|e| AppError::BadRequest(format!("Invalid {} template {}", field, e)))?;
+    Ok(())
+}
+
+/// Отрисовывает шаблон для игрока. Никогда не возвращает ошибку: сломанный шаблон
+/// превращается в текст без тегов, а подробности уходят только в лог.
+pub fn render(source: &str, ctx: &TemplateContext) -> String {
+    match parse(source) {
+        Ok(nodes) => {
+            let mut out = String::with_capacity(source.len());
+            render_nodes(&nodes, ctx, &mut out);
+            out
+        }
+        Err(e) => {
+            tracing::warn!("Не удалось разобрать шаблон описания {}", e);
+            strip_tags(source)
+        }
+    }
+}
+
+fn render_nodes(nodes: &[Node], ctx: &TemplateContext, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(variable) => out.push_str(&render_variable(variable, ctx)),
+            Node::If { branches, otherwise } => {
+                let body = branches
+                    .iter()
+                    .find(|(condition, _)| condition.evaluate(ctx.rules))
+                    .map_or(otherwise, |(_, body)| body);
+                render_nodes(body, ctx, out);
+            }
+        }
+    }
+}
+
+fn render_variable(variable: &Variable, ctx: &TemplateContext) -> String {
+    match variable {
+        Variable::PlayerName => ctx.player_name.to_string(),
+        Variable::PlayerAccessLevel => ctx.rules.access_level.to_string(),
+        Variable::PlayerRole => format!("{:?}", ctx.rules.role),
+        Variable::Time => ctx.rules.now.format("%H:%M").to_string(),
+        Variable::Players => ctx.rules.players_present.to_string(),
+        Variable::Flag(name) => match ctx.rules.flags.get(name) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(s)) => s.clone(),
+            Some(other) => other.to_string(),
+        },
+    }
+}
+
+/// Убирает все теги, оставляя только обычный текст. Незакрытый тег отрезается до конца строки.
+fn strip_tags(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find('{') {
+        let closer = match rest[start..].chars().nth(1) {
+            Some('{') => "}}",
+            Some('%') => "%}",
+            _ => {
+                out.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+                continue;
+            }
+        };
+        out.push_str(&rest[..start]);
+        match rest[start + 2..].find(closer) {
+            Some(end) => rest = &rest[start + 2 + end + 2..],
+            None => return out,
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+enum Segment {
+    Text(String),
+    /// `{{ ... }}`; позиция указывает на начало содержимого.
+    Output { position: usize, body: String },
+    /// `{% ... %}`
+    Tag { position: usize, body: String },
+}
+
+fn split_segments(source: &str) -> Result<Vec<Segment>, RuleError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let closer = match (chars[i], chars.get(i + 1)) {
+            ('{', Some('{')) => '}',
+            ('{', Some('%')) => '%',
+            (c, _) => {
+                text.push(c);
+                i += 1;
+                continue;
+            }
+        };
+        let start = i;
+        i += 2;
+        let mut body = String::new();
+        loop {
+            match (chars.get(i), chars.get(i + 1)) {
+                (Some(c), Some('}')) if *c == closer => {
+                    i += 2;
+                    break;
+                }
+                (Some(c), _) => {
+                    body.push(*c);
+                    i += 1;
+                }
+                (None, _) => return Err(RuleError { position: start, message: "unclosed tag".to_string() }),
+            }
+        }
+
+        if !text.is_empty() {
+            segments.push(Segment::Text(std::mem::take(&mut text)));
+        }
+        let position = start + 2;
+        segments.push(if closer == '}' { Segment::Output { position, body } } else { Segment::Tag { position, body } });
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    Ok(segments)
+}
+
+fn parse(source: &str) -> Result<Vec<Node>, RuleError> {
+    let mut parser = TemplateParser { segments: split_segments(source)?.into_iter() };
+    let (nodes, terminator) = parser.parse_block(0)?;
+    if let Some(terminator) = terminator {
+        return Err(RuleError {
+            position: terminator.position,
+            message: format!("unexpected '{}'", terminator.kind.keyword()),
+        });
+    }
+    Ok(nodes)
+}
+
+enum TerminatorKind {
+    Elif(Condition),
+    Else,
+    Endif,
+}
+
+impl TerminatorKind {
+    fn keyword(&self) -> &'static str {
+        match self {
+            TerminatorKind::Elif(_) => "elif",
+            TerminatorKind::Else => "else",
+            TerminatorKind::Endif => "endif",
+        }
+    }
+}
+
+/// Тег, которым закончился блок: `elif`, `else` или `endif`.
+struct Terminator {
+    position: usize,
+    kind: TerminatorKind,
+}
+
+struct TemplateParser {
+    segments: std::vec::IntoIter<Segment>,
+}
+
+impl TemplateParser {
+    fn parse_block(&mut self, depth: usize) -> Result<(Vec<Node>, Option<Terminator>), RuleError> {
+        let mut nodes = Vec::new();
+        while let Some(segment) = self.segments.next() {
+            match segment {
+                Segment::Text(text) => nodes.push(Node::Text(text)),
+                Segment::Output { position, body } => nodes.push(Node::Var(parse_variable(&body, position)?)),
+                Segment::Tag { position, body } => {
+                    let (keyword, argument, argument_position) = split_tag(&body, position);
+                    match keyword.as_str() {
+                        "if" => {
+                            if depth >= MAX_NESTING {
+                                return Err(RuleError { position, message: "too many nested 'if' blocks".to_string() });
+                            }
+                            let condition = parse_condition(argument, argument_position)?;
+                            nodes.push(self.parse_if(condition, position, depth + 1)?);
+                        }
+                        "elif" => {
+                            let condition = parse_condition(argument, argument_position)?;
+                            return Ok((nodes, Some(Terminator { position, kind: TerminatorKind::Elif(condition) })));
+                        }
+                        "else" | "endif" => {
+                            if !argument.trim().is_empty() {
+                                return Err(RuleError {
+                                    position: argument_position,
+                                    message: format!("'{}' takes no arguments", keyword),
+                                });
+                            }
+                            let kind = if keyword == "else" { TerminatorKind::Else } else { TerminatorKind::Endif };
+                            return Ok((nodes, Some(Terminator { position, kind })));
+                        }
+                        _ => return Err(RuleError { position, message: format!("unknown tag '{}'", keyword) }),
+                    }
+                }
+            }
+        }
+        Ok((nodes, None))
+    }
+
+    fn parse_if(&mut self, first: Condition, position: usize, depth: usize) -> Result<Node, RuleError> {
+        let unclosed = || RuleError { position, message: "'if' without 'endif'".to_string() };
+        let mut branches = Vec::new();
+        let mut condition = first;
+        loop {
+            let (body, terminator) = self.parse_block(depth)?;
+            branches.push((condition, body));
+            match terminator.ok_or_else(unclosed)?.kind {
+                TerminatorKind::Elif(next) => condition = next,
+                TerminatorKind::Endif => return Ok(Node::If { branches, otherwise: Vec::new() }),
+                TerminatorKind::Else => {
+                    let (otherwise, terminator) = self.parse_block(depth)?;
+                    let terminator = terminator.ok_or_else(unclosed)?;
+                    return match terminator.kind {
+                        TerminatorKind::Endif => Ok(Node::If { branches, otherwise }),
+                        other => Err(RuleError {
+                            position: terminator.position,
+                            message: format!("expected 'endif', found '{}'", other.keyword()),
+                        }),
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Делит содержимое тега на ключевое слово и аргумент, считая позицию аргумента в исходном тексте.
+fn split_tag(body: &str, position: usize) -> (String, &str, usize) {
+    let leading = body.chars().take_while(|c| c.is_whitespace()).count();
+    let trimmed = body.trim_start();
+    let keyword_len = trimmed.chars().take_while(|c| !c.is_whitespace()).count();
+    let keyword: String = trimmed.chars().take(keyword_len).collect();
+    let argument = &trimmed[keyword.len()..];
+    (keyword.to_ascii_lowercase(), argument, position + leading + keyword_len)
+}
+
+fn parse_condition(source: &str, position: usize) -> Result<Condition, RuleError> {
+    rules::parse(source).map_err(|e| RuleError { position: position + e.position, message: e.message })
+}
+
+fn parse_variable(body: &str, position: usize) -> Result<Variable, RuleError> {
+    let name = body.trim();
+    let variable = match name {
+        "player.name" => Variable::PlayerName,
+        "player.access_level" => Variable::PlayerAccessLevel,
+        "player.role" => Variable::PlayerRole,
+        "time" => Variable::Time,
+        "players" => Variable::Players,
+        _ => match name.strip_prefix("flag.") {
+            Some(flag) if !flag.is_empty() && flag.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.')) => {
+                Variable::Flag(flag.to_string())
+            }
+            _ => {
+                return Err(RuleError { position, message: format!("unknown variable '{}'", name) });
+            }
+        },
+    };
+    Ok(variable)
+}
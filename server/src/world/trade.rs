@@ -0,0 +1,457 @@
+// /server/src/world/trade.rs
+
+// Обмен между игроками.
+//
+// Все, что игрок выставил на обмен, сразу уходит в залог: предметы изымаются из
+// инвентаря, кредиты списываются. Поэтому выставленное нельзя параллельно выбросить,
+// использовать или предложить в другой сделке, а завершение сделки - это передача
+// залога второй стороне в одной транзакции. Отмена возвращает залог владельцам.
+//
+// Порядок блокировок везде один: сначала строка сделки (FOR UPDATE), потом строки игроков.
+// Операции со сделкой (lock_trade, cancel_player_trade) начинают с нее, а код, которому
+// нужна и строка игрока, и отмена его сделки (истечение допуска, удаление комнаты),
+// блокирует сделку игрока через lock_player_trade до того, как заблокировать игрока.
+// Исключение - propose_trade: она блокирует только игроков, а сделки еще не существует.
+
+use super::inventory::{grant_item, take_item};
+use crate::{error::AppError, state::AppState, ws::utils::send_to_user};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+pub const CANCELLED_BY_PLAYER: &str = "СДЕЛКА ОТМЕНЕНА ИГРОКОМ.";
+pub const CANCELLED_LEFT_ROOM: &str = "СДЕЛКА ОТМЕНЕНА: ИГРОК ПОКИНУЛ КОМНАТУ.";
+pub const CANCELLED_DISCONNECTED: &str = "СДЕЛКА ОТМЕНЕНА: ИГРОК ОТКЛЮЧИЛСЯ.";
+pub const CANCELLED_OFFER_CHANGED: &str = "СДЕЛКА ОТМЕНЕНА: ПРЕДЛОЖЕНИЕ ИЗМЕНЕНО ПОСЛЕ ПОДТВЕРЖДЕНИЯ.";
+
+#[derive(Serialize)]
+pub struct EscrowItem {
+    pub id: Uuid,
+    pub item_id: String,
+    pub name: String,
+    pub quantity: i32,
+}
+
+/// Одна сторона сделки.
+#[derive(Serialize)]
+pub struct TradeOffer {
+    pub user_id: Uuid,
+    pub username: String,
+    pub initiator: bool,
+    pub credits: i64,
+    pub confirmed: bool,
+    pub items: Vec<EscrowItem>,
+}
+
+#[derive(Serialize)]
+pub struct Trade {
+    pub id: Uuid,
+    pub location_id: Uuid,
+    pub instance_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    /// Инициатор всегда первый.
+    pub offers: Vec<TradeOffer>,
+}
+
+impl Trade {
+    pub fn participants(&self) -> Vec<Uuid> {
+        self.offers.iter().map(|offer| offer.user_id).collect()
+    }
+}
+
+pub async fn load_trade(conn: &mut PgConnection, trade_id: Uuid) -> Result<Trade, AppError> {
+    let trade = sqlx::query!("SELECT location_id, instance_id, created_at FROM trades WHERE id = $1", trade_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let offers = sqlx::query!(
+        r#"
+        SELECT o.user_id, u.username, o.initiator, o.credits, o.confirmed
+        FROM trade_offers o
+        JOIN users u ON u.id = o.user_id
+        WHERE o.trade_id = $1
+        ORDER BY o.initiator DESC
+        "#,
+        trade_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let items = sqlx::query!(
+        r#"
+        SELECT ti.id, ti.user_id, ti.item_id, d.name, ti.quantity
+        FROM trade_items ti
+        JOIN item_definitions d ON d.id = ti.item_id
+        WHERE ti.trade_id = $1
+        ORDER BY d.name
+        "#,
+        trade_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let offers = offers
+        .into_iter()
+        .map(|offer| TradeOffer {
+            items: items
+                .iter()
+                .filter(|item| item.user_id == offer.user_id)
+                .map(|item| EscrowItem {
+                    id: item.id,
+                    item_id: item.item_id.clone(),
+                    name: item.name.clone(),
+                    quantity: item.quantity,
+                })
+                .collect(),
+            user_id: offer.user_id,
+            username: offer.username,
+            initiator: offer.initiator,
+            credits: offer.credits,
+            confirmed: offer.confirmed,
+        })
+        .collect();
+
+    Ok(Trade {
+        id: trade_id,
+        location_id: trade.location_id,
+        instance_id: trade.instance_id,
+        created_at: trade.created_at,
+        offers,
+    })
+}
+
+/// Сделка, в которой сейчас участвует игрок.
+pub async fn find_player_trade(conn: &mut PgConnection, user_id: Uuid) -> Result<Option<Uuid>, AppError> {
+    let trade_id = sqlx::query_scalar!("SELECT trade_id FROM trade_offers WHERE user_id = $1", user_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(trade_id)
+}
+
+/// Блокирует сделку до конца транзакции. `NotFound`, если игрок в ней не участвует.
+pub async fn lock_trade(conn: &mut PgConnection, trade_id: Uuid, user_id: Uuid) -> Result<(), AppError> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT t.id FROM trades t
+        JOIN trade_offers o ON o.trade_id = t.id AND o.user_id = $2
+        WHERE t.id = $1
+        FOR UPDATE OF t
+        "#,
+        trade_id,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    Ok(())
+}
+
+/// Открывает сделку между двумя игроками, стоящими в одной комнате.
+pub async fn propose_trade(conn: &mut PgConnection, initiator_id: Uuid, partner_id: Uuid) -> Result<Uuid, AppError> {
+    if initiator_id == partner_id {
+        return Err(AppError::BadRequest("Cannot trade with yourself".to_string()));
+    }
+    // Строки обоих игроков блокируются в одном порядке, чтобы встречные предложения не взаимоблокировались
+    let players = sqlx::query!(
+        r#"
+        SELECT p.user_id, p.current_location_id, p.current_instance_id,
+               EXISTS (SELECT 1 FROM player_transits t WHERE t.user_id = p.user_id) AS "in_transit!"
+        FROM players p
+        WHERE p.user_id = ANY($1) ORDER BY p.user_id FOR UPDATE
+        "#,
+        &[initiator_id, partner_id]
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    if players.len() != 2 {
+        return Err(AppError::NotFound);
+    }
+    let (first, second) = (&players[0], &players[1]);
+    let location_id = first.current_location_id.ok_or(AppError::NotFound)?;
+    // Игрок в пути уже покинул комнату, хотя в базе еще числится в ней
+    if first.current_location_id != second.current_location_id
+        || first.current_instance_id != second.current_instance_id
+        || first.in_transit
+        || second.in_transit
+    {
+        return Err(AppError::AccessDenied("ИГРОКА НЕТ РЯДОМ С ВАМИ.".to_string()));
+    }
+
+    let busy = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM trade_offers WHERE user_id = ANY($1)) AS "busy!""#,
+        &[initiator_id, partner_id]
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if busy {
+        return Err(AppError::AccessDenied("ОДИН ИЗ ИГРОКОВ УЖЕ УЧАСТВУЕТ В СДЕЛКЕ.".to_string()));
+    }
+
+    let trade_id = sqlx::query_scalar!(
+        "INSERT INTO trades (location_id, instance_id) VALUES ($1, $2) RETURNING id",
+        location_id,
+        first.current_instance_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO trade_offers (trade_id, user_id, initiator) VALUES ($1, $2, TRUE), ($1, $3, FALSE)",
+        trade_id,
+        initiator_id,
+        partner_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(trade_id)
+}
+
+/// Подтвердил ли сделку хоть кто-то. После этого любое изменение предложения отменяет сделку,
+/// чтобы подтвердившему нельзя было подменить условия.
+pub async fn any_confirmed(conn: &mut PgConnection, trade_id: Uuid) -> Result<bool, AppError> {
+    let confirmed = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM trade_offers WHERE trade_id = $1 AND confirmed) AS "confirmed!""#,
+        trade_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(confirmed)
+}
+
+/// Переносит предмет из инвентаря игрока в залог сделки.
+pub async fn escrow_item(
+    conn: &mut PgConnection,
+    trade_id: Uuid,
+    user_id: Uuid,
+    inventory_item_id: Uuid,
+    quantity: Option<i32>,
+) -> Result<(), AppError> {
+    let (item_id, taken) = take_item(conn, user_id, inventory_item_id, quantity).await?;
+    sqlx::query!(
+        "INSERT INTO trade_items (trade_id, user_id, item_id, quantity) VALUES ($1, $2, $3, $4)",
+        trade_id,
+        user_id,
+        item_id,
+        taken
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Возвращает предмет из залога в инвентарь владельца.
+pub async fn release_item(conn: &mut PgConnection, trade_id: Uuid, user_id: Uuid, escrow_id: Uuid) -> Result<(), AppError> {
+    let item = sqlx::query!(
+        "DELETE FROM trade_items WHERE id = $1 AND trade_id = $2 AND user_id = $3 RETURNING item_id, quantity",
+        escrow_id,
+        trade_id,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    grant_item(conn, user_id, &item.item_id, item.quantity).await
+}
+
+/// Устанавливает сумму кредитов в залоге; разница списывается с игрока или возвращается ему.
+pub async fn set_escrow_credits(
+    conn: &mut PgConnection,
+    trade_id: Uuid,
+    user_id: Uuid,
+    credits: i64,
+) -> Result<(), AppError> {
+    if credits < 0 {
+        return Err(AppError::BadRequest("Credits cannot be negative".to_string()));
+    }
+    let current = sqlx::query_scalar!(
+        "SELECT credits FROM trade_offers WHERE trade_id = $1 AND user_id = $2",
+        trade_id,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let delta = credits - current;
+    let charged = sqlx::query!(
+        "UPDATE players SET credits = credits - $2 WHERE user_id = $1 AND credits >= $2",
+        user_id,
+        delta
+    )
+    .execute(&mut *conn)
+    .await?;
+    if charged.rows_affected() == 0 {
+        return Err(AppError::AccessDenied("НЕДОСТАТОЧНО КРЕДИТОВ.".to_string()));
+    }
+
+    sqlx::query!(
+        "UPDATE trade_offers SET credits = $3 WHERE trade_id = $1 AND user_id = $2",
+        trade_id,
+        user_id,
+        credits
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Оба участника все еще в той комнате, где открыли сделку.
+pub async fn participants_together(conn: &mut PgConnection, trade_id: Uuid) -> Result<bool, AppError> {
+    let present = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!" FROM trade_offers o
+        JOIN trades t ON t.id = o.trade_id
+        JOIN players p ON p.user_id = o.user_id
+        WHERE o.trade_id = $1
+          AND p.current_location_id = t.location_id
+          AND p.current_instance_id IS NOT DISTINCT FROM t.instance_id
+        "#,
+        trade_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(present == 2)
+}
+
+/// Отмечает подтверждение игрока. Если теперь подтвердили оба, залог каждой стороны
+/// передается другой и сделка закрывается; возвращает `true`, если так и вышло.
+pub async fn confirm_trade(conn: &mut PgConnection, trade_id: Uuid, user_id: Uuid) -> Result<bool, AppError> {
+    sqlx::query!(
+        "UPDATE trade_offers SET confirmed = TRUE WHERE trade_id = $1 AND user_id = $2",
+        trade_id,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let waiting = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM trade_offers WHERE trade_id = $1 AND NOT confirmed) AS "waiting!""#,
+        trade_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if waiting {
+        return Ok(false);
+    }
+
+    let items = sqlx::query!(
+        r#"
+        SELECT other.user_id AS receiver, ti.item_id, ti.quantity
+        FROM trade_items ti
+        JOIN trade_offers other ON other.trade_id = ti.trade_id AND other.user_id <> ti.user_id
+        WHERE ti.trade_id = $1
+        "#,
+        trade_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for item in items {
+        grant_item(conn, item.receiver, &item.item_id, item.quantity).await?;
+    }
+    sqlx::query!(
+        r#"
+        UPDATE players p SET credits = p.credits + other.credits
+        FROM trade_offers me
+        JOIN trade_offers other ON other.trade_id = me.trade_id AND other.user_id <> me.user_id
+        WHERE me.trade_id = $1 AND p.user_id = me.user_id
+        "#,
+        trade_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!("DELETE FROM trades WHERE id = $1", trade_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(true)
+}
+
+/// Возвращает залог владельцам и удаляет сделку. Сделка должна быть заблокирована.
+/// Возвращает участников, чтобы их можно было уведомить после коммита.
+pub async fn cancel_trade(conn: &mut PgConnection, trade_id: Uuid) -> Result<Vec<Uuid>, AppError> {
+    let items = sqlx::query!("SELECT user_id, item_id, quantity FROM trade_items WHERE trade_id = $1", trade_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    for item in items {
+        grant_item(conn, item.user_id, &item.item_id, item.quantity).await?;
+    }
+    sqlx::query!(
+        "UPDATE players p SET credits = p.credits + o.credits FROM trade_offers o WHERE o.trade_id = $1 AND p.user_id = o.user_id",
+        trade_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let participants = sqlx::query_scalar!("SELECT user_id FROM trade_offers WHERE trade_id = $1", trade_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    sqlx::query!("DELETE FROM trades WHERE id = $1", trade_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(participants)
+}
+
+/// Блокирует сделку, в которой участвует игрок, если она есть, и возвращает ее.
+pub async fn lock_player_trade(conn: &mut PgConnection, user_id: Uuid) -> Result<Option<Uuid>, AppError> {
+    let trade_id = sqlx::query_scalar!(
+        r#"
+        SELECT t.id FROM trades t
+        JOIN trade_offers o ON o.trade_id = t.id
+        WHERE o.user_id = $1
+        FOR UPDATE OF t
+        "#,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(trade_id)
+}
+
+/// Отменяет сделку игрока, если она есть. Для ухода из комнаты и отключения.
+pub async fn cancel_player_trade(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Option<(Uuid, Vec<Uuid>)>, AppError> {
+    let Some(trade_id) = lock_player_trade(conn, user_id).await? else {
+        return Ok(None);
+    };
+    let participants = cancel_trade(conn, trade_id).await?;
+    Ok(Some((trade_id, participants)))
+}
+
+/// Рассылает участникам текущее состояние сделки.
+pub async fn notify_trade(state: &AppState, trade: &Trade, event: &str) {
+    let message = serde_json::json!({ "type": event, "trade": trade }).to_string();
+    for user_id in trade.participants() {
+        send_to_user(state, user_id, message.clone()).await;
+    }
+}
+
+pub async fn notify_trade_cancelled(state: &AppState, trade_id: Uuid, participants: &[Uuid], reason: &str) {
+    let message = serde_json::json!({ "type": "trade_cancelled", "trade_id": trade_id, "reason": reason }).to_string();
+    for user_id in participants {
+        send_to_user(state, *user_id, message.clone()).await;
+    }
+}
+
+/// Отменяет сделку игрока в отдельной транзакции и уведомляет участников.
+pub async fn cancel_player_trade_now(state: &AppState, user_id: Uuid, reason: &str) -> Result<(), AppError> {
+    let mut tx = state.pool.begin().await?;
+    let cancelled = cancel_player_trade(&mut tx, user_id).await?;
+    tx.commit().await?;
+
+    if let Some((trade_id, participants)) = cancelled {
+        notify_trade_cancelled(state, trade_id, &participants, reason).await;
+    }
+    Ok(())
+}
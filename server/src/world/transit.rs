@@ -0,0 +1,426 @@
+// /server/src/world/transit.rs
+
+// Перемещение между локациями.
+//
+// По связи с нулевым временем в пути игрок переходит сразу. Иначе он отправляется
+// в путь: выходит из комнаты исходной локации, но в целевую еще не входит и не слышит
+// ни ту, ни другую. В базе он до прибытия числится в исходной локации, а запись
+// в player_transits говорит, куда и когда он прибудет. Прибытие выполняет таймер
+// на сервере, а все, что он пропустил (в том числе после перезапуска), раз в секунду
+// подбирает фоновый сборщик.
+//
+// Прибытие и отмена забирают запись о пути DELETE ... RETURNING, поэтому из двух
+// одновременных попыток (таймер и отмена, два таймера) срабатывает ровно одна.
+
+use super::{
+    acl::acl_denial,
+    capacity::{admit, enqueue, process_queue},
+    discovery::{known_links, remember_links},
+    grants::consume_passes,
+    instances::{enter_instance, release_instance},
+    intrusion::{movement_cooldown, record_intrusion},
+    inventory::fetch_player,
+    links::link_denial,
+    quests::{emit_quest_events, QuestEvent},
+    rules::RuleContext,
+    spawn::ensure_player_location,
+    trade::{cancel_player_trade, notify_trade_cancelled, CANCELLED_LEFT_ROOM},
+    visits::record_visit,
+    zones::{fetch_location, lockdown_denial, policy_denial},
+};
+use crate::{
+    auth::Claims,
+    error::AppError,
+    models::{
+        link::LocationLink,
+        location::Location,
+        player::{Player, PlayerTransit},
+        user::UserRole,
+    },
+    state::AppState,
+    ws::{
+        utils::{change_room, connected_user_ids, enter_transit, send_to_user},
+        RoomKey,
+    },
+};
+use chrono::Utc;
+use sqlx::PgExecutor;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Как часто фоновая задача завершает переходы, пропущенные таймерами.
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Незавершенный переход игрока, если он сейчас в пути.
+pub async fn active_transit<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+) -> Result<Option<PlayerTransit>, AppError> {
+    let transit = sqlx::query_as!(PlayerTransit, "SELECT * FROM player_transits WHERE user_id = $1", user_id)
+        .fetch_optional(executor)
+        .await?;
+
+    Ok(transit)
+}
+
+/// Комната, в которой игрок числится по базе (для игрока в пути - исходная).
+fn player_room(player: &Player) -> Option<RoomKey> {
+    player
+        .current_location_id
+        .map(|location_id| RoomKey::new(location_id, player.current_instance_id))
+}
+
+/// Перемещает игрока в соседнюю локацию со всеми проверками доступа. Если связь
+/// требует времени, игрок отправляется в путь и возвращается запись о переходе.
+pub async fn travel(
+    state: &AppState,
+    claims: &Claims,
+    target_location_id: Uuid,
+    queue: bool,
+) -> Result<Option<PlayerTransit>, AppError> {
+    // 1. Получаем текущее состояние игрока. Без исходной локации переход по связи
+    //    невозможно проверить, поэтому потерявший ее игрок сначала возвращается в точку появления.
+    ensure_player_location(state, claims).await?;
+    let player = fetch_player(&state.pool, claims.sub).await?;
+    if player.transit.is_some() {
+        return Err(AppError::AccessDenied("ВЫ В ПУТИ. СНАЧАЛА ОТМЕНИТЕ ТЕКУЩИЙ ПЕРЕХОД.".to_string()));
+    }
+
+    // Система безопасности могла заблокировать перемещения после попыток проникновения
+    if let Some(remaining) = movement_cooldown(&state.pool, claims.sub).await? {
+        return Err(AppError::AccessDenied(format!(
+            "ПЕРЕМЕЩЕНИЯ ЗАБЛОКИРОВАНЫ СИСТЕМОЙ БЕЗОПАСНОСТИ. ОСТАЛОСЬ {} С.",
+            remaining
+        )));
+    }
+
+    // 2. Получаем данные о целевой локации (с учетом наследования от зоны)
+    let target_location = fetch_location(&state.pool, target_location_id).await?;
+
+    // 3. Проверяем, достаточно ли у игрока прав доступа (с учетом временных допусков)
+    let ctx = RuleContext::load(&state.pool, claims.sub, claims.role).await?;
+    if !ctx.clears(target_location.id, target_location.security_level) {
+        // Попытки модераторов и выше не считаются вторжением. Сбой записи не должен
+        // превращать отказ в ошибку сервера: игрок все равно получает отказ
+        if claims.role < UserRole::Moderator
+            && let Err(e) = record_intrusion(state, claims.sub, &claims.username, &target_location, ctx.access_level).await
+        {
+            tracing::error!("Не удалось записать попытку проникновения игрока {}: {:?}", claims.sub, e);
+        }
+        return Err(AppError::Unauthorized);
+    }
+
+    if let Some(message) = policy_denial(&target_location, &ctx).or_else(|| lockdown_denial(&target_location, claims.role)) {
+        return Err(AppError::AccessDenied(message));
+    }
+    if let Some(message) = acl_denial(&state.pool, &target_location, claims.sub, claims.role).await? {
+        return Err(AppError::AccessDenied(message));
+    }
+
+    // 4. Переходить можно только по существующей связи, и ее условия должны выполняться
+    let mut route = None;
+    if let Some(source_id) = player.current_location_id {
+        // Из изолированной зоны тоже не выйти
+        let source_location = fetch_location(&state.pool, source_id).await?;
+        if let Some(message) = lockdown_denial(&source_location, claims.role) {
+            return Err(AppError::AccessDenied(message));
+        }
+
+        let links = sqlx::query_as!(
+            LocationLink,
+            r#"
+            SELECT id, source_location_id, target_location_id, link_text, required_access_level, access_condition,
+                   denial_message, created_at, visibility AS "visibility: _", search_chance, reveal_condition,
+                   travel_secs
+            FROM location_links WHERE source_location_id = $1 AND target_location_id = $2
+            "#,
+            source_id,
+            target_location_id
+        )
+        .fetch_all(&state.pool)
+        .await?;
+
+        // Необнаруженные скрытые переходы для игрока не существуют
+        let links = known_links(&state.pool, claims.sub, links, &ctx).await?;
+        if links.is_empty() {
+            return Err(AppError::AccessDenied("ПЕРЕХОД В ЭТУ ЛОКАЦИЮ ОТСУТСТВУЕТ.".to_string()));
+        }
+
+        // Между двумя локациями может быть несколько связей: достаточно одной открытой,
+        // и из открытых выбирается самая быстрая
+        let denials: Vec<Option<String>> = links.iter().map(|link| link_denial(link, &ctx)).collect();
+        if denials.iter().all(Option::is_some) {
+            let message = denials.into_iter().flatten().next().unwrap_or_default();
+            return Err(AppError::AccessDenied(message));
+        }
+        route = links
+            .iter()
+            .zip(&denials)
+            .filter(|(_, denial)| denial.is_none())
+            .map(|(link, _)| (link.id, link.travel_secs))
+            .min_by_key(|(_, travel_secs)| *travel_secs);
+        // Скрытый переход, открытый условием обнаружения, запоминается, когда по нему идут
+        remember_links(&state.pool, claims.sub, &links).await?;
+    }
+
+    // 5. Долгий переход: игрок отправляется в путь и прибудет по таймеру
+    if let Some((link_id, travel_secs)) = route.filter(|(_, travel_secs)| *travel_secs > 0) {
+        let transit = start_transit(state, claims.sub, &claims.username, &player, link_id, travel_secs, queue).await?;
+        return Ok(Some(transit));
+    }
+
+    enter_location(state, claims.sub, &claims.username, &player, &target_location, queue).await?;
+    Ok(None)
+}
+
+/// Вводит игрока в локацию: занимает место (или очередь), отменяет сделку, выбирает копию
+/// инстанцированной локации, переносит WS-клиента и освобождает место в покинутой.
+/// Все проверки доступа к этому моменту уже пройдены.
+async fn enter_location(
+    state: &AppState,
+    user_id: Uuid,
+    username: &str,
+    player: &Player,
+    target: &Location,
+    queue: bool,
+) -> Result<(), AppError> {
+    let source = match player.current_location_id {
+        Some(source_id) => Some(fetch_location(&state.pool, source_id).await?),
+        None => None,
+    };
+
+    let online = connected_user_ids(state).await;
+    let mut tx = state.pool.begin().await?;
+    if let Some(capacity) = target.capacity
+        && !admit(&mut tx, user_id, target.id, capacity, &online).await?
+    {
+        if !queue {
+            return Err(AppError::AccessDenied("ЛОКАЦИЯ ЗАПОЛНЕНА.".to_string()));
+        }
+        let position = enqueue(&mut tx, user_id, target.id).await?;
+        tx.commit().await?;
+        return Err(AppError::AccessDenied(format!(
+            "ЛОКАЦИЯ ЗАПОЛНЕНА. ВЫ В ОЧЕРЕДИ НА ВХОД: ПОЗИЦИЯ {}.",
+            position
+        )));
+    }
+    // Уход из комнаты отменяет незавершенную сделку, залог возвращается владельцам
+    let cancelled_trade = cancel_player_trade(&mut tx, user_id).await?;
+    // Для инстанцированной локации игрок попадает в копию своей группы (или личную)
+    let instance_id = if target.instanced {
+        Some(enter_instance(&mut tx, user_id, target.id).await?)
+    } else {
+        None
+    };
+    sqlx::query!(
+        "UPDATE players SET current_location_id = $1, current_instance_id = $2 WHERE user_id = $3",
+        target.id,
+        instance_id,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if let Some(old_instance_id) = player.current_instance_id.filter(|id| Some(*id) != instance_id) {
+        release_instance(&mut tx, old_instance_id).await?;
+    }
+    // Разовый пропуск в покинутую локацию сгорает
+    if let Some(source_id) = player.current_location_id.filter(|id| *id != target.id) {
+        consume_passes(&mut tx, user_id, source_id).await?;
+    }
+    record_visit(&mut *tx, user_id, target.id).await?;
+    tx.commit().await?;
+
+    if let Some((trade_id, participants)) = cancelled_trade {
+        notify_trade_cancelled(state, trade_id, &participants, CANCELLED_LEFT_ROOM).await;
+    }
+
+    // Единая функция обновления состояния WebSocket сама разошлет уведомления о выходе и входе
+    change_room(state, user_id, username, player_room(player), RoomKey::new(target.id, instance_id)).await;
+
+    emit_quest_events(state, user_id, vec![QuestEvent::Visited(target.id)]).await;
+
+    // Игрок освободил место: следующий в очереди получает бронь
+    if let Some(source) = source.filter(|source| source.capacity.is_some()) {
+        process_queue(state, source.id).await?;
+    }
+    Ok(())
+}
+
+/// Отправляет игрока в путь по связи `link_id`. Место в целевой локации занимается
+/// только по прибытии.
+async fn start_transit(
+    state: &AppState,
+    user_id: Uuid,
+    username: &str,
+    player: &Player,
+    link_id: Uuid,
+    travel_secs: i32,
+    queue: bool,
+) -> Result<PlayerTransit, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let transit = sqlx::query_as!(
+        PlayerTransit,
+        r#"
+        INSERT INTO player_transits (user_id, link_id, source_location_id, target_location_id, queue, arrives_at)
+        SELECT $1, id, source_location_id, target_location_id, $3, NOW() + make_interval(secs => $4)
+        FROM location_links WHERE id = $2
+        ON CONFLICT (user_id) DO NOTHING
+        RETURNING *
+        "#,
+        user_id,
+        link_id,
+        queue,
+        f64::from(travel_secs)
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::AccessDenied("ВЫ УЖЕ В ПУТИ.".to_string()))?;
+    // Игрок покидает комнату уже сейчас, поэтому и сделка отменяется сейчас
+    let cancelled_trade = cancel_player_trade(&mut tx, user_id).await?;
+    tx.commit().await?;
+
+    if let Some((trade_id, participants)) = cancelled_trade {
+        notify_trade_cancelled(state, trade_id, &participants, CANCELLED_LEFT_ROOM).await;
+    }
+    enter_transit(state, user_id, username, player_room(player)).await;
+    let message = serde_json::json!({ "type": "transit_started", "transit": &transit });
+    send_to_user(state, user_id, message.to_string()).await;
+    schedule_arrival(state.clone(), &transit);
+
+    tracing::info!("{} в пути: {} -> {}", username, transit.source_location_id, transit.target_location_id);
+    Ok(transit)
+}
+
+/// Запускает таймер прибытия.
+fn schedule_arrival(state: AppState, transit: &PlayerTransit) {
+    let user_id = transit.user_id;
+    let delay = (transit.arrives_at - Utc::now()).to_std().unwrap_or_default();
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        if let Err(e) = arrive(&state, user_id).await {
+            tracing::error!("Не удалось завершить переход игрока {}: {:?}", user_id, e);
+        }
+    });
+}
+
+/// Завершает переход, если его время пришло. По дороге игрок мог лишиться допуска,
+/// а цель - заполниться или закрыться; тогда он возвращается туда, откуда вышел.
+async fn arrive(state: &AppState, user_id: Uuid) -> Result<(), AppError> {
+    let Some(transit) = sqlx::query_as!(
+        PlayerTransit,
+        "DELETE FROM player_transits WHERE user_id = $1 AND arrives_at <= NOW() RETURNING *",
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    else {
+        // Переход отменен, уже завершен или таймер сработал раньше часов БД - тогда его завершит сборщик
+        return Ok(());
+    };
+
+    let user = sqlx::query!(r#"SELECT username, role AS "role: UserRole" FROM users WHERE id = $1"#, user_id)
+        .fetch_one(&state.pool)
+        .await?;
+    let player = fetch_player(&state.pool, user_id).await?;
+
+    let result = match fetch_location(&state.pool, transit.target_location_id).await {
+        Ok(target) => match arrival_denial(state, user_id, user.role, &target).await {
+            Ok(Some(message)) => Err(AppError::AccessDenied(message)),
+            Ok(None) => enter_location(state, user_id, &user.username, &player, &target, transit.queue).await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+
+    let message = match result {
+        Ok(()) => serde_json::json!({ "type": "transit_completed", "location_id": transit.target_location_id }),
+        Err(e) => {
+            let reason = match e {
+                AppError::AccessDenied(message) => message,
+                e => {
+                    tracing::error!("Переход игрока {} прерван ошибкой: {:?}", user_id, e);
+                    "ПЕРЕХОД ПРЕРВАН.".to_string()
+                }
+            };
+            if let Some(room) = player_room(&player) {
+                change_room(state, user_id, &user.username, None, room).await;
+            }
+            serde_json::json!({
+                "type": "transit_failed",
+                "location_id": transit.target_location_id,
+                "message": reason,
+            })
+        }
+    };
+    send_to_user(state, user_id, message.to_string()).await;
+    Ok(())
+}
+
+/// Доступ, проверенный при отправлении, мог истечь в пути.
+async fn arrival_denial(
+    state: &AppState,
+    user_id: Uuid,
+    role: UserRole,
+    target: &Location,
+) -> Result<Option<String>, AppError> {
+    let ctx = RuleContext::load(&state.pool, user_id, role).await?;
+    if !ctx.clears(target.id, target.security_level) {
+        return Ok(Some("ДОПУСК К ЛОКАЦИИ УТРАЧЕН В ПУТИ.".to_string()));
+    }
+    if let Some(message) = policy_denial(target, &ctx).or_else(|| lockdown_denial(target, role)) {
+        return Ok(Some(message));
+    }
+    acl_denial(&state.pool, target, user_id, role).await
+}
+
+/// Отменяет переход: игрок возвращается в комнату, из которой вышел.
+pub async fn cancel_transit(state: &AppState, user_id: Uuid, username: &str) -> Result<(), AppError> {
+    let transit = sqlx::query_as!(
+        PlayerTransit,
+        "DELETE FROM player_transits WHERE user_id = $1 RETURNING *",
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let player = fetch_player(&state.pool, user_id).await?;
+    if let Some(room) = player_room(&player) {
+        change_room(state, user_id, username, None, room).await;
+    }
+    let message = serde_json::json!({ "type": "transit_cancelled", "location_id": transit.source_location_id });
+    send_to_user(state, user_id, message.to_string()).await;
+
+    tracing::info!("{} отменил переход в {}", username, transit.target_location_id);
+    Ok(())
+}
+
+/// Фоновая задача: завершает переходы, которые должны были закончиться, но не закончились.
+/// Таймер мог сработать раньше часов БД или упасть с ошибкой, а после перезапуска сервера
+/// таймеров нет вовсе; при старте первым проходом прибывают все опоздавшие.
+pub fn spawn_transit_sweeper(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = sweep_transits(&state).await {
+                tracing::error!("Не удалось обработать завершившиеся переходы: {:?}", e);
+            }
+        }
+    });
+}
+
+async fn sweep_transits(state: &AppState) -> Result<(), AppError> {
+    let user_ids = sqlx::query_scalar!(
+        "SELECT user_id FROM player_transits WHERE arrives_at <= NOW() ORDER BY arrives_at"
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    for user_id in user_ids {
+        if let Err(e) = arrive(state, user_id).await {
+            tracing::error!("Не удалось завершить переход игрока {}: {:?}", user_id, e);
+        }
+    }
+    Ok(())
+}
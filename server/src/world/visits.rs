@@ -0,0 +1,43 @@
+// /var/www/structure/server/src/world/visits.rs
+
+use crate::error::AppError;
+use sqlx::PgExecutor;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Отмечает посещение локации: первое посещение создает запись,
+/// повторные обновляют время последнего визита и счетчик.
+pub async fn record_visit<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+    location_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        INSERT INTO player_visits (user_id, location_id) VALUES ($1, $2)
+        ON CONFLICT (user_id, location_id)
+        DO UPDATE SET last_visited_at = NOW(), visit_count = player_visits.visit_count + 1
+        "#,
+        user_id,
+        location_id
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Множество локаций, в которых игрок когда-либо бывал.
+pub async fn visited_location_ids<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: Uuid,
+) -> Result<HashSet<Uuid>, AppError> {
+    let ids = sqlx::query_scalar!(
+        "SELECT location_id FROM player_visits WHERE user_id = $1",
+        user_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(ids.into_iter().collect())
+}
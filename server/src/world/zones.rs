@@ -0,0 +1,109 @@
+// /var/www/structure/server/src/world/zones.rs
+
+use super::rules::{self, RuleContext};
+use crate::{
+    error::AppError,
+    models::{location::Location, user::UserRole, zone::ZoneKind},
+};
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+/// Загружает локации с примененным наследованием: незаданные у локации значения
+/// берутся у ее зоны, затем у сектора. Иерархия фиксированной глубины, поэтому
+/// хватает двух JOIN'ов.
+pub async fn fetch_locations<'e>(
+    executor: impl PgExecutor<'e>,
+    ids: &[Uuid],
+) -> Result<Vec<Location>, AppError> {
+    let locations = sqlx::query_as!(
+        Location,
+        r#"
+        SELECT l.id, l.name, l.description, l.image_url, l.image_asset_id,
+               COALESCE(l.security_level, z.security_level, s.security_level, 0) AS "security_level!",
+               l.creator_id, l.created_at, l.updated_at, l.zone_id,
+               COALESCE(l.ambient_description, z.ambient_description, s.ambient_description) AS ambient_description,
+               COALESCE(l.access_condition, z.access_condition, s.access_condition) AS access_condition,
+               (z.locked_down OR COALESCE(s.locked_down, FALSE)) AS "locked_down!",
+               l.instanced, l.capacity, l.redaction_leaks, l.owner_id, l.entry_policy AS "entry_policy: _",
+               l.personal, l.public_hub
+        FROM locations l
+        JOIN zones z ON z.id = l.zone_id
+        LEFT JOIN zones s ON s.id = z.parent_id
+        WHERE l.id = ANY($1)
+        "#,
+        ids
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(locations)
+}
+
+pub async fn fetch_location<'e>(executor: impl PgExecutor<'e>, id: Uuid) -> Result<Location, AppError> {
+    fetch_locations(executor, &[id])
+        .await?
+        .into_iter()
+        .next()
+        .ok_or(AppError::NotFound)
+}
+
+/// Локации живут только в зонах, не напрямую в секторах.
+pub async fn validate_location_zone<'e>(executor: impl PgExecutor<'e>, zone_id: Uuid) -> Result<(), AppError> {
+    let kind = sqlx::query_scalar!(r#"SELECT kind AS "kind: ZoneKind" FROM zones WHERE id = $1"#, zone_id)
+        .fetch_optional(executor)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Unknown zone".to_string()))?;
+    if kind != ZoneKind::Zone {
+        return Err(AppError::BadRequest("Locations must belong to a zone, not a sector".to_string()));
+    }
+    Ok(())
+}
+
+/// Причина, по которой игрок не видит содержимое локации: недостаточный
+/// уровень доступа или политика зоны. `None` - локация открыта.
+/// Временный допуск к самой локации заменяет уровень, но не политику.
+pub fn visibility_denial(location: &Location, ctx: &RuleContext) -> Option<String> {
+    if !ctx.clears(location.id, location.security_level) {
+        return Some(format!(
+            "ТРЕБУЕТСЯ УРОВЕНЬ ДОСТУПА: {}. ВАШ УРОВЕНЬ: {}.",
+            location.security_level, ctx.access_level
+        ));
+    }
+    policy_denial(location, ctx)
+}
+
+/// Проверяет политику доступа локации (собственную или унаследованную от зоны).
+/// Уровень доступа проверяется отдельно.
+pub fn policy_denial(location: &Location, ctx: &RuleContext) -> Option<String> {
+    let source = location.access_condition.as_deref()?;
+    let passed = match rules::parse(source) {
+        Ok(condition) => condition.evaluate(ctx),
+        Err(e) => {
+            tracing::error!("Некорректная политика доступа у локации {}: {}", location.id, e);
+            false
+        }
+    };
+    (!passed).then(|| "ДОСТУП ОГРАНИЧЕН ПОЛИТИКОЙ БЕЗОПАСНОСТИ ЗОНЫ.".to_string())
+}
+
+/// В изолированную зону нельзя ни войти, ни выйти из нее; на модераторов и выше это не действует.
+pub fn lockdown_denial(location: &Location, role: UserRole) -> Option<String> {
+    (location.locked_down && role < UserRole::Moderator)
+        .then(|| "ЗОНА ИЗОЛИРОВАНА: ПЕРЕМЕЩЕНИЕ ЗАПРЕЩЕНО.".to_string())
+}
+
+/// Все локации зоны или сектора (для сектора - включая локации дочерних зон).
+pub async fn zone_location_ids<'e>(executor: impl PgExecutor<'e>, zone_id: Uuid) -> Result<Vec<Uuid>, AppError> {
+    let ids = sqlx::query_scalar!(
+        r#"
+        SELECT l.id FROM locations l
+        JOIN zones z ON z.id = l.zone_id
+        WHERE z.id = $1 OR z.parent_id = $1
+        "#,
+        zone_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(ids)
+}
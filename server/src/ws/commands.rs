@@ -0,0 +1,507 @@
+// /server/src/ws/commands.rs
+
+// Текстовые команды по WebSocket.
+//
+// Каждое сообщение клиента, кроме служебного `__ping__`, - строка команды: имя и аргументы.
+// Имя можно сократить до любого однозначного префикса или заменить псевдонимом (`l` - look).
+// Аргументы разделяются пробелами, аргумент с пробелами берется в кавычки; у `say` и
+// последнего аргумента `whisper` кавычки не нужны - текстом считается весь остаток строки.
+//
+// Ответ всегда один и структурированный: `command_result` с `ok` и либо `result`, либо `error`,
+// поэтому играть простым текстом может любой клиент. Команды выше роли игрока для него
+// не существуют: их нет в `help`, и они не распознаются.
+
+use super::{
+    state::RoomKey,
+    utils::{broadcast_message, send_to_user},
+};
+use crate::{
+    auth::Claims,
+    error::AppError,
+    handlers::location_handler::describe_location,
+    models::user::{PublicUser, UserRole},
+    state::AppState,
+    world::{
+        i18n::preferred_language,
+        inventory::fetch_player,
+        npcs::{answer, location_npcs, talk},
+        room_items::load_room_items,
+        spawn::ensure_player_location,
+        transit::{cancel_transit, travel},
+    },
+};
+use axum::extract::ws::Message;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+const MAX_TEXT_CHARS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CommandKind {
+    Look,
+    Go,
+    Stop,
+    Say,
+    Whisper,
+    Talk,
+    Answer,
+    Inventory,
+    Who,
+    Help,
+    Announce,
+}
+
+#[derive(Serialize)]
+struct CommandSpec {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    usage: &'static str,
+    description: &'static str,
+    #[serde(skip)]
+    min_role: UserRole,
+    #[serde(skip)]
+    kind: CommandKind,
+}
+
+const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "look",
+        aliases: &["l"],
+        usage: "look",
+        description: "Осмотреть текущую локацию: описание, переходы, игроки и предметы.",
+        min_role: UserRole::User,
+        kind: CommandKind::Look,
+    },
+    CommandSpec {
+        name: "go",
+        aliases: &["move"],
+        usage: "go <номер или название перехода> [queue]",
+        description: "Перейти в соседнюю локацию. С queue - встать в очередь, если она заполнена.",
+        min_role: UserRole::User,
+        kind: CommandKind::Go,
+    },
+    CommandSpec {
+        name: "stop",
+        aliases: &[],
+        usage: "stop",
+        description: "Отменить переход, пока вы в пути.",
+        min_role: UserRole::User,
+        kind: CommandKind::Stop,
+    },
+    CommandSpec {
+        name: "say",
+        aliases: &[],
+        usage: "say <текст>",
+        description: "Сказать всем в комнате.",
+        min_role: UserRole::User,
+        kind: CommandKind::Say,
+    },
+    CommandSpec {
+        name: "whisper",
+        aliases: &["tell", "w"],
+        usage: "whisper <игрок> <текст>",
+        description: "Шепнуть игроку, где бы он ни был.",
+        min_role: UserRole::User,
+        kind: CommandKind::Whisper,
+    },
+    CommandSpec {
+        name: "talk",
+        aliases: &[],
+        usage: "talk [персонаж]",
+        description: "Заговорить с персонажем в комнате или продолжить разговор. Без аргумента - кто здесь есть.",
+        min_role: UserRole::User,
+        kind: CommandKind::Talk,
+    },
+    CommandSpec {
+        name: "answer",
+        aliases: &["a"],
+        usage: "answer <номер>",
+        description: "Выбрать ответ в текущем разговоре.",
+        min_role: UserRole::User,
+        kind: CommandKind::Answer,
+    },
+    CommandSpec {
+        name: "inventory",
+        aliases: &["i", "inv"],
+        usage: "inventory",
+        description: "Показать инвентарь и кредиты.",
+        min_role: UserRole::User,
+        kind: CommandKind::Inventory,
+    },
+    CommandSpec {
+        name: "who",
+        aliases: &[],
+        usage: "who",
+        description: "Кто сейчас в сети.",
+        min_role: UserRole::User,
+        kind: CommandKind::Who,
+    },
+    CommandSpec {
+        name: "help",
+        aliases: &["?"],
+        usage: "help [команда]",
+        description: "Список команд или справка по одной.",
+        min_role: UserRole::User,
+        kind: CommandKind::Help,
+    },
+    CommandSpec {
+        name: "announce",
+        aliases: &[],
+        usage: "announce <текст>",
+        description: "Объявление всем подключенным игрокам.",
+        min_role: UserRole::Moderator,
+        kind: CommandKind::Announce,
+    },
+];
+
+/// Аргументы команды: разбираются по одному по мере надобности.
+struct Args<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Args<'a> {
+    fn new(line: &'a str) -> Self {
+        Self { rest: line.trim_start() }
+    }
+
+    /// Следующий аргумент: слово или строка в двойных кавычках.
+    fn next(&mut self) -> Option<String> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let (arg, rest) = match self.rest.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => self.rest.split_once(char::is_whitespace).unwrap_or((self.rest, "")),
+        };
+        self.rest = rest.trim_start();
+        Some(arg.to_string())
+    }
+
+    /// Весь остаток строки как текст.
+    fn text(&self) -> Result<&'a str, AppError> {
+        let text = self.rest.trim();
+        if text.is_empty() {
+            return Err(AppError::BadRequest("НУЖЕН ТЕКСТ.".to_string()));
+        }
+        if text.chars().count() > MAX_TEXT_CHARS {
+            return Err(AppError::BadRequest(format!("ТЕКСТ ДЛИННЕЕ {} СИМВОЛОВ.", MAX_TEXT_CHARS)));
+        }
+        Ok(text)
+    }
+}
+
+/// Находит команду по имени, псевдониму или однозначному префиксу имени среди доступных роли.
+fn resolve(word: &str, role: UserRole) -> Result<&'static CommandSpec, AppError> {
+    let word = word.to_lowercase();
+    let available = || COMMANDS.iter().filter(|spec| role >= spec.min_role);
+
+    if let Some(spec) = available().find(|spec| spec.name == word || spec.aliases.contains(&word.as_str())) {
+        return Ok(spec);
+    }
+    let candidates: Vec<&CommandSpec> = available().filter(|spec| spec.name.starts_with(&word)).collect();
+    match candidates.as_slice() {
+        [spec] => Ok(spec),
+        [] => Err(AppError::BadRequest("НЕИЗВЕСТНАЯ КОМАНДА. ВВЕДИТЕ help.".to_string())),
+        _ => {
+            let names: Vec<&str> = candidates.iter().map(|spec| spec.name).collect();
+            Err(AppError::BadRequest(format!("НЕОДНОЗНАЧНАЯ КОМАНДА: {}.", names.join(", "))))
+        }
+    }
+}
+
+/// Текст ошибки для игрока. Игровые отказы передаются как есть, внутренние ошибки - только в лог.
+fn error_message(error: AppError) -> String {
+    match error {
+        AppError::AccessDenied(message) | AppError::BadRequest(message) => message,
+        AppError::NotFound => "НЕ НАЙДЕНО.".to_string(),
+        AppError::Unauthorized | AppError::Forbidden => "ДОСТУП ЗАПРЕЩЕН.".to_string(),
+        e => {
+            tracing::error!("Ошибка при выполнении команды: {:?}", e);
+            "ВНУТРЕННЯЯ ОШИБКА.".to_string()
+        }
+    }
+}
+
+/// Выполняет строку команды и возвращает ответ для клиента.
+pub async fn execute(state: &AppState, claims: &Claims, line: &str) -> String {
+    let mut args = Args::new(line);
+    let Some(word) = args.next() else {
+        return json!({ "type": "command_result", "command": null, "ok": false, "error": "ПУСТАЯ КОМАНДА." })
+            .to_string();
+    };
+
+    let (command, result) = match resolve(&word, claims.role) {
+        Ok(spec) => (Some(spec.name), run(state, claims, spec.kind, args).await),
+        Err(e) => (None, Err(e)),
+    };
+    let reply = match result {
+        Ok(result) => json!({ "type": "command_result", "command": command, "ok": true, "result": result }),
+        Err(e) => json!({ "type": "command_result", "command": command, "ok": false, "error": error_message(e) }),
+    };
+    reply.to_string()
+}
+
+async fn run(state: &AppState, claims: &Claims, kind: CommandKind, mut args: Args<'_>) -> Result<Value, AppError> {
+    match kind {
+        CommandKind::Look => look(state, claims).await,
+        CommandKind::Go => {
+            let exit = args.next().ok_or_else(|| AppError::BadRequest("КУДА ИДТИ?".to_string()))?;
+            let queue = match args.next() {
+                None => false,
+                Some(word) if word.eq_ignore_ascii_case("queue") => true,
+                Some(_) => return Err(AppError::BadRequest("ИСПОЛЬЗОВАНИЕ: go <переход> [queue].".to_string())),
+            };
+            go(state, claims, &exit, queue).await
+        }
+        CommandKind::Stop => {
+            cancel_transit(state, claims.sub, &claims.username).await.map_err(|e| match e {
+                AppError::NotFound => AppError::AccessDenied("ВЫ НЕ В ПУТИ.".to_string()),
+                e => e,
+            })?;
+            Ok(json!({ "cancelled": true }))
+        }
+        CommandKind::Say => say(state, claims, args.text()?).await,
+        CommandKind::Whisper => {
+            let username = args.next().ok_or_else(|| AppError::BadRequest("КОМУ ШЕПНУТЬ?".to_string()))?;
+            whisper(state, claims, &username, args.text()?).await
+        }
+        CommandKind::Talk => {
+            let room = current_room(state, claims).await?;
+            match args.next() {
+                Some(npc) => Ok(json!(talk(state, claims, room, &npc).await?)),
+                None => Ok(json!({ "npcs": location_npcs(&state.pool, room.location_id).await? })),
+            }
+        }
+        CommandKind::Answer => {
+            let number = args
+                .next()
+                .and_then(|number| number.parse::<usize>().ok())
+                .ok_or_else(|| AppError::BadRequest("УКАЖИТЕ НОМЕР ОТВЕТА.".to_string()))?;
+            let room = current_room(state, claims).await?;
+            Ok(json!(answer(state, claims, room, number).await?))
+        }
+        CommandKind::Inventory => {
+            let player = fetch_player(&state.pool, claims.sub).await?;
+            Ok(json!({ "credits": player.credits, "items": player.inventory }))
+        }
+        CommandKind::Who => who(state).await,
+        CommandKind::Help => help(claims.role, args.next()),
+        CommandKind::Announce => announce(state, claims, args.text()?).await,
+    }
+}
+
+/// Комната игрока, если он не в пути.
+async fn current_room(state: &AppState, claims: &Claims) -> Result<RoomKey, AppError> {
+    let (location_id, instance_id) = ensure_player_location(state, claims).await?;
+    let player = fetch_player(&state.pool, claims.sub).await?;
+    if player.transit.is_some() {
+        return Err(AppError::AccessDenied("ВЫ В ПУТИ.".to_string()));
+    }
+    Ok(RoomKey::new(location_id, instance_id))
+}
+
+/// Заголовков Accept-Language у WebSocket нет: только настройка игрока или язык по умолчанию.
+async fn player_language(state: &AppState, claims: &Claims) -> Result<String, AppError> {
+    let language = preferred_language(&state.pool, claims.sub, &state.config).await?;
+    Ok(language.unwrap_or_else(|| state.config.default_language.clone()))
+}
+
+async fn look(state: &AppState, claims: &Claims) -> Result<Value, AppError> {
+    let player = fetch_player(&state.pool, claims.sub).await?;
+    if let Some(transit) = player.transit {
+        return Ok(json!({ "transit": transit }));
+    }
+    let room = current_room(state, claims).await?;
+
+    let language = player_language(state, claims).await?;
+    let view = describe_location(state, claims, &language, room.location_id).await?;
+    let items = load_room_items(&state.pool, room.location_id, room.instance_id).await?;
+    let npcs = location_npcs(&state.pool, room.location_id).await?;
+    let users: Vec<PublicUser> = {
+        let rooms = state.ws_state.rooms.lock().await;
+        rooms
+            .get(&room)
+            .map(|clients| {
+                clients
+                    .values()
+                    .map(|(user, _)| user.clone())
+                    .filter(|user| user.id != claims.sub)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    Ok(json!({ "location": view.location, "links": view.links, "users": users, "npcs": npcs, "items": items }))
+}
+
+/// Переход выбирается по номеру из `look`, по названию или по однозначному началу названия.
+async fn go(state: &AppState, claims: &Claims, exit: &str, queue: bool) -> Result<Value, AppError> {
+    let room = current_room(state, claims).await?;
+    let language = player_language(state, claims).await?;
+    let links = describe_location(state, claims, &language, room.location_id).await?.links;
+
+    let wanted = exit.to_lowercase();
+    let link = match exit.parse::<usize>() {
+        Ok(number) => number.checked_sub(1).and_then(|index| links.get(index)),
+        Err(_) => match links.iter().find(|link| link.link_text.to_lowercase() == wanted) {
+            Some(link) => Some(link),
+            None => {
+                let candidates: Vec<_> =
+                    links.iter().filter(|link| link.link_text.to_lowercase().starts_with(&wanted)).collect();
+                if candidates.len() > 1 {
+                    let names: Vec<&str> = candidates.iter().map(|link| link.link_text.as_str()).collect();
+                    return Err(AppError::BadRequest(format!("НЕОДНОЗНАЧНЫЙ ПЕРЕХОД: {}.", names.join(", "))));
+                }
+                candidates.into_iter().next()
+            }
+        },
+    };
+    let link = link.ok_or_else(|| AppError::AccessDenied("ТАКОГО ПЕРЕХОДА НЕТ.".to_string()))?;
+
+    match travel(state, claims, link.target_location_id, queue).await? {
+        Some(transit) => Ok(json!({ "transit": transit })),
+        None => Ok(json!({ "location_id": link.target_location_id })),
+    }
+}
+
+async fn say(state: &AppState, claims: &Claims, text: &str) -> Result<Value, AppError> {
+    let room = current_room(state, claims).await?;
+    let message = json!({ "type": "say", "user_id": claims.sub, "username": claims.username, "text": text });
+
+    let rooms = state.ws_state.rooms.lock().await;
+    if let Some(clients) = rooms.get(&room) {
+        broadcast_message(clients, message.to_string(), claims.sub);
+    }
+    Ok(json!({ "text": text }))
+}
+
+async fn whisper(state: &AppState, claims: &Claims, username: &str, text: &str) -> Result<Value, AppError> {
+    let user_id = sqlx::query_scalar!("SELECT id FROM users WHERE LOWER(username) = LOWER($1)", username)
+        .fetch_optional(&state.pool)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("ТАКОГО ИГРОКА НЕТ.".to_string()))?;
+    if user_id == claims.sub {
+        return Err(AppError::BadRequest("ШЕПТАТЬ САМОМУ СЕБЕ БЕССМЫСЛЕННО.".to_string()));
+    }
+
+    let message = json!({ "type": "whisper", "user_id": claims.sub, "username": claims.username, "text": text });
+    if !send_to_user(state, user_id, message.to_string()).await {
+        return Err(AppError::AccessDenied("ИГРОК НЕ В СЕТИ.".to_string()));
+    }
+    Ok(json!({ "user_id": user_id, "text": text }))
+}
+
+/// Все подключенные игроки, включая тех, кто в пути.
+async fn online_users(state: &AppState) -> Vec<PublicUser> {
+    let rooms = state.ws_state.rooms.lock().await;
+    let transit = state.ws_state.transit.lock().await;
+    let mut users: Vec<PublicUser> =
+        rooms.values().chain([&*transit]).flat_map(|room| room.values()).map(|(user, _)| user.clone()).collect();
+    users.sort_by(|a, b| a.username.cmp(&b.username));
+    users
+}
+
+async fn who(state: &AppState) -> Result<Value, AppError> {
+    let users = online_users(state).await;
+    Ok(json!({ "count": users.len(), "users": users }))
+}
+
+fn help(role: UserRole, command: Option<String>) -> Result<Value, AppError> {
+    match command {
+        Some(word) => Ok(json!({ "commands": [resolve(&word, role)?] })),
+        None => {
+            let commands: Vec<&CommandSpec> = COMMANDS.iter().filter(|spec| role >= spec.min_role).collect();
+            Ok(json!({ "commands": commands }))
+        }
+    }
+}
+
+async fn announce(state: &AppState, claims: &Claims, text: &str) -> Result<Value, AppError> {
+    let message = json!({ "type": "announcement", "username": claims.username, "text": text }).to_string();
+    let rooms = state.ws_state.rooms.lock().await;
+    let transit = state.ws_state.transit.lock().await;
+    let mut delivered = 0;
+    for (_, tx) in rooms.values().chain([&*transit]).flat_map(|room| room.values()) {
+        if tx.send(Message::Text(message.clone())).is_ok() {
+            delivered += 1;
+        }
+    }
+
+    tracing::info!("{} сделал объявление для {} игроков", claims.username, delivered);
+    Ok(json!({ "delivered": delivered }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_args(line: &str) -> Vec<String> {
+        let mut args = Args::new(line);
+        std::iter::from_fn(|| args.next()).collect()
+    }
+
+    fn resolved(word: &str, role: UserRole) -> Option<&'static str> {
+        resolve(word, role).ok().map(|spec| spec.name)
+    }
+
+    #[test]
+    fn args_split_on_whitespace_and_quotes() {
+        assert_eq!(all_args("  go   north  "), ["go", "north"]);
+        assert_eq!(all_args(r#"go "north gate" queue"#), ["go", "north gate", "queue"]);
+        assert_eq!(all_args(r#"talk """#), ["talk", ""]);
+        assert!(all_args("").is_empty());
+    }
+
+    #[test]
+    fn unterminated_quote_takes_rest_of_line() {
+        assert_eq!(all_args(r#"go "north gate"#), ["go", "north gate"]);
+        assert_eq!(all_args(r#"go ""#), ["go", ""]);
+    }
+
+    #[test]
+    fn text_is_rest_of_line() {
+        let mut args = Args::new("whisper bob  hello  there ");
+        args.next();
+        assert_eq!(args.next().as_deref(), Some("bob"));
+        assert_eq!(args.text().unwrap(), "hello  there");
+        args.next();
+        args.next();
+        assert!(args.text().is_err());
+    }
+
+    #[test]
+    fn resolve_names_aliases_and_prefixes() {
+        assert_eq!(resolved("look", UserRole::User), Some("look"));
+        assert_eq!(resolved("LOOK", UserRole::User), Some("look"));
+        assert_eq!(resolved("inv", UserRole::User), Some("inventory"));
+        assert_eq!(resolved("whi", UserRole::User), Some("whisper"));
+        assert_eq!(resolved("wh", UserRole::User), None);
+        assert_eq!(resolved("xyzzy", UserRole::User), None);
+    }
+
+    #[test]
+    fn resolve_rejects_ambiguous_prefix() {
+        // say и stop
+        match resolve("s", UserRole::User) {
+            Err(AppError::BadRequest(message)) => assert_eq!(message, "НЕОДНОЗНАЧНАЯ КОМАНДА: stop, say."),
+            _ => panic!("expected ambiguous command"),
+        }
+    }
+
+    #[test]
+    fn resolve_prefers_alias_over_prefix() {
+        // `w` - псевдоним whisper, хотя с него начинаются и whisper, и who
+        assert_eq!(resolved("w", UserRole::User), Some("whisper"));
+        // `a` - псевдоним answer, хотя с него начинается и announce
+        assert_eq!(resolved("a", UserRole::Moderator), Some("answer"));
+    }
+
+    #[test]
+    fn resolve_hides_commands_above_role() {
+        assert_eq!(resolved("announce", UserRole::User), None);
+        assert_eq!(resolved("ann", UserRole::User), None);
+        assert_eq!(resolved("an", UserRole::User), Some("answer"));
+        assert_eq!(resolved("an", UserRole::Moderator), None);
+        assert_eq!(resolved("announce", UserRole::Moderator), Some("announce"));
+    }
+}
@@ -1,6 +1,17 @@
 // /var/www/structure/server/src/ws/handler.rs
-use super::utils::broadcast_message;
-use crate::{auth::Claims, error::AppError, state::AppState};
+use super::{commands, state::RoomKey, utils::{broadcast_message, room_npcs, room_state_message}};
+use crate::{
+    auth::Claims,
+    error::AppError,
+    state::AppState,
+    world::{
+        capacity::process_queue,
+        quests::{emit_quest_events, QuestEvent},
+        spawn::locate_player,
+        trade::{cancel_player_trade_now, CANCELLED_DISCONNECTED},
+        transit::active_transit,
+    },
+};
 use crate::models::user::PublicUser;
 use axum::{
     extract::{
@@ -53,19 +64,40 @@ async fn handle_socket(socket: WebSocket, state: AppState, claims: Claims) {
         }
     });
 
-    let player_location_id = sqlx::query_scalar!(
-        "SELECT current_location_id FROM players WHERE user_id = $1", user_id
-    ).fetch_one(&state.pool).await.unwrap_or(None).unwrap_or_else(Uuid::nil);
+    // Прибытие из пути забирает запись о пути, а затем переводит клиента через change_room под этой же
+    // блокировкой. Поэтому и проверка пути, и выбор комнаты, и вставка клиента идут под ней: иначе прибытие
+    // может завершиться между проверкой и вставкой и не найти клиента, оставив его в пути навсегда
+    let mut rooms = state.ws_state.rooms.lock().await;
 
-    {
-        let mut rooms = state.ws_state.rooms.lock().await;
-        let room = rooms.entry(player_location_id).or_default();
+    // Игрок без локации (ее удалили) возвращается в точку появления, а не в несуществующую комнату
+    let position = match locate_player(&state.pool, user_id, claims.role, &state.config).await {
+        Ok(position) => position,
+        Err(e) => {
+            tracing::error!("Не удалось определить локацию игрока {}: {:?}", user_id, e);
+            return;
+        }
+    };
+    let player_room = RoomKey::new(position.location_id, position.instance_id);
 
-        let current_users_in_room: Vec<&PublicUser> = room.values().map(|(user, _)| user).collect();
-        let room_state_msg = serde_json::to_string(&serde_json::json!({
-            "type": "room_state", "users": current_users_in_room,
-        })).unwrap_or_default();
-        let _ = tx.send(Message::Text(room_state_msg));
+    // Переподключившийся в пути игрок возвращается в путь, а не в исходную комнату:
+    // таймер прибытия продолжает идти на сервере
+    let transit = match active_transit(&state.pool, user_id).await {
+        Ok(transit) => transit,
+        Err(e) => {
+            tracing::error!("Не удалось проверить переход игрока {}: {:?}", user_id, e);
+            return;
+        }
+    };
+
+    if let Some(transit) = transit {
+        let transit_msg = serde_json::json!({ "type": "transit_started", "transit": transit }).to_string();
+        let _ = tx.send(Message::Text(transit_msg));
+        state.ws_state.transit.lock().await.insert(user_id, (user_info.clone(), tx.clone()));
+    } else {
+        let npcs = room_npcs(&state, player_room).await;
+        let room = rooms.entry(player_room).or_default();
+
+        let _ = tx.send(Message::Text(room_state_message(room, &npcs)));
 
         let join_msg = serde_json::to_string(&serde_json::json!({
         "type": "user_joined",
@@ -74,12 +106,22 @@ async fn handle_socket(socket: WebSocket, state: AppState, claims: Claims) {
     broadcast_message(room, join_msg, user_id);
     room.insert(user_id, (user_info.clone(), tx.clone()));
 }
+    drop(rooms);
+    // События заданий отправляют сообщения через ту же блокировку, поэтому только после нее
+    if position.respawned {
+        emit_quest_events(&state, user_id, vec![QuestEvent::Visited(position.location_id)]).await;
+    }
 
     while let Some(Ok(msg)) = receiver.next().await {
         match msg {
             Message::Text(text) if text == "__ping__" => {
                 let _ = tx.send(Message::Text("__pong__".to_string()));
             }
+            // Все остальные текстовые сообщения - команды терминала
+            Message::Text(text) => {
+                let reply = commands::execute(&state, &claims, &text).await;
+                let _ = tx.send(Message::Text(reply));
+            }
             Message::Close(_) => break,
             _ => {}
         }
@@ -87,12 +129,39 @@ async fn handle_socket(socket: WebSocket, state: AppState, claims: Claims) {
 
     {
        let mut rooms = state.ws_state.rooms.lock().await;
-    if let Some(room) = rooms.get_mut(&player_location_id) {
+    // За время сессии игрок мог перейти в другую комнату, поэтому ищем его по всем
+    if let Some(room) = rooms.values_mut().find(|room| room.contains_key(&user_id)) {
         room.remove(&user_id);
         // Отправляем только ID и username, т.к. полный профиль уже не нужен
         let leave_msg = format!(r#"{{"type": "user_left", "user_id": "{}", "username": "{}"}}"#, user_id, &user_info.username);
         broadcast_message(room, leave_msg, user_id);
          }
      }
+    state.ws_state.transit.lock().await.remove(&user_id);
+        // Отключившийся игрок не может завершить обмен, поэтому его сделка отменяется
+        if let Err(e) = cancel_player_trade_now(&state, user_id, CANCELLED_DISCONNECTED).await {
+            tracing::error!("Не удалось отменить сделку игрока {}: {:?}", user_id, e);
+        }
+        // Отключившийся не занимает место в локации: оно достается следующему в очереди
+        if let Err(e) = free_slot(&state, user_id).await {
+            tracing::error!("Не удалось освободить место игрока {}: {:?}", user_id, e);
+        }
         tracing::info!("WebSocket client disconnected: {}", &user_info.username);
-}
\ No newline at end of file
+}
+
+/// Раздает место, которое отключившийся игрок держал в локации с ограниченной вместимостью.
+async fn free_slot(state: &AppState, user_id: Uuid) -> Result<(), AppError> {
+    let location = sqlx::query!(
+        r#"
+        SELECT l.id FROM players p JOIN locations l ON l.id = p.current_location_id
+        WHERE p.user_id = $1 AND l.capacity IS NOT NULL
+        "#,
+        user_id
+    )
+    .fetch_optional(&state.pool)
+    .await?;
+    if let Some(location) = location {
+        process_queue(state, location.id).await?;
+    }
+    Ok(())
+}
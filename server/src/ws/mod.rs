@@ -1,6 +1,7 @@
 // /var/www/structure/server/src/ws/mod.rs
 
 // Объявляем наши под-модули
+pub mod commands;
 pub mod handler;
 pub mod state;
 pub mod utils;
@@ -10,5 +11,5 @@ pub mod utils;
 // - Функцию для перемещения для player_handler.
 // - Тип состояния для AppState.
 pub use handler::ws_handler;
-pub use state::WsState;
+pub use state::{RoomKey, WsState};
 pub use utils::change_room;
\ No newline at end of file
@@ -11,16 +11,40 @@ pub type Client = (PublicUser, tokio::sync::mpsc::UnboundedSender<Message>);
 // Комната: user_id -> Client
 pub type Room = HashMap<Uuid, Client>;
 
-// Состояние WebSocket: location_id -> Room
+/// Ключ комнаты. У инстанцированной локации столько комнат, сколько у нее копий,
+/// поэтому одного location_id недостаточно.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RoomKey {
+    pub location_id: Uuid,
+    pub instance_id: Option<Uuid>,
+}
+
+impl RoomKey {
+    pub fn new(location_id: Uuid, instance_id: Option<Uuid>) -> Self {
+        Self { location_id, instance_id }
+    }
+}
+
+// Состояние WebSocket: RoomKey -> Room
 #[derive(Clone)]
 pub struct WsState {
-    pub rooms: Arc<Mutex<HashMap<Uuid, Room>>>,
+    pub rooms: Arc<Mutex<HashMap<RoomKey, Room>>>,
+    // Игроки в пути: вне всех комнат, но на связи для личных сообщений.
+    // Если нужны обе блокировки, сначала берется rooms.
+    pub transit: Arc<Mutex<Room>>,
 }
 
 impl WsState {
     pub fn new() -> Self {
         Self {
             rooms: Arc::new(Mutex::new(HashMap::new())),
+            transit: Arc::new(Mutex::new(HashMap::new())),
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for WsState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
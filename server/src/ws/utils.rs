@@ -1,8 +1,16 @@
 // /var/www/structure/server/src/ws/utils.rs
 
-use super::state::Room;
-use crate::{auth::Claims, models::user::PublicUser, state::AppState};
+use super::state::{Client, Room, RoomKey};
+use crate::{
+    models::{
+        npc::NpcPresence,
+        user::UserRole,
+    },
+    state::AppState,
+    world::npcs::location_npcs,
+};
 use axum::extract::ws::Message;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 /// Отправляет сообщение всем клиентам в комнате, кроме одного (skip_user)
@@ -14,49 +22,126 @@ pub fn broadcast_message(room: &Room, message: String, skip_user: Uuid) {
     }
 }
 
-/// Перемещает клиента из старой комнаты в новую и рассылает уведомления.
+/// NPC комнаты для room_state. Ошибка чтения не мешает входу: комната показывается без них.
+pub async fn room_npcs(state: &AppState, room_id: RoomKey) -> Vec<NpcPresence> {
+    location_npcs(&state.pool, room_id.location_id).await.unwrap_or_else(|e| {
+        tracing::error!("Не удалось загрузить NPC локации {}: {:?}", room_id.location_id, e);
+        Vec::new()
+    })
+}
+
+/// Сообщение room_state: игроки комнаты и NPC с пометкой `npc`.
+pub fn room_state_message(room: &Room, npcs: &[NpcPresence]) -> String {
+    let mut users: Vec<serde_json::Value> = room.values().map(|(user, _)| serde_json::json!(user)).collect();
+    users.extend(npcs.iter().map(|npc| serde_json::json!(npc)));
+    serde_json::json!({ "type": "room_state", "users": users }).to_string()
+}
+
+/// Игроки, подключенные сейчас по WebSocket: в комнатах и в пути.
+pub async fn connected_user_ids(state: &AppState) -> Vec<Uuid> {
+    let rooms = state.ws_state.rooms.lock().await;
+    let transit = state.ws_state.transit.lock().await;
+    rooms.values().flat_map(|room| room.keys()).chain(transit.keys()).copied().collect()
+}
+
+/// Отправляет сообщение всем клиентам в перечисленных локациях (например, во всей зоне),
+/// включая все копии инстанцированных локаций.
+pub async fn broadcast_to_locations(state: &AppState, location_ids: &[Uuid], message: String) {
+    let rooms = state.ws_state.rooms.lock().await;
+    for (key, room) in rooms.iter() {
+        if location_ids.contains(&key.location_id) {
+            broadcast_message(room, message.clone(), Uuid::nil());
+        }
+    }
+}
+
+/// Отправляет личное сообщение игроку, в какой бы комнате он ни находился.
+/// Возвращает `false`, если игрок сейчас не подключен.
+pub async fn send_to_user(state: &AppState, user_id: Uuid, message: String) -> bool {
+    let rooms = state.ws_state.rooms.lock().await;
+    let transit = state.ws_state.transit.lock().await;
+    match rooms.values().chain([&*transit]).find_map(|room| room.get(&user_id)) {
+        Some((_, tx)) => tx.send(Message::Text(message)).is_ok(),
+        None => false,
+    }
+}
+
+/// Отправляет сообщение всем подключенным модераторам и выше.
+pub async fn send_to_staff(state: &AppState, message: String) {
+    let rooms = state.ws_state.rooms.lock().await;
+    let transit = state.ws_state.transit.lock().await;
+    for (user, tx) in rooms.values().chain([&*transit]).flat_map(|room| room.values()) {
+        if user.role >= UserRole::Moderator {
+            let _ = tx.send(Message::Text(message.clone()));
+        }
+    }
+}
+
+/// Забирает клиента из комнаты и оповещает оставшихся о его выходе.
+fn leave_room(
+    rooms: &mut HashMap<RoomKey, Room>,
+    room_id: Option<RoomKey>,
+    user_id: Uuid,
+    username: &str,
+) -> Option<Client> {
+    let room = rooms.get_mut(&room_id?)?;
+    let client_data = room.remove(&user_id)?;
+    let leave_msg = format!(r#"{{"type": "user_left", "user_id": "{}", "username": "{}"}}"#, user_id, username);
+    broadcast_message(room, leave_msg, Uuid::nil());
+    Some(client_data)
+}
+
+/// Выводит клиента из комнаты в путь: он пропадает из комнаты, но остается на связи.
+pub async fn enter_transit(state: &AppState, user_id: Uuid, username: &str, old_room_id: Option<RoomKey>) {
+    let mut rooms = state.ws_state.rooms.lock().await;
+    let mut transit = state.ws_state.transit.lock().await;
+    match leave_room(&mut rooms, old_room_id, user_id, username) {
+        Some(client_data) => {
+            transit.insert(user_id, client_data);
+        }
+        None => tracing::warn!("Не удалось отправить WS-клиента {} в путь: он не найден в комнате.", username),
+    }
+}
+
+/// Перемещает клиента из старой комнаты (или из пути) в новую и рассылает уведомления.
 pub async fn change_room(
     state: &AppState,
-    claims: &Claims,
-    old_room_id: Option<Uuid>,
-    new_room_id: Uuid,
+    user_id: Uuid,
+    username: &str,
+    old_room_id: Option<RoomKey>,
+    new_room_id: RoomKey,
 ) {
+    let npcs = room_npcs(state, new_room_id).await;
     let mut rooms = state.ws_state.rooms.lock().await;
 
-    // 1. Забираем клиента из старой комнаты и оповещаем о выходе
-    let client_tuple = if let Some(old_id) = old_room_id {
-        if let Some(room) = rooms.get_mut(&old_id) {
-            let client_data = room.remove(&claims.sub);
-            let leave_msg = format!(r#"{{"type": "user_left", "username": "{}"}}"#, &claims.username);
-            broadcast_message(room, leave_msg, Uuid::nil());
-            client_data
-        } else {
-            None
-        }
-    } else {
-        None
+    // 1. Забираем клиента из старой комнаты и оповещаем о выходе. Игрок в пути ни в одной комнате не числится.
+    //    Если старая комната неизвестна (локацию удалили и игрока вернули в точку появления), ищем по всем
+    let client_tuple = match leave_room(&mut rooms, old_room_id, user_id, username) {
+        Some(client_data) => Some(client_data),
+        None => match state.ws_state.transit.lock().await.remove(&user_id) {
+            Some(client_data) => Some(client_data),
+            None => {
+                let room_id = rooms.iter().find(|(_, room)| room.contains_key(&user_id)).map(|(key, _)| *key);
+                leave_room(&mut rooms, room_id, user_id, username)
+            }
+        },
     };
 
     // 2. Если мы успешно забрали клиента, вставляем его в новую комнату
     if let Some(client_data) = client_tuple {
         let room = rooms.entry(new_room_id).or_default();
         
-        let current_users: Vec<&PublicUser> = room.values().map(|(user, _)| user).collect();
-        
-        let room_state_msg = serde_json::to_string(&serde_json::json!({
-            "type": "room_state", "users": current_users,
-        })).unwrap_or_default();
-        let _ = client_data.1.send(Message::Text(room_state_msg));
+        let _ = client_data.1.send(Message::Text(room_state_message(room, &npcs)));
 
         let join_msg = serde_json::to_string(&serde_json::json!({
             "type": "user_joined", "user": &client_data.0,
         })).unwrap_or_default();
         broadcast_message(room, join_msg, Uuid::nil());
-        room.insert(claims.sub, client_data);
+        room.insert(user_id, client_data);
     } else {
         tracing::warn!(
             "Не удалось переместить WS-клиента {}, так как он не найден в старой комнате.",
-            &claims.username
+            username
         );
     }
 }
\ No newline at end of file